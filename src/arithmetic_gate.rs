@@ -1,7 +1,6 @@
 // implement arithmetization of the different circuits
 
-use crate::constraint_system::{StandardComposer, Variable};
-use ark_ec::TEModelParameters;
+use crate::constraint_system::Variable;
 use ark_ff::PrimeField;
 
 #[derive(Debug, Clone, Copy)]
@@ -15,6 +14,7 @@ where
     pub(crate) add_selectors: (F, F),
     pub(crate) out_selector: F,
     pub(crate) const_selector: F,
+    pub(crate) high_degree: (F, F, F),
     pub(crate) pi: Option<F>,
 }
 
@@ -30,6 +30,7 @@ where
             add_selectors: (F::zero(), F::zero()),
             out_selector: -F::one(),
             const_selector: F::zero(),
+            high_degree: (F::zero(), F::zero(), F::zero()),
             pi: None,
         }
     }
@@ -78,6 +79,13 @@ where
         self
     }
 
+    /// Adds `q_hl·a^5 + q_hr·b^5 + q_h4·d^5` to the gate, `d` being the
+    /// fan-in-3 wire.
+    pub fn high_degree(&mut self, q_hl: F, q_hr: F, q_h4: F) -> &mut Self {
+        self.high_degree = (q_hl, q_hr, q_h4);
+        self
+    }
+
     pub fn pi(&mut self, pi: F) -> &mut Self {
         self.pi = Some(pi);
         self
@@ -0,0 +1,284 @@
+//! Witness assignments detached from the circuit they satisfy.
+//!
+//! Building a circuit runs every gadget and fills the permutation map, which
+//! dominates the cost of proving small circuits many times. An
+//! [`Assignment`] holds only the variable and public input values, so a
+//! [`Prover`](crate::prover::Prover) can keep its circuit and
+//! [`ProverKey`](crate::proof_system::ProverKey) and prove again once the
+//! new values are assigned.
+use crate::{
+    constraint_system::{CircuitBuilder, GateKind, Variable},
+    error::Error,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+use hashbrown::HashSet;
+use rand_core::{CryptoRng, OsRng, RngCore};
+
+/// Values of the variables and public inputs of a circuit. It can be
+/// written to a witness file with [`CanonicalSerialize`].
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Assignment<F>
+where
+    F: PrimeField,
+{
+    /// Ids of the assigned variables.
+    pub ids: Vec<u64>,
+    /// Value of each variable in `ids`.
+    pub values: Vec<F>,
+    /// Gate indices holding public inputs.
+    pub pi_positions: Vec<u64>,
+    /// Value of the public input at each position in `pi_positions`.
+    pub pi_values: Vec<F>,
+}
+
+impl<F> FromIterator<(Variable, F)> for Assignment<F>
+where
+    F: PrimeField,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (Variable, F)>,
+    {
+        let (ids, values) = iter
+            .into_iter()
+            .map(|(var, value)| (var.0 as u64, value))
+            .unzip();
+        Self {
+            ids,
+            values,
+            ..Default::default()
+        }
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Variables whose values make up the witness: every variable placed on
+    /// a gate other than the dummy and blinding rows, except the zero
+    /// variable. The values of the others are fixed by the circuit itself.
+    fn witness_variables(&self) -> HashSet<Variable> {
+        (0..self.n)
+            .filter(|i| {
+                !matches!(
+                    self.gate_kinds[*i],
+                    GateKind::Dummy | GateKind::Blinding
+                )
+            })
+            .flat_map(|i| [self.w_l[i], self.w_r[i], self.w_o[i], self.w_4[i]])
+            .filter(|var| *var != self.zero_var)
+            .collect()
+    }
+
+    /// Returns the current values of the witness variables and public
+    /// inputs.
+    pub fn assignment(&self) -> Assignment<F> {
+        let mut vars = self.witness_variables().into_iter().collect::<Vec<_>>();
+        vars.sort_by_key(|var| var.0);
+        let mut assignment: Assignment<F> = vars
+            .into_iter()
+            .filter_map(|var| self.value(var).map(|value| (var, value)))
+            .collect();
+        assignment.pi_positions = self
+            .public_inputs
+            .get_pos()
+            .map(|pos| *pos as u64)
+            .collect();
+        assignment.pi_values = self.public_inputs.get_vals().copied().collect();
+        assignment
+    }
+
+    /// Removes the values of the witness variables, keeping the gates, the
+    /// permutation and the values fixed by the circuit.
+    pub fn clear_witness(&mut self) {
+        for var in self.witness_variables() {
+            self.variables.remove(&var);
+        }
+    }
+
+    /// Samples new values for the variables of the blinding rows, whose
+    /// selectors are all zero, so that two proofs of the circuit do not
+    /// share their blinding values.
+    pub(crate) fn resample_blinding<R>(&mut self, rng: &mut R)
+    where
+        R: CryptoRng + RngCore + ?Sized,
+    {
+        let blinding = (0..self.n)
+            .filter(|i| self.gate_kinds[*i] == GateKind::Blinding)
+            .flat_map(|i| [self.w_l[i], self.w_r[i], self.w_o[i], self.w_4[i]])
+            .filter(|var| *var != self.zero_var)
+            .collect::<HashSet<_>>();
+        for var in blinding {
+            self.variables.insert(var, F::rand(rng));
+        }
+    }
+
+    /// Replaces the witness and the public input values with `assignment`,
+    /// which must give a value to every witness variable and public input
+    /// position of this circuit and to nothing else. The blinding values
+    /// are sampled again.
+    ///
+    /// On error the witness is left cleared and the public inputs are left
+    /// unchanged.
+    pub fn assign(&mut self, assignment: &Assignment<F>) -> Result<(), Error> {
+        let witness = self.witness_variables();
+        let same_positions = assignment.pi_positions.len()
+            == self.public_inputs.len()
+            && assignment
+                .pi_positions
+                .iter()
+                .map(|pos| *pos as usize)
+                .collect::<HashSet<_>>()
+                == self.public_inputs.get_pos().copied().collect();
+        if assignment.ids.len() != assignment.values.len()
+            || assignment.pi_values.len() != assignment.pi_positions.len()
+            || !same_positions
+        {
+            return Err(Error::AssignmentMismatch);
+        }
+
+        self.clear_witness();
+        for (id, value) in assignment.ids.iter().zip(assignment.values.iter()) {
+            let var = Variable(*id as usize);
+            if !witness.contains(&var) {
+                self.clear_witness();
+                return Err(Error::AssignmentMismatch);
+            }
+            self.variables.insert(var, *value);
+        }
+
+        if let Some(var) =
+            witness.iter().find(|var| !self.variables.contains_key(var))
+        {
+            let var = *var;
+            self.clear_witness();
+            return Err(Error::MissingWitness(var));
+        }
+
+        for (pos, value) in
+            assignment.pi_positions.iter().zip(&assignment.pi_values)
+        {
+            self.public_inputs.update(*pos as usize, *value)?;
+        }
+        self.resample_blinding(&mut OsRng);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment::KZG10, error::Error, prover::Prover, verifier::Verifier,
+    };
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_poly_commit::PolynomialCommitment;
+
+    type PC = KZG10<Bls12_381>;
+
+    /// `a * b = c` with `c` public.
+    fn mul_circuit(
+        cs: &mut CircuitBuilder<Fr, EdwardsParameters>,
+        a: u64,
+        b: u64,
+    ) {
+        let a = cs.add_input(Fr::from(a));
+        let b = cs.add_input(Fr::from(b));
+        let c = cs.arithmetic_gate(|gate| {
+            gate.witness(a, b, None).mul(Fr::from(1u64))
+        });
+        let value = cs.value(c).unwrap();
+        cs.constrain_to_constant(c, Fr::from(0u64), Some(-value));
+    }
+
+    fn assignment(a: u64, b: u64) -> Assignment<Fr> {
+        let mut cs = CircuitBuilder::new();
+        mul_circuit(&mut cs, a, b);
+        cs.assignment()
+    }
+
+    fn blinding_values(
+        cs: &CircuitBuilder<Fr, EdwardsParameters>,
+    ) -> Vec<Option<Fr>> {
+        (0..cs.n)
+            .filter(|i| cs.gate_kinds[*i] == GateKind::Blinding)
+            .map(|i| cs.value(cs.w_l[i]))
+            .collect()
+    }
+
+    #[test]
+    fn two_assignments_are_proven_with_one_preprocessing() {
+        let rng = &mut ark_std::test_rng();
+
+        let mut prover = Prover::<Fr, EdwardsParameters, PC>::new(b"mul");
+        mul_circuit(prover.mut_cs(), 3, 5);
+        let n = prover.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+        let first = prover.prove(&ck).unwrap();
+        let first_pi = prover.mut_cs().get_pi().clone();
+        let prover_key = prover.prover_key.clone();
+        let blinding = blinding_values(prover.mut_cs());
+
+        let mut verifier = Verifier::<Fr, EdwardsParameters, PC>::new(b"mul");
+        mul_circuit(verifier.mut_cs(), 0, 0);
+        verifier.preprocess(&ck).unwrap();
+        assert_eq!(verifier.verify(&first, &vk, &first_pi), Ok(()));
+
+        // The witness is cleared by the proof, the circuit is kept.
+        assert!(matches!(prover.prove(&ck), Err(Error::MissingWitness(_))));
+
+        prover.assign_witness(&assignment(4, 6)).unwrap();
+        assert_ne!(blinding_values(prover.mut_cs()), blinding);
+        let second = prover.prove(&ck).unwrap();
+        let second_pi = prover.mut_cs().get_pi().clone();
+        assert_eq!(prover.prover_key, prover_key);
+        assert_ne!(second_pi, first_pi);
+
+        assert_eq!(verifier.verify(&second, &vk, &second_pi), Ok(()));
+        assert_eq!(
+            verifier.verify(&second, &vk, &first_pi),
+            Err(Error::ProofVerificationError)
+        );
+    }
+
+    #[test]
+    fn mismatching_assignments_are_rejected() {
+        let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+        mul_circuit(&mut cs, 3, 5);
+        let good = assignment(4, 6);
+
+        let mut bytes = Vec::new();
+        good.serialize(&mut bytes).unwrap();
+        assert_eq!(Assignment::deserialize(&bytes[..]).unwrap(), good);
+
+        let mut extra = good.clone();
+        extra.ids.push(cs.n as u64 * 4);
+        extra.values.push(Fr::from(1u64));
+        assert_eq!(cs.assign(&extra), Err(Error::AssignmentMismatch));
+
+        let mut missing = good.clone();
+        let id = missing.ids.pop().unwrap();
+        missing.values.pop();
+        assert_eq!(
+            cs.assign(&missing),
+            Err(Error::MissingWitness(Variable(id as usize)))
+        );
+
+        let mut moved = good.clone();
+        moved.pi_positions[0] += 1;
+        assert_eq!(cs.assign(&moved), Err(Error::AssignmentMismatch));
+
+        assert_eq!(cs.assign(&good), Ok(()));
+        assert_eq!(cs.check_satisfied(), Ok(()));
+        assert_eq!(cs.assignment(), good);
+    }
+}
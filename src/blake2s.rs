@@ -0,0 +1,219 @@
+//! BLAKE2s-256 over byte variables, built on lookup tables.
+//!
+//! Words are 32-bit words of 4-bit limbs, see [`crate::limbs`], and bytes
+//! enter and leave them little-endian. Of the G rotations, 16, 12 and 8 bits
+//! only reorder limbs, only the rotation by 7 goes through the split table.
+//! The parameter block, the counters and the finalization flag are
+//! constants, so the initial chaining value and the last eight words of the
+//! working vector cost nothing.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    limbs::LimbTables,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+
+/// Initialization vector.
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+    0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Message word permutations of the ten rounds.
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Working vector words mixed by the G calls of a round, columns first and
+/// then diagonals.
+const G_POSITIONS: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+/// A 32-bit word as eight 4-bit limbs, least significant first.
+type Word = [Variable; 8];
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Constrains the unkeyed BLAKE2s-256 digest of `bytes` with the given
+    /// personalization, as computed by the `blake2` crate with an empty key
+    /// and salt. Each input is range checked to a byte, and each output is a
+    /// byte of the digest.
+    pub fn blake2s(
+        &mut self,
+        bytes: &[Variable],
+        personalization: &[u8; 8],
+    ) -> [Variable; 32] {
+        let mut tables = self.limb_tables();
+
+        // parameter block: 32 byte digest, no key, fanout and depth of 1
+        let mut h = IV;
+        h[0] ^= 0x0101_0020;
+        h[6] ^= u32::from_le_bytes([
+            personalization[0],
+            personalization[1],
+            personalization[2],
+            personalization[3],
+        ]);
+        h[7] ^= u32::from_le_bytes([
+            personalization[4],
+            personalization[5],
+            personalization[6],
+            personalization[7],
+        ]);
+        let mut state = h.map(|h| self.word_constant(&mut tables, h as u64));
+
+        let mut message = bytes
+            .iter()
+            .map(|byte| self.byte_limbs(&tables, *byte))
+            .collect::<Vec<_>>();
+        let blocks = bytes.len().div_ceil(64);
+        message.resize(64 * blocks.max(1), [self.zero_var; 2]);
+        for (i, block) in message.chunks(64).enumerate() {
+            let last = i + 1 == blocks.max(1);
+            let counter = match last {
+                true => bytes.len(),
+                false => 64 * (i + 1),
+            } as u64;
+            state = self.blake2s_compress(
+                &mut tables,
+                &state,
+                block,
+                counter,
+                last,
+            );
+        }
+
+        let mut digest = [self.zero_var; 32];
+        for (i, word) in state.iter().enumerate() {
+            for j in 0..4 {
+                digest[4 * i + j] =
+                    self.limbs_byte(word[2 * j], word[2 * j + 1]);
+            }
+        }
+        digest
+    }
+
+    /// Compresses one 64-byte block of `(low, high)` limbs into `h`, the
+    /// counter being the number of bytes hashed so far.
+    fn blake2s_compress(
+        &mut self,
+        tables: &mut LimbTables,
+        h: &[Word; 8],
+        block: &[[Variable; 2]],
+        counter: u64,
+        last: bool,
+    ) -> [Word; 8] {
+        let m = block
+            .chunks(4)
+            .map(|bytes| {
+                let mut word = [self.zero_var; 8];
+                for (j, byte) in bytes.iter().enumerate() {
+                    word[2 * j] = byte[0];
+                    word[2 * j + 1] = byte[1];
+                }
+                word
+            })
+            .collect::<Vec<_>>();
+
+        let mut iv = IV;
+        iv[4] ^= counter as u32;
+        iv[5] ^= (counter >> 32) as u32;
+        if last {
+            iv[6] = !iv[6];
+        }
+        let mut v = [[self.zero_var; 8]; 16];
+        v[..8].copy_from_slice(h);
+        for (i, word) in iv.iter().enumerate() {
+            v[8 + i] = self.word_constant(tables, *word as u64);
+        }
+
+        for s in SIGMA {
+            for (i, positions) in G_POSITIONS.iter().enumerate() {
+                let (x, y) = (&m[s[2 * i]], &m[s[2 * i + 1]]);
+                self.blake2s_g(tables, &mut v, *positions, x, y);
+            }
+        }
+
+        let mut next = *h;
+        for (i, word) in next.iter_mut().enumerate() {
+            let low = self.word_xor(tables, word, &v[i]);
+            *word = self.word_xor(tables, &low, &v[i + 8]);
+        }
+        next
+    }
+
+    /// The G mixing function on `v[a], v[b], v[c], v[d]`.
+    fn blake2s_g(
+        &mut self,
+        tables: &mut LimbTables,
+        v: &mut [Word; 16],
+        [a, b, c, d]: [usize; 4],
+        x: &Word,
+        y: &Word,
+    ) {
+        for (m, (r_d, r_b)) in [(x, (16, 12)), (y, (8, 7))] {
+            v[a] = self.word_add(tables, &[&v[a], &v[b], m], 0);
+            let d_xor_a = self.word_xor(tables, &v[d], &v[a]);
+            v[d] = self.word_rotations(tables, &d_xor_a, &[(r_d, false)])[0];
+            v[c] = self.word_add(tables, &[&v[c], &v[d]], 0);
+            let b_xor_c = self.word_xor(tables, &v[b], &v[c]);
+            v[b] = self.word_rotations(tables, &b_xor_c, &[(r_b, false)])[0];
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::limbs::to_u64;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::rand::RngCore;
+    use blake2::{Blake2s, Digest};
+
+    #[test]
+    fn digest_matches_blake2() {
+        let rng = &mut ark_std::test_rng();
+        for (len, personalization) in
+            [(0, [0; 8]), (64, [0; 8]), (65, [0; 8]), (65, *b"Zcash_PH")]
+        {
+            let mut bytes = vec![0u8; len];
+            rng.fill_bytes(&mut bytes);
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let vars = bytes
+                .iter()
+                .map(|byte| cs.add_input(Fr::from(*byte as u64)))
+                .collect::<Vec<_>>();
+            let digest = cs
+                .blake2s(&vars, &personalization)
+                .iter()
+                .map(|byte| to_u64(cs.value(*byte).unwrap()) as u8)
+                .collect::<Vec<_>>();
+
+            let mut hasher = Blake2s::with_params(&[], &[], &personalization);
+            hasher.update(&bytes);
+            assert_eq!(digest, hasher.finalize().to_vec(), "{}", len);
+            assert_eq!(cs.check_satisfied(), Ok(()), "{}", len);
+        }
+    }
+}
@@ -0,0 +1,508 @@
+//! Import of circom circuits: the `.r1cs` constraint file and the `.wtns`
+//! witness file produced by `snarkjs` / the circom witness generators.
+//!
+//! Every R1CS constraint `A * B = C` is translated into width-4 arithmetic
+//! gates. Linear combinations are folded three terms at a time, so an
+//! intermediate variable is only introduced when a combination does not fit
+//! on the wires of a single gate.
+use crate::constraint_system::{CircuitBuilder, Variable};
+use ark_ec::TEModelParameters;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use std::io::{self, Read};
+
+/// A linear combination of circom wires: `(wire, coefficient)` pairs. Wire
+/// 0 is the constant one.
+pub type LinearCombination<F> = Vec<(usize, F)>;
+
+/// Constraint system read from a `.r1cs` file.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct R1cs<F>
+where
+    F: PrimeField,
+{
+    /// Number of wires, including the constant wire 0.
+    pub num_wires: usize,
+    /// Number of public outputs, wires `1..=num_pub_out`.
+    pub num_pub_out: usize,
+    /// Number of public inputs, following the public outputs.
+    pub num_pub_in: usize,
+    /// Number of private inputs.
+    pub num_prv_in: usize,
+    /// `(A, B, C)` of every constraint `A * B = C`.
+    pub constraints: Vec<(
+        LinearCombination<F>,
+        LinearCombination<F>,
+        LinearCombination<F>,
+    )>,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Reads a little-endian field element of `n8` bytes, rejecting values not
+/// reduced modulo the field characteristic.
+fn read_field<F: PrimeField, R: Read>(r: &mut R, n8: usize) -> io::Result<F> {
+    let mut buf = vec![0u8; n8];
+    r.read_exact(&mut buf)?;
+    let value = F::from_le_bytes_mod_order(&buf);
+    let mut canonical = value.into_repr().to_bytes_le();
+    canonical.resize(n8, 0);
+    if canonical != buf {
+        return Err(invalid("field element is not reduced"));
+    }
+    Ok(value)
+}
+
+/// Reads the `n8` and prime of a header and checks they match `F`.
+fn read_prime<F: PrimeField, R: Read>(r: &mut R) -> io::Result<usize> {
+    let n8 = read_u32(r)? as usize;
+    let mut prime = vec![0u8; n8];
+    r.read_exact(&mut prime)?;
+    let mut modulus = F::Params::MODULUS.to_bytes_le();
+    modulus.resize(n8, 0);
+    if prime != modulus {
+        return Err(invalid("file prime does not match the scalar field"));
+    }
+    Ok(n8)
+}
+
+/// Reads the magic and version of a circom binary file and returns its
+/// sections as `(type, content)`.
+fn read_sections<R: Read>(
+    r: &mut R,
+    magic: &[u8; 4],
+) -> io::Result<Vec<(u32, Vec<u8>)>> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    if &buf != magic {
+        return Err(invalid("unexpected magic"));
+    }
+    let _version = read_u32(r)?;
+    let num_sections = read_u32(r)?;
+    (0..num_sections)
+        .map(|_| {
+            let kind = read_u32(r)?;
+            let size = read_u64(r)?;
+            // The size comes from the file, so the content is read as it
+            // arrives instead of being allocated up front.
+            let mut content = Vec::new();
+            r.by_ref().take(size).read_to_end(&mut content)?;
+            if content.len() as u64 != size {
+                return Err(invalid("truncated section"));
+            }
+            Ok((kind, content))
+        })
+        .collect()
+}
+
+fn section(sections: &[(u32, Vec<u8>)], kind: u32) -> io::Result<&[u8]> {
+    sections
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, content)| content.as_slice())
+        .ok_or_else(|| invalid("missing section"))
+}
+
+impl<F> R1cs<F>
+where
+    F: PrimeField,
+{
+    /// Parses a `.r1cs` file.
+    pub fn load<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let sections = read_sections(reader, b"r1cs")?;
+
+        let mut header = section(&sections, 1)?;
+        let n8 = read_prime::<F, _>(&mut header)?;
+        let num_wires = read_u32(&mut header)? as usize;
+        let num_pub_out = read_u32(&mut header)? as usize;
+        let num_pub_in = read_u32(&mut header)? as usize;
+        let num_prv_in = read_u32(&mut header)? as usize;
+        let _num_labels = read_u64(&mut header)?;
+        let num_constraints = read_u32(&mut header)? as usize;
+
+        let mut body = section(&sections, 2)?;
+        let read_lc = |body: &mut &[u8]| -> io::Result<LinearCombination<F>> {
+            let nnz = read_u32(body)? as usize;
+            (0..nnz)
+                .map(|_| {
+                    let wire = read_u32(body)? as usize;
+                    if wire >= num_wires {
+                        return Err(invalid("wire out of range"));
+                    }
+                    Ok((wire, read_field::<F, _>(body, n8)?))
+                })
+                .collect()
+        };
+        let constraints = (0..num_constraints)
+            .map(|_| {
+                Ok((
+                    read_lc(&mut body)?,
+                    read_lc(&mut body)?,
+                    read_lc(&mut body)?,
+                ))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            num_wires,
+            num_pub_out,
+            num_pub_in,
+            num_prv_in,
+            constraints,
+        })
+    }
+
+    /// Number of public wires, outputs first then inputs.
+    pub fn num_public(&self) -> usize {
+        self.num_pub_out + self.num_pub_in
+    }
+}
+
+/// Parses a `.wtns` file into the values of every wire, wire 0 included.
+pub fn load_wtns<F, R>(reader: &mut R) -> io::Result<Vec<F>>
+where
+    F: PrimeField,
+    R: Read,
+{
+    let sections = read_sections(reader, b"wtns")?;
+    let mut header = section(&sections, 1)?;
+    let n8 = read_prime::<F, _>(&mut header)?;
+    let num_witness = read_u32(&mut header)? as usize;
+
+    let mut body = section(&sections, 2)?;
+    let witness = (0..num_witness)
+        .map(|_| read_field::<F, _>(&mut body, n8))
+        .collect::<io::Result<Vec<_>>>()?;
+    if witness.first() != Some(&F::one()) {
+        return Err(invalid("wire 0 must be one"));
+    }
+    Ok(witness)
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Adds the constraints of a circom circuit and returns the variable
+    /// of every wire, index 0 being unused as constants go in `q_c`.
+    ///
+    /// Public wires are exposed as public inputs in their circom order.
+    /// `witness` holds the value of every wire, as read by [`load_wtns`], or
+    /// is `None` to synthesize the circuit for key generation only.
+    pub fn add_r1cs(
+        &mut self,
+        r1cs: &R1cs<F>,
+        witness: Option<&[F]>,
+    ) -> io::Result<Vec<Variable>> {
+        let witness = match witness {
+            Some(witness) if witness.len() != r1cs.num_wires => {
+                return Err(invalid(
+                    "witness does not match the number of wires",
+                ));
+            }
+            Some(witness) => witness.iter().map(|value| Some(*value)).collect(),
+            None => vec![None; r1cs.num_wires],
+        };
+
+        let zero = self.zero_var();
+        let wires = std::iter::once(zero)
+            .chain(witness.iter().skip(1).map(|value| self.add_witness(*value)))
+            .collect::<Vec<_>>();
+
+        // -w + PI = 0 for every public wire. Without a witness only the PI
+        // positions matter, their values are given when proving.
+        for wire in 1..=r1cs.num_public() {
+            let (var, value) = (wires[wire], witness[wire].unwrap_or_default());
+            self.arithmetic_gate(|gate| {
+                gate.witness(var, zero, Some(zero))
+                    .add(-F::one(), F::zero())
+                    .out(F::zero())
+                    .pi(value)
+            });
+        }
+
+        for (a, b, c) in r1cs.constraints.iter() {
+            self.add_r1cs_constraint(&wires, a, b, c);
+        }
+        Ok(wires)
+    }
+
+    /// Translates `A * B = C`.
+    fn add_r1cs_constraint(
+        &mut self,
+        wires: &[Variable],
+        a: &LinearCombination<F>,
+        b: &LinearCombination<F>,
+        c: &LinearCombination<F>,
+    ) {
+        let zero = self.zero_var();
+        let (a_terms, a_const) = split_constant(wires, a);
+        let (b_terms, b_const) = split_constant(wires, b);
+        let (mut c_terms, c_const) = split_constant(wires, c);
+
+        // A or B constant: the constraint is linear, k * B - C = 0.
+        if a_terms.is_empty() || b_terms.is_empty() {
+            let (k, lc, k_const) = match a_terms.is_empty() {
+                true => (a_const, b_terms, b_const),
+                false => (b_const, a_terms, a_const),
+            };
+            let mut terms = lc
+                .into_iter()
+                .map(|(var, coeff)| (var, coeff * k))
+                .collect::<Vec<_>>();
+            terms.extend(c_terms.into_iter().map(|(var, coeff)| (var, -coeff)));
+            self.constrain_linear(terms, k * k_const - c_const);
+            return;
+        }
+
+        let a_var = self.fold_linear(a_terms, a_const);
+        let b_var = self.fold_linear(b_terms, b_const);
+
+        // a * b - c_1 * w_o - c_2 * w_4 - c_const = 0, with any remaining
+        // terms of C folded into one variable first.
+        if c_terms.len() > 2 {
+            let rest = c_terms.split_off(1);
+            let folded = self.fold_linear(rest, F::zero());
+            c_terms.push((folded, F::one()));
+        }
+        let mut c_terms = c_terms.into_iter();
+        let (c_1, k_1) = c_terms.next().unwrap_or((zero, F::zero()));
+        let (c_2, k_2) = c_terms.next().unwrap_or((zero, F::zero()));
+        self.arithmetic_gate(|gate| {
+            gate.witness(a_var, b_var, Some(c_1))
+                .mul(F::one())
+                .out(-k_1)
+                .fan_in_3(-k_2, c_2)
+                .constant(-c_const)
+        });
+    }
+}
+
+/// Maps a circom combination to variables, merging repeated wires and
+/// pulling out the coefficient of the constant wire.
+fn split_constant<F: PrimeField>(
+    wires: &[Variable],
+    lc: &LinearCombination<F>,
+) -> (Vec<(Variable, F)>, F) {
+    let mut constant = F::zero();
+    let mut terms: Vec<(Variable, F)> = Vec::with_capacity(lc.len());
+    for (wire, coeff) in lc.iter() {
+        if *wire == 0 {
+            constant += coeff;
+        } else if let Some(term) =
+            terms.iter_mut().find(|(var, _)| *var == wires[*wire])
+        {
+            term.1 += coeff;
+        } else {
+            terms.push((wires[*wire], *coeff));
+        }
+    }
+    terms.retain(|(_, coeff)| !coeff.is_zero());
+    (terms, constant)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment::KZG10, error::Error, prover::Prover, verifier::Verifier,
+    };
+    use ark_bn254::{Bn254, Fr};
+    use ark_ed_on_bn254::EdwardsParameters;
+    use ark_poly_commit::PolynomialCommitment;
+
+    type PC = KZG10<Bn254>;
+
+    /// Encodes `value` on 32 little-endian bytes, as circom does for BN254.
+    fn field_bytes(value: Fr) -> Vec<u8> {
+        let mut bytes = value.into_repr().to_bytes_le();
+        bytes.resize(32, 0);
+        bytes
+    }
+
+    fn modulus() -> Vec<u8> {
+        let mut bytes = <Fr as PrimeField>::Params::MODULUS.to_bytes_le();
+        bytes.resize(32, 0);
+        bytes
+    }
+
+    /// Writes a circom binary file from its sections.
+    fn file(
+        magic: &[u8; 4],
+        version: u32,
+        sections: &[(u32, Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut out = magic.to_vec();
+        out.extend(version.to_le_bytes());
+        out.extend((sections.len() as u32).to_le_bytes());
+        for (kind, content) in sections {
+            out.extend(kind.to_le_bytes());
+            out.extend((content.len() as u64).to_le_bytes());
+            out.extend(content);
+        }
+        out
+    }
+
+    fn lc(terms: &[(u32, u64)]) -> Vec<u8> {
+        let mut out = (terms.len() as u32).to_le_bytes().to_vec();
+        for (wire, coeff) in terms {
+            out.extend(wire.to_le_bytes());
+            out.extend(field_bytes(Fr::from(*coeff)));
+        }
+        out
+    }
+
+    /// Wires `[1, out, a, b, t]`, `out` public:
+    ///
+    /// - `a * b = out`
+    /// - `1 * (a + 2b + 3) = t`
+    /// - `(a + b) * (t + 1) = out + a + b + t + 97`
+    fn r1cs_fixture() -> Vec<u8> {
+        let mut header = modulus();
+        header.splice(0..0, 32u32.to_le_bytes());
+        for count in [5u32, 1, 0, 2] {
+            header.extend(count.to_le_bytes());
+        }
+        header.extend(5u64.to_le_bytes());
+        header.extend(3u32.to_le_bytes());
+
+        let constraints = [
+            lc(&[(2, 1)]),
+            lc(&[(3, 1)]),
+            lc(&[(1, 1)]),
+            lc(&[(0, 1)]),
+            lc(&[(2, 1), (3, 2), (0, 3)]),
+            lc(&[(4, 1)]),
+            lc(&[(2, 1), (3, 1)]),
+            lc(&[(4, 1), (0, 1)]),
+            lc(&[(1, 1), (2, 1), (3, 1), (4, 1), (0, 97)]),
+        ]
+        .concat();
+        file(b"r1cs", 1, &[(1, header), (2, constraints)])
+    }
+
+    fn wtns_fixture(values: &[u64]) -> Vec<u8> {
+        let mut header = 32u32.to_le_bytes().to_vec();
+        header.extend(modulus());
+        header.extend((values.len() as u32).to_le_bytes());
+        let body = values
+            .iter()
+            .flat_map(|value| field_bytes(Fr::from(*value)))
+            .collect();
+        file(b"wtns", 2, &[(1, header), (2, body)])
+    }
+
+    fn circuit(witness: &[u64]) -> CircuitBuilder<Fr, EdwardsParameters> {
+        let r1cs = R1cs::<Fr>::load(&mut &r1cs_fixture()[..]).unwrap();
+        let witness =
+            load_wtns::<Fr, _>(&mut &wtns_fixture(witness)[..]).unwrap();
+        let mut cs = CircuitBuilder::new();
+        cs.add_r1cs(&r1cs, Some(&witness)).unwrap();
+        cs
+    }
+
+    #[test]
+    fn r1cs_fixture_is_parsed() {
+        let r1cs = R1cs::<Fr>::load(&mut &r1cs_fixture()[..]).unwrap();
+        assert_eq!(r1cs.num_wires, 5);
+        assert_eq!(r1cs.num_public(), 1);
+        assert_eq!(r1cs.num_prv_in, 2);
+        assert_eq!(r1cs.constraints.len(), 3);
+        assert_eq!(
+            r1cs.constraints[1].1,
+            vec![
+                (2, Fr::from(1u64)),
+                (3, Fr::from(2u64)),
+                (0, Fr::from(3u64))
+            ]
+        );
+    }
+
+    #[test]
+    fn imported_circuit_accepts_its_witness_only() {
+        let cs = circuit(&[1, 15, 3, 5, 16]);
+        assert_eq!(cs.check_satisfied(), Ok(()));
+        assert_eq!(
+            cs.get_pi().get_vals().copied().collect::<Vec<_>>(),
+            vec![Fr::from(15u64)]
+        );
+
+        assert!(circuit(&[1, 15, 3, 5, 17]).check_satisfied().is_err());
+        assert!(circuit(&[1, 16, 3, 5, 16]).check_satisfied().is_err());
+    }
+
+    /// Proves the fixture with `out = 15` and verifies the proof with the
+    /// keys of the witness-free circuit and `out_public` as public input.
+    fn prove_and_verify(out_public: u64) -> Result<(), Error> {
+        let rng = &mut ark_std::test_rng();
+
+        let mut prover = Prover::<Fr, EdwardsParameters, PC>::new(b"circom");
+        *prover.mut_cs() = circuit(&[1, 15, 3, 5, 16]);
+        let n = prover.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+        let proof = prover.prove(&ck)?;
+
+        let r1cs = R1cs::<Fr>::load(&mut &r1cs_fixture()[..]).unwrap();
+        let mut verifier =
+            Verifier::<Fr, EdwardsParameters, PC>::new(b"circom");
+        verifier.mut_cs().add_r1cs(&r1cs, None).unwrap();
+        let mut pi = verifier.mut_cs().get_pi().clone();
+        let pos = *pi.get_pos().next().unwrap();
+        pi.update(pos, Fr::from(out_public))?;
+        verifier.preprocess(&ck)?;
+        verifier.verify(&proof, &vk, &pi)
+    }
+
+    #[test]
+    fn imported_circuit_proof_verifies() {
+        assert_eq!(prove_and_verify(15), Ok(()));
+        assert_eq!(prove_and_verify(16), Err(Error::ProofVerificationError));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let r1cs = r1cs_fixture();
+
+        // A section claiming more bytes than the file holds.
+        let truncated = &r1cs[..r1cs.len() - 1];
+        assert!(R1cs::<Fr>::load(&mut &truncated[..]).is_err());
+
+        let mut huge = r1cs.clone();
+        huge[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(R1cs::<Fr>::load(&mut &huge[..]).is_err());
+
+        let mut magic = r1cs.clone();
+        magic[0] = b'x';
+        assert!(R1cs::<Fr>::load(&mut &magic[..]).is_err());
+
+        // The first coefficient, `1`, replaced by the modulus.
+        let mut unreduced = r1cs.clone();
+        let coeff = 12 + 12 + 64 + 12 + 4 + 4;
+        unreduced[coeff..coeff + 32].copy_from_slice(&modulus());
+        assert!(R1cs::<Fr>::load(&mut &unreduced[..]).is_err());
+
+        assert!(R1cs::<ark_bls12_381::Fr>::load(&mut &r1cs[..]).is_err());
+
+        let wtns = wtns_fixture(&[2, 15, 3, 5, 16]);
+        assert!(load_wtns::<Fr, _>(&mut &wtns[..]).is_err());
+
+        let r1cs = R1cs::<Fr>::load(&mut &r1cs[..]).unwrap();
+        let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+        assert!(cs.add_r1cs(&r1cs, Some(&[Fr::from(1u64)])).is_err());
+    }
+}
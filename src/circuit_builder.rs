@@ -1,9 +1,29 @@
+use crate::{
+    constraint_system::{ArithmeticGate, Variable},
+    error::Error,
+    lookup_table::LookupTable,
+    permutation::Permutation,
+    proof_system::pi::PublicInputs,
+};
 use ark_ec::{models::TEModelParameters, ModelParameters};
 use ark_ff::{PrimeField, ToConstraintField};
-use crate::permutation::Permutation;
-use rand_core::{CryptoRng, RngCore};
-
-
+use core::marker::PhantomData;
+use hashbrown::HashMap;
+use rand_core::{CryptoRng, OsRng, RngCore};
+
+/// Kind of a gate, as recorded when the gate is added to the circuit.
+#[derive(
+    Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, serde::Serialize,
+)]
+pub enum GateKind {
+    Arithmetic,
+    Lookup,
+    Range,
+    Logic,
+    Curve,
+    Dummy,
+    Blinding,
+}
 
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
@@ -16,7 +36,6 @@ where
     pub(crate) n: usize,
 
     // Selector vectors
-   
     pub(crate) q_m: Vec<F>,
     pub(crate) q_l: Vec<F>,
     pub(crate) q_r: Vec<F>,
@@ -24,76 +43,153 @@ where
     pub(crate) q_4: Vec<F>,
     pub(crate) q_c: Vec<F>,
 
+    // High degree selectors: q_hl·a^5 + q_hr·b^5 + q_h4·d^5
+    pub(crate) q_hl: Vec<F>,
+    pub(crate) q_hr: Vec<F>,
+    pub(crate) q_h4: Vec<F>,
+
+    // Enables the arithmetic equation on a row: 1 on arithmetic and dummy
+    // rows, 0 on every other row.
+    pub(crate) q_arith: Vec<F>,
+
+    // Enables the lookup query on a row.
+    pub(crate) q_lookup: Vec<F>,
+
     // Witness vectors
- 
     pub(crate) w_l: Vec<Variable>,
     pub(crate) w_r: Vec<Variable>,
     pub(crate) w_o: Vec<Variable>,
+    pub(crate) w_4: Vec<Variable>,
 
     // Public lookup table
     pub(crate) lookup_table: LookupTable<F>,
 
-
     // Permutation argument.
     pub(crate) perm: Permutation,
 
+    // Kind of each gate, used for circuit statistics
+    pub(crate) gate_kinds: Vec<GateKind>,
+
     // public inputs
     pub(crate) public_inputs: PublicInputs<F>,
 
+    // Gate positions holding a public input
+    pub(crate) intended_pi_pos: Vec<usize>,
+
+    // Values of the variables, missing during witness-free synthesis
+    pub(crate) variables: HashMap<Variable, F>,
 
     // zero var
     pub(crate) zero_var: Variable,
 
+    // Regions closed so far, innermost first: (path, first gate, end gate)
+    pub(crate) regions: Vec<(String, usize, usize)>,
+
+    // Path of the namespaces currently open
+    pub(crate) namespace_path: Vec<String>,
+
+    // Optional names given to variables, prefixed with their namespace
+    pub(crate) variable_names: HashMap<Variable, String>,
 
     // Type Parameter Marker
     __: PhantomData<P>,
 }
 
-impl<F,P> CircuitBuilder<F,P> 
+impl<F, P> Default for CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
 where
     F: PrimeField,
-    P: TEModelParameters<BaseField:F>
+    P: TEModelParameters<BaseField = F>,
 {
+    // Creates a new circuit with an expected circuit size of zero.
+    pub fn new() -> Self {
+        Self::with_expected_size(0)
+    }
 
-    // Creates a new circuit with an expected circuit size.
-    pub fn new(circuit_size: usize) -> Self {
+    // Creates a new circuit with an expected circuit size. The zero
+    // variable, the dummy constraints and lookup rows, and the blinding rows
+    // are added right away.
+    pub fn with_expected_size(circuit_size: usize) -> Self {
         let mut builder = Self {
             n: 0,
             q_m: Vec::with_capacity(circuit_size),
             q_l: Vec::with_capacity(circuit_size),
             q_r: Vec::with_capacity(circuit_size),
             q_o: Vec::with_capacity(circuit_size),
+            q_4: Vec::with_capacity(circuit_size),
             q_c: Vec::with_capacity(circuit_size),
+            q_hl: Vec::with_capacity(circuit_size),
+            q_hr: Vec::with_capacity(circuit_size),
+            q_h4: Vec::with_capacity(circuit_size),
+            q_arith: Vec::with_capacity(circuit_size),
             q_lookup: Vec::with_capacity(circuit_size),
             public_inputs: PublicInputs::new(),
+            intended_pi_pos: Vec::new(),
             w_l: Vec::with_capacity(circuit_size),
             w_r: Vec::with_capacity(circuit_size),
             w_o: Vec::with_capacity(circuit_size),
+            w_4: Vec::with_capacity(circuit_size),
             lookup_table: LookupTable::new(),
-            perm: Permutation::new(),
+            perm: Permutation::with_capacity(circuit_size),
+            gate_kinds: Vec::with_capacity(circuit_size),
+            variables: HashMap::with_capacity(circuit_size),
+            zero_var: Variable(0),
+            regions: Vec::new(),
+            namespace_path: Vec::new(),
+            variable_names: HashMap::new(),
             __: PhantomData::<P>,
         };
 
-        builder
-
-  
-    }
-
-
-
-
-
-
+        // Reserve the first variable for zero and constrain it.
+        builder.zero_var = builder.add_input(F::zero());
+        builder.constrain_to_constant(builder.zero_var, F::zero(), None);
 
+        builder.add_dummy_constraints();
+        builder.add_dummy_lookup_table();
+        builder.add_blinding_factors(&mut OsRng);
 
+        builder
+    }
 
+    // Returns the number of gates in the circuit.
+    pub fn circuit_size(&self) -> usize {
+        self.n
+    }
 
+    // Returns the smallest power of two holding every gate and every row of
+    // the lookup table.
+    pub fn circuit_bound(&self) -> usize {
+        core::cmp::max(self.n, self.lookup_table.size()).next_power_of_two()
+    }
 
+    // Returns the public inputs of the circuit.
+    pub fn get_pi(&self) -> &PublicInputs<F> {
+        &self.public_inputs
+    }
 
+    // Returns the lookup table of the circuit.
+    pub fn lookup_table(&self) -> &LookupTable<F> {
+        &self.lookup_table
+    }
 
+    // Returns a mutable reference to the lookup table of the circuit, used to
+    // add the rows queried by `lookup_gate`.
+    pub fn lookup_table_mut(&mut self) -> &mut LookupTable<F> {
+        &mut self.lookup_table
+    }
 
-     /*  The final constraint added will force the following:
+    /*  The final constraint added will force the following:
     `(a * b) * q_m + a * q_l + b * q_r + q_c + PI + q_o * c = 0`.*/
+    #[allow(clippy::too_many_arguments)]
     pub fn poly_gate(
         &mut self,
         a: Variable,
@@ -109,22 +205,22 @@ where
         self.w_l.push(a);
         self.w_r.push(b);
         self.w_o.push(c);
+        self.w_4.push(self.zero_var);
 
         // Add selector vectors
-   
         self.q_l.push(q_l);
         self.q_r.push(q_r);
-
-        
         self.q_m.push(q_m);
         self.q_o.push(q_o);
         self.q_c.push(q_c);
-  
-
+        self.q_4.push(F::zero());
+        self.q_arith.push(F::one());
 
         self.q_lookup.push(F::zero());
-
-
+        self.q_hl.push(F::zero());
+        self.q_hr.push(F::zero());
+        self.q_h4.push(F::zero());
+        self.gate_kinds.push(GateKind::Arithmetic);
 
         if let Some(pi) = pi {
             self.add_pi(self.n, &pi).unwrap_or_else(|_| {
@@ -159,8 +255,8 @@ where
         );
     }
 
-        // assert two variable to be equal
-        pub fn assert_equal(&mut self, a: Variable, b: Variable) {
+    // assert two variable to be equal
+    pub fn assert_equal(&mut self, a: Variable, b: Variable) {
         self.poly_gate(
             a,
             b,
@@ -174,24 +270,37 @@ where
         );
     }
 
-
     pub fn zero_var(&self) -> Variable {
         self.zero_var
     }
 
-
     pub fn add_input(&mut self, s: F) -> Variable {
+        self.add_witness(Some(s))
+    }
+
+    /// Allocates a variable whose value may be unknown, as when a circuit
+    /// is synthesized only to generate its keys. Gates record their
+    /// structure either way, proving fails with
+    /// [`Error::MissingWitness`](crate::error::Error::MissingWitness) while a
+    /// value is missing.
+    pub fn add_witness(&mut self, value: Option<F>) -> Variable {
         // Get a new Variable from the permutation
         let var = self.perm.new_variable();
         // The composer now links the Variable returned from
         // the Permutation to the value F.
-        self.variables.insert(var, s);
+        if let Some(value) = value {
+            self.variables.insert(var, value);
+        }
 
         var
     }
 
+    /// Value of `var`, `None` during witness-free synthesis.
+    pub fn value(&self, var: Variable) -> Option<F> {
+        self.variables.get(&var).copied()
+    }
 
-     //Insert data in the PI starting at the given position and stores the occupied positions as intended for public inputs.
+    //Insert data in the PI starting at the given position and stores the occupied positions as intended for public inputs.
     pub(crate) fn add_pi<T>(
         &mut self,
         pos: usize,
@@ -205,23 +314,19 @@ where
         Ok(())
     }
 
-
     // output 1 if the input is 0 otherwise 1
+    pub fn is_zero_with_output(&mut self, a: Variable) -> Variable {
+        let a_value = self.value(a);
+        let y_value = a_value.map(|a| a.inverse().unwrap_or_else(F::one));
 
-    pub fn is_zero_with_output(&mut self, a:Variable) -> Variable {
-        let a_value = self.variabels.get(&a).unwrap();
-        let y_value = a_value.inverse().unwrap_or_else(F::one);
-
+        let b_value = a_value.zip(y_value).map(|(a, y)| F::one() - a * y);
 
-        let b_value = F::one() - *a_value * y_value;
+        let y = self.add_witness(y_value);
 
-        let y = self.add_input(y_value);
-
-        let b = self.add_input(b_value);
+        let b = self.add_witness(b_value);
 
         let zero = self.zero_var();
 
-
         // Enforce constraints. The constraint system being used here is
         // a * y + b - 1 = 0
         // a * b = 0
@@ -248,11 +353,10 @@ where
     }
 
     // Conditionally selects a [`Variable`] based on an input bit.
-    
+
     // If:
     // bit == 1 => choice_a,
     // bit == 0 => choice_b,
-
     pub fn conditional_select(
         &mut self,
         bit: Variable,
@@ -284,9 +388,7 @@ where
         })
     }
 
-
     // This function adds two dummy gates to the circuit description which are guaranteed to always satisfy the gate equation.
-
     pub fn add_dummy_constraints(&mut self) {
         let var_six = self.add_input(F::from(6u64));
         let var_one = self.add_input(F::one());
@@ -298,13 +400,19 @@ where
         self.q_r.push(F::from(3u64));
         self.q_o.push(F::from(4u64));
         self.q_c.push(F::from(4u64));
-
+        self.q_4.push(F::one());
+        self.q_arith.push(F::one());
 
         self.q_lookup.push(F::one());
-     
+        self.q_hl.push(F::zero());
+        self.q_hr.push(F::zero());
+        self.q_h4.push(F::zero());
+        self.gate_kinds.push(GateKind::Dummy);
+
         self.w_l.push(var_six);
         self.w_r.push(var_seven);
         self.w_o.push(var_min_twenty);
+        self.w_4.push(var_one);
 
         self.perm.add_variables_to_map(
             var_six,
@@ -321,13 +429,19 @@ where
         self.q_o.push(F::one());
         self.q_c.push(F::from(127u64));
         self.q_4.push(F::zero());
+        self.q_arith.push(F::one());
 
         self.q_lookup.push(F::one());
+        self.q_hl.push(F::zero());
+        self.q_hr.push(F::zero());
+        self.q_h4.push(F::zero());
+        self.gate_kinds.push(GateKind::Dummy);
 
         self.w_l.push(var_min_twenty);
         self.w_r.push(var_six);
         self.w_o.push(var_seven);
-    
+        self.w_4.push(self.zero_var);
+
         self.perm.add_variables_to_map(
             var_min_twenty,
             var_six,
@@ -339,7 +453,6 @@ where
     }
 
     // Adds 3 dummy rows to the lookup table
-
     pub fn add_dummy_lookup_table(&mut self) {
         self.lookup_table.insert_row(
             F::from(6u64),
@@ -363,8 +476,6 @@ where
         );
     }
 
-
-
     //This function is used to add a blinding factors to the witness and permutation polynomials.
     pub fn add_blinding_factors<R>(&mut self, rng: &mut R)
     where
@@ -382,7 +493,7 @@ where
             self.w_l.push(rand_var_1);
             self.w_r.push(rand_var_2);
             self.w_o.push(rand_var_3);
-
+            self.w_4.push(rand_var_4);
 
             // All selectors fixed to 0 so that the constraints are satisfied
             self.q_m.push(F::zero());
@@ -390,9 +501,14 @@ where
             self.q_r.push(F::zero());
             self.q_o.push(F::zero());
             self.q_c.push(F::zero());
-         
-            self.q_lookup.push(F::zero());
+            self.q_4.push(F::zero());
+            self.q_arith.push(F::zero());
 
+            self.q_lookup.push(F::zero());
+            self.q_hl.push(F::zero());
+            self.q_hr.push(F::zero());
+            self.q_h4.push(F::zero());
+            self.gate_kinds.push(GateKind::Blinding);
 
             self.perm.add_variables_to_map(
                 rand_var_1, rand_var_2, rand_var_3, rand_var_4, self.n,
@@ -406,7 +522,7 @@ where
         self.w_l.push(rand_var_1);
         self.w_r.push(rand_var_2);
         self.w_o.push(self.zero_var());
-
+        self.w_4.push(self.zero_var());
 
         // All selectors fixed to 0 so that the constraints are satisfied
         self.q_m.push(F::zero());
@@ -414,10 +530,14 @@ where
         self.q_r.push(F::zero());
         self.q_o.push(F::zero());
         self.q_c.push(F::zero());
-
+        self.q_4.push(F::zero());
+        self.q_arith.push(F::zero());
 
         self.q_lookup.push(F::zero());
-
+        self.q_hl.push(F::zero());
+        self.q_hr.push(F::zero());
+        self.q_h4.push(F::zero());
+        self.gate_kinds.push(GateKind::Blinding);
 
         self.perm.add_variables_to_map(
             rand_var_1,
@@ -428,11 +548,8 @@ where
         );
         self.n += 1;
     }
-
-
 }
 
-
 impl<F, P> CircuitBuilder<F, P>
 where
     F: PrimeField,
@@ -467,8 +584,14 @@ where
         self.q_o.push(gate.out_selector);
         self.q_c.push(gate.const_selector);
 
+        let (q_hl, q_hr, q_h4) = gate.high_degree;
+        self.q_hl.push(q_hl);
+        self.q_hr.push(q_hr);
+        self.q_h4.push(q_h4);
 
+        self.q_arith.push(F::one());
         self.q_lookup.push(F::zero());
+        self.gate_kinds.push(GateKind::Arithmetic);
 
         if let Some(pi) = gate.pi {
             self.add_pi(self.n, &pi).unwrap_or_else(|_| {
@@ -476,19 +599,30 @@ where
             });
         };
 
-        let c = gate_witness.2.unwrap_or_else(|| {
-            self.add_input(
-                ((gate.mul_selector
-                    * (self.variables[&gate_witness.0]
-                        * self.variables[&gate_witness.1]))
-                    + gate.add_selectors.0 * self.variables[&gate_witness.0]
-                    + gate.add_selectors.1 * self.variables[&gate_witness.1]
-                    + gate.const_selector
-                    + q4 * self.variables[&w4]
-                    + gate.pi.unwrap_or_default())
-                    * (-gate.out_selector),
-            )
-        });
+        let c = match gate_witness.2 {
+            Some(c) => c,
+            None => {
+                // Left unknown when any input is.
+                let value = (|| {
+                    let a = self.value(gate_witness.0)?;
+                    let b = self.value(gate_witness.1)?;
+                    let d = self.value(w4)?;
+                    Some(
+                        (gate.mul_selector * a * b
+                            + gate.add_selectors.0 * a
+                            + gate.add_selectors.1 * b
+                            + gate.const_selector
+                            + q4 * d
+                            + q_hl * a.pow([5u64])
+                            + q_hr * b.pow([5u64])
+                            + q_h4 * d.pow([5u64])
+                            + gate.pi.unwrap_or_default())
+                            * (-gate.out_selector),
+                    )
+                })();
+                self.add_witness(value)
+            }
+        };
         self.w_o.push(c);
         self.perm.add_variables_to_map(
             gate_witness.0,
@@ -501,7 +635,6 @@ where
 
         c
     }
-
 }
 
 // implement lookup gate
@@ -539,10 +672,6 @@ where
         self.q_c.push(F::zero());
         self.q_4.push(F::zero());
         self.q_arith.push(F::zero());
-        self.q_range.push(F::zero());
-        self.q_logic.push(F::zero());
-        self.q_fixed_group_add.push(F::zero());
-        self.q_variable_group_add.push(F::zero());
 
         // add high degree selectors
         self.q_hl.push(F::zero());
@@ -552,6 +681,7 @@ where
         // For a lookup gate, only one selector poly is
         // turned on as the output is inputted directly
         self.q_lookup.push(F::one());
+        self.gate_kinds.push(GateKind::Lookup);
 
         if let Some(pi) = pi {
             self.add_pi(self.n, &pi).unwrap_or_else(|_| {
@@ -567,12 +697,149 @@ where
     }
 }
 
+// implement namespaces
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Runs `func` inside the namespace `name`, nested in the currently open
+    /// ones. Every gate added by `func` is recorded as belonging to the
+    /// region `outer/.../name`.
+    pub fn namespace<T, C>(&mut self, name: &str, func: C) -> T
+    where
+        C: FnOnce(&mut Self) -> T,
+    {
+        self.namespace_path.push(name.to_string());
+        let start = self.n;
+        let result = func(self);
+        let path = self.namespace_path.join("/");
+        self.regions.push((path, start, self.n));
+        self.namespace_path.pop();
+        result
+    }
+
+    /// Gives `var` a name, prefixed with the namespace currently open.
+    pub fn name_variable(&mut self, var: Variable, name: &str) {
+        let name = match self.namespace_path.is_empty() {
+            true => name.to_string(),
+            false => format!("{}/{}", self.namespace_path.join("/"), name),
+        };
+        self.variable_names.insert(var, name);
+    }
+
+    /// Returns the path of the innermost region containing gate `index`.
+    pub fn region_of(&self, index: usize) -> Option<&str> {
+        self.regions
+            .iter()
+            .find(|(_, start, end)| (*start..*end).contains(&index))
+            .map(|(path, _, _)| path.as_str())
+    }
+
+    /// Returns the name given to `var`, if any.
+    pub fn variable_name(&self, var: Variable) -> Option<&str> {
+        self.variable_names.get(&var).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::satisfiability::Unsatisfied;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
 
+    type Builder = CircuitBuilder<Fr, EdwardsParameters>;
 
+    /// Adds `a * b` and returns its gate index.
+    fn mul(cs: &mut Builder, a: Variable, b: Variable) -> usize {
+        let index = cs.n;
+        cs.arithmetic_gate(|gate| gate.witness(a, b, None).mul(Fr::from(1u64)));
+        index
+    }
 
+    #[test]
+    fn nested_namespaces_give_innermost_region() {
+        let mut cs = Builder::new();
+        let a = cs.add_input(Fr::from(3u64));
+        let (before, inner, after) = cs.namespace("outer", |cs| {
+            let before = mul(cs, a, a);
+            let inner = cs.namespace("inner", |cs| mul(cs, a, a));
+            (before, inner, mul(cs, a, a))
+        });
+        let outside = mul(&mut cs, a, a);
 
+        assert_eq!(cs.region_of(before), Some("outer"));
+        assert_eq!(cs.region_of(inner), Some("outer/inner"));
+        assert_eq!(cs.region_of(after), Some("outer"));
+        assert_eq!(cs.region_of(outside), None);
+    }
 
+    #[test]
+    fn variable_names_are_prefixed_with_namespaces() {
+        let mut cs = Builder::new();
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        let c = cs.add_input(Fr::from(7u64));
+        cs.name_variable(a, "a");
+        cs.namespace("outer", |cs| {
+            cs.name_variable(b, "b");
+            cs.namespace("inner", |cs| cs.name_variable(c, "c"));
+        });
 
+        assert_eq!(cs.variable_name(a), Some("a"));
+        assert_eq!(cs.variable_name(b), Some("outer/b"));
+        assert_eq!(cs.variable_name(c), Some("outer/inner/c"));
+        assert_eq!(cs.variable_name(cs.zero_var()), None);
+    }
 
+    #[test]
+    fn unsatisfied_gate_carries_region_and_names() {
+        let mut cs = Builder::new();
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        cs.namespace("outer", |cs| {
+            cs.namespace("check", |cs| {
+                cs.name_variable(a, "a");
+                cs.constrain_to_constant(a, Fr::from(4u64), None);
+            });
+            mul(cs, a, b);
+        });
 
+        match cs.check_satisfied() {
+            Err(Unsatisfied::Gate { region, names, .. }) => {
+                assert_eq!(region.as_deref(), Some("outer/check"));
+                assert_eq!(names[0].as_deref(), Some("outer/check/a"));
+            }
+            other => panic!("expected a gate violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unsatisfied_lookup_carries_region_and_names() {
+        let mut cs = Builder::new();
+        let zero = Fr::from(0u64);
+        cs.lookup_table_mut().insert_row(
+            Fr::from(1u64),
+            Fr::from(2u64),
+            Fr::from(3u64),
+            zero,
+        );
+        let a = cs.add_input(Fr::from(1u64));
+        let b = cs.add_input(Fr::from(2u64));
+        let c = cs.add_input(Fr::from(4u64));
+        cs.namespace("table", |cs| {
+            cs.name_variable(c, "c");
+            cs.lookup_gate(a, b, c, None, None);
+        });
 
+        match cs.check_satisfied() {
+            Err(Unsatisfied::Lookup { region, names, .. }) => {
+                assert_eq!(region.as_deref(), Some("table"));
+                assert_eq!(names[0], None);
+                assert_eq!(names[2].as_deref(), Some("table/c"));
+            }
+            other => panic!("expected a lookup violation, got {:?}", other),
+        }
+    }
+}
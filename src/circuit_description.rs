@@ -0,0 +1,418 @@
+//! Witness-free description of a compiled circuit.
+//!
+//! A [`CircuitDescription`] holds everything preprocessing needs: selectors,
+//! wiring, the lookup table and the public input positions. It can be
+//! written on one machine and turned back into a [`CircuitBuilder`] on
+//! another one, without linking the gadget code that built the circuit.
+use crate::{
+    constraint_system::{CircuitBuilder, GateKind, Variable},
+    error::Error,
+    lookup_table::LookupTable,
+    permutation::Permutation,
+    proof_system::pi::PublicInputs,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+use hashbrown::HashMap;
+
+/// Selectors, wiring and lookup table of a circuit, without witness values.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CircuitDescription<F>
+where
+    F: PrimeField,
+{
+    /// Number of gates.
+    pub n: u64,
+    /// Number of variables allocated.
+    pub num_variables: u64,
+    /// Id of the variable constrained to zero.
+    pub zero_var: u64,
+
+    /// Multiplication selector, applied to `a·b`.
+    pub q_m: Vec<F>,
+    /// Left wire selector.
+    pub q_l: Vec<F>,
+    /// Right wire selector.
+    pub q_r: Vec<F>,
+    /// Output wire selector.
+    pub q_o: Vec<F>,
+    /// Fourth wire selector.
+    pub q_4: Vec<F>,
+    /// Constant selector.
+    pub q_c: Vec<F>,
+    /// Selector of `a^5`.
+    pub q_hl: Vec<F>,
+    /// Selector of `b^5`.
+    pub q_hr: Vec<F>,
+    /// Selector of `d^5`.
+    pub q_h4: Vec<F>,
+    /// Enables the arithmetic equation on a gate.
+    pub q_arith: Vec<F>,
+    /// Enables the lookup query on a gate.
+    pub q_lookup: Vec<F>,
+
+    /// Variable ids on the left wires.
+    pub w_l: Vec<u64>,
+    /// Variable ids on the right wires.
+    pub w_r: Vec<u64>,
+    /// Variable ids on the output wires.
+    pub w_o: Vec<u64>,
+    /// Variable ids on the fourth wires.
+    pub w_4: Vec<u64>,
+
+    /// Columns of the lookup table.
+    pub table: Vec<Vec<F>>,
+
+    /// Gate indices holding public inputs.
+    pub pi_positions: Vec<u64>,
+    /// Public input values, in the order of `pi_positions`.
+    pub pi_values: Vec<F>,
+
+    /// Kind of each gate, see [`GateKind::to_u8`].
+    pub gate_kinds: Vec<u8>,
+
+    /// Region paths as UTF-8, innermost region first.
+    pub region_paths: Vec<Vec<u8>>,
+    /// First gate of each region.
+    pub region_starts: Vec<u64>,
+    /// Gate following the last gate of each region.
+    pub region_ends: Vec<u64>,
+}
+
+impl GateKind {
+    /// Stable encoding used by [`CircuitDescription`].
+    pub fn to_u8(self) -> u8 {
+        match self {
+            GateKind::Arithmetic => 0,
+            GateKind::Lookup => 1,
+            GateKind::Range => 2,
+            GateKind::Logic => 3,
+            GateKind::Curve => 4,
+            GateKind::Dummy => 5,
+            GateKind::Blinding => 6,
+        }
+    }
+
+    /// Inverse of [`GateKind::to_u8`].
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        Some(match kind {
+            0 => GateKind::Arithmetic,
+            1 => GateKind::Lookup,
+            2 => GateKind::Range,
+            3 => GateKind::Logic,
+            4 => GateKind::Curve,
+            5 => GateKind::Dummy,
+            6 => GateKind::Blinding,
+            _ => return None,
+        })
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Exports the shape of the circuit, leaving out every witness value.
+    pub fn export(&self) -> CircuitDescription<F> {
+        let ids = |wires: &[Variable]| {
+            wires.iter().map(|var| var.0 as u64).collect::<Vec<_>>()
+        };
+        let table = (0..4)
+            .map(|col| self.lookup_table.0.iter().map(|row| row[col]).collect())
+            .collect();
+
+        CircuitDescription {
+            n: self.n as u64,
+            num_variables: self.perm.variable_map.len() as u64,
+            zero_var: self.zero_var.0 as u64,
+            q_m: self.q_m.clone(),
+            q_l: self.q_l.clone(),
+            q_r: self.q_r.clone(),
+            q_o: self.q_o.clone(),
+            q_4: self.q_4.clone(),
+            q_c: self.q_c.clone(),
+            q_hl: self.q_hl.clone(),
+            q_hr: self.q_hr.clone(),
+            q_h4: self.q_h4.clone(),
+            q_arith: self.q_arith.clone(),
+            q_lookup: self.q_lookup.clone(),
+            w_l: ids(&self.w_l),
+            w_r: ids(&self.w_r),
+            w_o: ids(&self.w_o),
+            w_4: ids(&self.w_4),
+            table,
+            pi_positions: self
+                .intended_pi_pos
+                .iter()
+                .map(|pos| *pos as u64)
+                .collect(),
+            pi_values: self.public_inputs.get_vals().copied().collect(),
+            gate_kinds: self.gate_kinds.iter().map(|k| k.to_u8()).collect(),
+            region_paths: self
+                .regions
+                .iter()
+                .map(|(path, _, _)| path.as_bytes().to_vec())
+                .collect(),
+            region_starts: self
+                .regions
+                .iter()
+                .map(|(_, start, _)| *start as u64)
+                .collect(),
+            region_ends: self
+                .regions
+                .iter()
+                .map(|(_, _, end)| *end as u64)
+                .collect(),
+        }
+    }
+
+    /// Rebuilds a circuit from its description. The result can be
+    /// preprocessed right away, witness values have to be assigned before
+    /// it can be proven.
+    pub fn from_description(
+        desc: &CircuitDescription<F>,
+    ) -> Result<Self, Error> {
+        let n = desc.n as usize;
+        let num_variables = desc.num_variables as usize;
+
+        let columns_ok = [
+            &desc.q_m,
+            &desc.q_l,
+            &desc.q_r,
+            &desc.q_o,
+            &desc.q_4,
+            &desc.q_c,
+            &desc.q_hl,
+            &desc.q_hr,
+            &desc.q_h4,
+            &desc.q_arith,
+            &desc.q_lookup,
+        ]
+        .iter()
+        .all(|q| q.len() == n)
+            && [&desc.w_l, &desc.w_r, &desc.w_o, &desc.w_4]
+                .iter()
+                .all(|w| {
+                    w.len() == n
+                        && w.iter().all(|id| (*id as usize) < num_variables)
+                })
+            && desc.gate_kinds.len() == n
+            && desc.table.len() == 4
+            && desc.table.iter().all(|t| t.len() == desc.table[0].len())
+            && desc.region_starts.len() == desc.region_paths.len()
+            && desc.region_ends.len() == desc.region_paths.len()
+            && desc
+                .region_starts
+                .iter()
+                .zip(&desc.region_ends)
+                .all(|(start, end)| start <= end && (*end as usize) <= n)
+            && desc.pi_positions.iter().all(|pos| (*pos as usize) < n)
+            && desc.pi_values.len() == desc.pi_positions.len()
+            && (desc.zero_var as usize) < num_variables;
+        if !columns_ok {
+            return Err(Error::InvalidCircuitDescription);
+        }
+
+        // Every row, variable and public input comes from the description,
+        // so the ones added by the constructor are dropped.
+        let mut cs = Self::with_expected_size(n);
+        cs.perm = Permutation::with_capacity(num_variables);
+        cs.variables = HashMap::with_capacity(num_variables);
+        cs.public_inputs = PublicInputs::new();
+        for _ in 0..num_variables {
+            cs.perm.new_variable();
+        }
+        cs.zero_var = Variable(desc.zero_var as usize);
+        cs.variables.insert(cs.zero_var, F::zero());
+
+        cs.q_m = desc.q_m.clone();
+        cs.q_l = desc.q_l.clone();
+        cs.q_r = desc.q_r.clone();
+        cs.q_o = desc.q_o.clone();
+        cs.q_4 = desc.q_4.clone();
+        cs.q_c = desc.q_c.clone();
+        cs.q_hl = desc.q_hl.clone();
+        cs.q_hr = desc.q_hr.clone();
+        cs.q_h4 = desc.q_h4.clone();
+        cs.q_arith = desc.q_arith.clone();
+        cs.q_lookup = desc.q_lookup.clone();
+
+        let vars = |ids: &[u64]| {
+            ids.iter()
+                .map(|id| Variable(*id as usize))
+                .collect::<Vec<_>>()
+        };
+        cs.w_l = vars(&desc.w_l);
+        cs.w_r = vars(&desc.w_r);
+        cs.w_o = vars(&desc.w_o);
+        cs.w_4 = vars(&desc.w_4);
+        for i in 0..n {
+            cs.perm.add_variables_to_map(
+                cs.w_l[i], cs.w_r[i], cs.w_o[i], cs.w_4[i], i,
+            );
+        }
+        cs.n = n;
+
+        cs.lookup_table = LookupTable(
+            (0..desc.table[0].len())
+                .map(|i| {
+                    [
+                        desc.table[0][i],
+                        desc.table[1][i],
+                        desc.table[2][i],
+                        desc.table[3][i],
+                    ]
+                })
+                .collect(),
+        );
+
+        cs.intended_pi_pos =
+            desc.pi_positions.iter().map(|pos| *pos as usize).collect();
+        for (pos, value) in cs.intended_pi_pos.iter().zip(&desc.pi_values) {
+            cs.public_inputs
+                .add_input(*pos, value)
+                .map_err(|_| Error::InvalidCircuitDescription)?;
+        }
+
+        cs.gate_kinds = desc
+            .gate_kinds
+            .iter()
+            .map(|k| GateKind::from_u8(*k))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::InvalidCircuitDescription)?;
+
+        cs.regions = desc
+            .region_paths
+            .iter()
+            .zip(desc.region_starts.iter().zip(desc.region_ends.iter()))
+            .map(|(path, (start, end))| {
+                String::from_utf8(path.clone())
+                    .map(|path| (path, *start as usize, *end as usize))
+                    .map_err(|_| Error::InvalidCircuitDescription)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(cs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{commitment::KZG10, prover::Prover, verifier::Verifier};
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_poly_commit::PolynomialCommitment;
+
+    type PC = KZG10<Bls12_381>;
+
+    /// `a * b = c` with `c` public, plus a lookup of `(a, b, c)`.
+    fn mul_circuit(cs: &mut CircuitBuilder<Fr, EdwardsParameters>) {
+        cs.lookup_table_mut().insert_row(
+            Fr::from(3u64),
+            Fr::from(5u64),
+            Fr::from(15u64),
+            Fr::from(0u64),
+        );
+        cs.namespace("mul", |cs| {
+            let a = cs.add_input(Fr::from(3u64));
+            let b = cs.add_input(Fr::from(5u64));
+            let c = cs.arithmetic_gate(|gate| {
+                gate.witness(a, b, None).mul(Fr::from(1u64))
+            });
+            cs.constrain_to_constant(c, Fr::from(0u64), Some(-Fr::from(15u64)));
+            cs.lookup_gate(a, b, c, None, None);
+        });
+    }
+
+    #[test]
+    fn description_round_trips_and_verifies_proofs() {
+        let rng = &mut ark_std::test_rng();
+
+        let mut prover =
+            Prover::<Fr, EdwardsParameters, PC>::new(b"description");
+        mul_circuit(prover.mut_cs());
+        let desc = prover.cs.export();
+
+        let mut bytes = Vec::new();
+        desc.serialize(&mut bytes).unwrap();
+        let read = CircuitDescription::<Fr>::deserialize(&bytes[..]).unwrap();
+        assert_eq!(read, desc);
+
+        let imported =
+            CircuitBuilder::<Fr, EdwardsParameters>::from_description(&read)
+                .unwrap();
+        assert_eq!(imported.export(), desc);
+        assert_eq!(imported.get_pi(), prover.cs.get_pi());
+
+        let n = prover.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+        let proof = prover.prove(&ck).unwrap();
+
+        let mut verifier =
+            Verifier::<Fr, EdwardsParameters, PC>::new(b"description");
+        *verifier.mut_cs() = imported;
+        let pi = verifier.mut_cs().get_pi().clone();
+        verifier.preprocess(&ck).unwrap();
+        assert_eq!(verifier.verify(&proof, &vk, &pi), Ok(()));
+    }
+
+    #[test]
+    fn inconsistent_description_is_rejected() {
+        let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+        mul_circuit(&mut cs);
+
+        let mut desc = cs.export();
+        desc.q_arith.pop();
+        assert_eq!(
+            CircuitBuilder::<Fr, EdwardsParameters>::from_description(&desc)
+                .err(),
+            Some(Error::InvalidCircuitDescription)
+        );
+
+        let mut desc = cs.export();
+        desc.pi_values.clear();
+        assert_eq!(
+            CircuitBuilder::<Fr, EdwardsParameters>::from_description(&desc)
+                .err(),
+            Some(Error::InvalidCircuitDescription)
+        );
+    }
+
+    #[test]
+    fn malformed_region_is_rejected() {
+        let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+        mul_circuit(&mut cs);
+        let n = cs.n as u64;
+
+        let mut desc = cs.export();
+        desc.region_starts[0] = desc.region_ends[0] + 1;
+        assert_eq!(
+            CircuitBuilder::<Fr, EdwardsParameters>::from_description(&desc)
+                .err(),
+            Some(Error::InvalidCircuitDescription)
+        );
+
+        let mut desc = cs.export();
+        desc.region_ends[0] = n + 1;
+        assert_eq!(
+            CircuitBuilder::<Fr, EdwardsParameters>::from_description(&desc)
+                .err(),
+            Some(Error::InvalidCircuitDescription)
+        );
+
+        // A region may end on the last row.
+        let mut desc = cs.export();
+        desc.region_ends[0] = n;
+        let imported =
+            CircuitBuilder::<Fr, EdwardsParameters>::from_description(&desc)
+                .unwrap();
+        assert_eq!(imported.stats().rows, cs.n);
+    }
+}
@@ -1,4 +1,5 @@
 //! Useful commitment stuff
+use crate::error::Error;
 use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine};
 use ark_ff::{Field, PrimeField};
 use ark_poly::univariate::DensePolynomial;
@@ -15,7 +16,13 @@ where
     fn multi_scalar_mul(
         commitments: &[Self::Commitment],
         scalars: &[F],
-    ) -> Self::Commitment;
+    ) -> Result<Self::Commitment, Error>;
+
+    /// Degree bound enforced on the witness polynomials of a circuit of
+    /// size `n`, if the scheme checks degree bounds.
+    fn degree_bound(_n: usize) -> Option<usize> {
+        None
+    }
 }
 
 /// The Default KZG-style commitment scheme
@@ -33,7 +40,7 @@ where
     fn multi_scalar_mul(
         commitments: &[KZG10Commitment<E>],
         scalars: &[E::Fr],
-    ) -> KZG10Commitment<E> {
+    ) -> Result<KZG10Commitment<E>, Error> {
         let scalars_repr = scalars
             .iter()
             .map(<E::Fr as PrimeField>::into_repr)
@@ -41,10 +48,10 @@ where
 
         let points_repr = commitments.iter().map(|c| c.0).collect::<Vec<_>>();
 
-        ark_poly_commit::kzg10::Commitment::<E>(
+        Ok(ark_poly_commit::kzg10::Commitment::<E>(
             VariableBaseMSM::multi_scalar_mul(&points_repr, &scalars_repr)
                 .into(),
-        )
+        ))
     }
 }
 
@@ -70,7 +77,16 @@ where
     fn multi_scalar_mul(
         commitments: &[IPACommitment<G, D>],
         scalars: &[<G as ark_ec::AffineCurve>::ScalarField],
-    ) -> IPACommitment<G, D> {
+    ) -> Result<IPACommitment<G, D>, Error> {
+        // The shifted part of a degree bounded commitment can only be
+        // combined with commitments sharing its bound, which is not tracked
+        // here.
+        if commitments.iter().any(|c| c.shifted_comm.is_some()) {
+            return Err(Error::PCError {
+                error: "can not combine degree bounded commitments".to_string(),
+            });
+        }
+
         let scalars_repr = scalars
             .iter()
             .map(<G as ark_ec::AffineCurve>::ScalarField::into_repr)
@@ -79,25 +95,29 @@ where
         let points_repr =
             commitments.iter().map(|c| c.comm).collect::<Vec<_>>();
 
-        IPACommitment::<G, D> {
+        Ok(IPACommitment::<G, D> {
             comm: VariableBaseMSM::multi_scalar_mul(
                 &points_repr,
                 &scalars_repr,
             )
             .into(),
-            shifted_comm: None, // TODO: support degree bounds?
-        }
+            shifted_comm: None,
+        })
+    }
+
+    fn degree_bound(n: usize) -> Option<usize> {
+        Some(n - 1)
     }
 }
 
 /// Computes a linear combination of the polynomial evaluations and polynomial
-/// commitments provided a challenge.
-// TODO: complete doc & use util::lc for eval combination
+/// commitments provided a challenge: both are combined with the increasing
+/// powers of `challenge`.
 pub fn linear_combination<F, H>(
     evals: &[F],
     commitments: &[H::Commitment],
     challenge: F,
-) -> (H::Commitment, F)
+) -> Result<(H::Commitment, F), Error>
 where
     F: PrimeField,
     H: HomomorphicCommitment<F>,
@@ -106,13 +126,9 @@ where
     let powers = crate::util::powers_of(challenge)
         .take(evals.len())
         .collect::<Vec<_>>();
-    let combined_eval = evals
-        .iter()
-        .zip(powers.iter())
-        .map(|(&eval, power)| eval * power)
-        .sum();
-    let combined_commitment = H::multi_scalar_mul(commitments, &powers);
-    (combined_commitment, combined_eval)
+    let combined_eval = crate::util::lc(evals, challenge);
+    let combined_commitment = H::multi_scalar_mul(commitments, &powers)?;
+    Ok((combined_commitment, combined_eval))
 }
 
 /// Aggregate polynomials
@@ -0,0 +1,5 @@
+//! The constraint system: circuit builder, gates, variables and wires.
+
+pub use crate::arithmetic_gate::ArithmeticGate;
+pub use crate::circuit_builder::{CircuitBuilder, GateKind};
+pub use crate::variable::{Variable, WireData};
@@ -0,0 +1,171 @@
+//! Concrete instantiations of the proving stack.
+//!
+//! Each module pairs a pairing-friendly curve with the twisted Edwards curve
+//! embedded in its scalar field, which is used as `P` by the
+//! [`CircuitBuilder`](crate::constraint_system::CircuitBuilder) ECC gadgets.
+
+use crate::commitment::KZG10;
+use ark_ec::TEModelParameters;
+
+/// Twisted Edwards curve defined over the scalar field of a pairing curve.
+///
+/// Only implemented for actual curves, not for the `no_embedded_curve!`
+/// placeholders, so the ECC gadgets can not be used without a group.
+pub trait EmbeddedCurve: TEModelParameters {}
+
+impl EmbeddedCurve for ark_ed_on_bn254::EdwardsParameters {}
+impl EmbeddedCurve for ark_ed_on_bls12_381::EdwardsParameters {}
+impl EmbeddedCurve for ark_ed_on_bls12_377::EdwardsParameters {}
+
+/// BN254 with Baby Jubjub. The only pairing curve with EVM precompiles.
+pub mod bn254 {
+    use super::*;
+
+    /// Pairing engine.
+    pub type E = ark_bn254::Bn254;
+    /// Scalar field of the circuit.
+    pub type F = ark_bn254::Fr;
+    /// Embedded Baby Jubjub curve parameters.
+    pub type P = ark_ed_on_bn254::EdwardsParameters;
+    /// KZG commitment scheme over BN254.
+    pub type PC = KZG10<E>;
+    /// Prover over BN254.
+    pub type Prover = crate::prover::Prover<F, P, PC>;
+}
+
+/// BLS12-381 with Jubjub.
+pub mod bls12_381 {
+    use super::*;
+
+    /// Pairing engine.
+    pub type E = ark_bls12_381::Bls12_381;
+    /// Scalar field of the circuit.
+    pub type F = ark_bls12_381::Fr;
+    /// Embedded Jubjub curve parameters.
+    pub type P = ark_ed_on_bls12_381::EdwardsParameters;
+    /// KZG commitment scheme over BLS12-381.
+    pub type PC = KZG10<E>;
+    /// Prover over BLS12-381.
+    pub type Prover = crate::prover::Prover<F, P, PC>;
+}
+
+/// BLS12-377 with its embedded Edwards curve.
+pub mod bls12_377 {
+    use super::*;
+
+    /// Pairing engine.
+    pub type E = ark_bls12_377::Bls12_377;
+    /// Scalar field of the circuit.
+    pub type F = ark_bls12_377::Fr;
+    /// Embedded Edwards curve parameters.
+    pub type P = ark_ed_on_bls12_377::EdwardsParameters;
+    /// KZG commitment scheme over BLS12-377.
+    pub type PC = KZG10<E>;
+    /// Prover over BLS12-377.
+    pub type Prover = crate::prover::Prover<F, P, PC>;
+}
+
+/// Calls the generic function `$body::<F, P, PC>()` once per supported
+/// pairing curve, so the same circuit can be exercised on every curve.
+///
+/// ```ignore
+/// for_each_curve!(prove_and_verify_circuit);
+/// ```
+#[macro_export]
+macro_rules! for_each_curve {
+    ($body:ident) => {{
+        $body::<
+            $crate::curves::bn254::F,
+            $crate::curves::bn254::P,
+            $crate::curves::bn254::PC,
+        >();
+        $body::<
+            $crate::curves::bls12_381::F,
+            $crate::curves::bls12_381::P,
+            $crate::curves::bls12_381::PC,
+        >();
+        $body::<
+            $crate::curves::bls12_377::F,
+            $crate::curves::bls12_377::P,
+            $crate::curves::bls12_377::PC,
+        >();
+    }};
+}
+
+/// Declares a placeholder embedded curve for a scalar field that has no
+/// twisted Edwards curve defined over it.
+///
+/// The parameters describe no usable group: the generator is the identity.
+/// They only satisfy the `P` bound of the prover, so circuits instantiated
+/// with them must not use the fixed or variable base scalar multiplication
+/// gadgets.
+macro_rules! no_embedded_curve {
+    ($name:ident, $field:ident) => {
+        /// Placeholder embedded curve, see `no_embedded_curve!`.
+        #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+        pub struct $name;
+
+        impl ark_ec::ModelParameters for $name {
+            type BaseField = $field;
+            type ScalarField = $field;
+        }
+
+        impl ark_ec::TEModelParameters for $name {
+            const COEFF_A: $field = ark_ff::field_new!($field, "-1");
+            const COEFF_D: $field = ark_ff::field_new!($field, "5");
+            const COFACTOR: &'static [u64] = &[1];
+            const COFACTOR_INV: $field = ark_ff::field_new!($field, "1");
+            const AFFINE_GENERATOR_COEFFS: ($field, $field) = (
+                ark_ff::field_new!($field, "0"),
+                ark_ff::field_new!($field, "1"),
+            );
+
+            type MontgomeryModelParameters = $name;
+        }
+
+        impl ark_ec::models::MontgomeryModelParameters for $name {
+            const COEFF_A: $field = ark_ff::field_new!($field, "0");
+            const COEFF_B: $field = ark_ff::field_new!($field, "1");
+
+            type TEModelParameters = $name;
+        }
+    };
+}
+
+/// Inner product argument over Pallas. Needs no trusted setup.
+pub mod pallas {
+    use crate::commitment::IPA;
+
+    /// Commitment group.
+    pub type G = ark_pallas::Affine;
+    /// Scalar field of the circuit.
+    pub type F = ark_pallas::Fr;
+    /// IPA commitment scheme over Pallas.
+    pub type PC = IPA<G, blake2::Blake2s>;
+
+    no_embedded_curve!(NoEmbeddedCurve, F);
+
+    /// Embedded curve placeholder, ECC gadgets are unavailable.
+    pub type P = NoEmbeddedCurve;
+    /// Prover over Pallas.
+    pub type Prover = crate::prover::Prover<F, P, PC>;
+}
+
+/// Inner product argument over Vesta. Needs no trusted setup.
+pub mod vesta {
+    use crate::commitment::IPA;
+
+    /// Commitment group.
+    pub type G = ark_vesta::Affine;
+    /// Scalar field of the circuit.
+    pub type F = ark_vesta::Fr;
+    /// IPA commitment scheme over Vesta.
+    pub type PC = IPA<G, blake2::Blake2s>;
+
+    no_embedded_curve!(NoEmbeddedCurve, F);
+
+    /// Embedded curve placeholder, ECC gadgets are unavailable.
+    pub type P = NoEmbeddedCurve;
+    /// Prover over Vesta.
+    pub type Prover = crate::prover::Prover<F, P, PC>;
+}
@@ -0,0 +1,283 @@
+//! Points of the embedded twisted Edwards curve `P` and scalar
+//! multiplications on arithmetic gates.
+//!
+//! Points are affine, `a·x^2 + y^2 = 1 + d·x^2·y^2`, and added with the
+//! complete formulas, so the identity `(0, 1)` and doublings need no special
+//! case. Scalars are decomposed into bits checked to be the canonical
+//! representation of a field element, and every bit adds either the
+//! identity or a multiple of the base, selected with one row per
+//! coordinate.
+//!
+//! The gadgets are only available for an [`EmbeddedCurve`], not for the
+//! placeholder parameters of fields without one.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    curves::EmbeddedCurve,
+};
+use ark_ec::twisted_edwards_extended::GroupAffine;
+use ark_ff::{BigInteger, FpParameters, PrimeField, Zero};
+
+/// A point of the embedded curve allocated in a circuit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Point {
+    /// Affine `x` coordinate.
+    pub x: Variable,
+    /// Affine `y` coordinate.
+    pub y: Variable,
+}
+
+fn bit(limbs: &[u64], i: usize) -> bool {
+    (limbs[i / 64] >> (i % 64)) & 1 == 1
+}
+
+fn num_bits(limbs: &[u64]) -> usize {
+    (0..64 * limbs.len())
+        .rev()
+        .find(|i| bit(limbs, *i))
+        .map_or(0, |i| i + 1)
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: EmbeddedCurve<BaseField = F>,
+{
+    /// Allocates a point constrained to be on the curve. The point is not
+    /// checked to be in the prime order subgroup, see
+    /// [`Self::assert_in_prime_subgroup`].
+    pub fn add_affine(&mut self, point: Option<GroupAffine<P>>) -> Point {
+        let zero = self.zero_var;
+        let x = self.add_witness(point.map(|p| p.x));
+        let y = self.add_witness(point.map(|p| p.y));
+        let xx =
+            self.arithmetic_gate(|gate| gate.witness(x, x, None).mul(F::one()));
+        let yy =
+            self.arithmetic_gate(|gate| gate.witness(y, y, None).mul(F::one()));
+        // a·x^2 + y^2 - d·x^2·y^2 - 1 = 0
+        self.arithmetic_gate(|gate| {
+            gate.witness(xx, yy, Some(zero))
+                .mul(-P::COEFF_D)
+                .add(P::COEFF_A, F::one())
+                .out(F::zero())
+                .constant(-F::one())
+        });
+        Point { x, y }
+    }
+
+    /// Allocates a point constrained to the given constant.
+    pub fn add_affine_constant(&mut self, point: GroupAffine<P>) -> Point {
+        let x = self.add_input(point.x);
+        self.constrain_to_constant(x, point.x, None);
+        let y = self.add_input(point.y);
+        self.constrain_to_constant(y, point.y, None);
+        Point { x, y }
+    }
+
+    /// Constrains two points to be equal.
+    pub fn assert_equal_point(&mut self, a: Point, b: Point) {
+        self.assert_equal(a.x, b.x);
+        self.assert_equal(a.y, b.y);
+    }
+
+    /// Constrains `point` to be in the prime order subgroup, as
+    /// `[l]point = (0, 1)` for the subgroup order `l`. Takes sixteen rows
+    /// per bit of `l`.
+    pub fn assert_in_prime_subgroup(&mut self, point: Point) {
+        let order = <P::ScalarField as PrimeField>::Params::MODULUS;
+        let order = order.as_ref();
+        let one = self.add_input(F::one());
+        self.constrain_to_constant(one, F::one(), None);
+        let bits = (0..num_bits(order))
+            .map(|i| match bit(order, i) {
+                true => one,
+                false => self.zero_var,
+            })
+            .collect::<Vec<_>>();
+        let multiple = self.variable_base_mul_bits(&bits, point);
+        self.constrain_to_constant(multiple.x, F::zero(), None);
+        self.constrain_to_constant(multiple.y, F::one(), None);
+    }
+
+    /// Constrains the sum of two points, in seven rows.
+    pub fn point_addition(&mut self, a: Point, b: Point) -> Point {
+        let x1y2 = self
+            .arithmetic_gate(|gate| gate.witness(a.x, b.y, None).mul(F::one()));
+        let y1x2 = self
+            .arithmetic_gate(|gate| gate.witness(a.y, b.x, None).mul(F::one()));
+        let y1y2 = self
+            .arithmetic_gate(|gate| gate.witness(a.y, b.y, None).mul(F::one()));
+        let x1x2 = self
+            .arithmetic_gate(|gate| gate.witness(a.x, b.x, None).mul(F::one()));
+        let t = self.arithmetic_gate(|gate| {
+            gate.witness(x1x2, y1y2, None).mul(F::one())
+        });
+
+        let values = (|| {
+            let [x1y2, y1x2, y1y2, x1x2, t] =
+                [x1y2, y1x2, y1y2, x1x2, t].map(|var| self.value(var));
+            let dt = P::COEFF_D * t?;
+            let x = (x1y2? + y1x2?) * (F::one() + dt).inverse()?;
+            let y = (y1y2? - P::COEFF_A * x1x2?) * (F::one() - dt).inverse()?;
+            Some((x, y))
+        })();
+        let x = self.add_witness(values.map(|(x, _)| x));
+        let y = self.add_witness(values.map(|(_, y)| y));
+
+        // x·(1 + d·t) - x1·y2 - y1·x2 = 0
+        self.arithmetic_gate(|gate| {
+            gate.witness(x, t, Some(x1y2))
+                .mul(P::COEFF_D)
+                .add(F::one(), F::zero())
+                .out(-F::one())
+                .fan_in_3(-F::one(), y1x2)
+        });
+        // y·(1 - d·t) - y1·y2 + a·x1·x2 = 0
+        self.arithmetic_gate(|gate| {
+            gate.witness(y, t, Some(y1y2))
+                .mul(-P::COEFF_D)
+                .add(F::one(), F::zero())
+                .out(-F::one())
+                .fan_in_3(P::COEFF_A, x1x2)
+        });
+        Point { x, y }
+    }
+
+    /// Constrains `[scalar]base` for a constant base, the scalar being read
+    /// as an integer below the field modulus.
+    pub fn fixed_base_scalar_mul(
+        &mut self,
+        scalar: Variable,
+        base: GroupAffine<P>,
+    ) -> Point {
+        let bits = self.canonical_bits(scalar, F::characteristic());
+        self.fixed_base_mul_bits(&bits, base)
+    }
+
+    /// Constrains `[scalar]base`, the scalar being read as an integer below
+    /// the field modulus.
+    pub fn variable_base_scalar_mul(
+        &mut self,
+        scalar: Variable,
+        base: Point,
+    ) -> Point {
+        let bits = self.canonical_bits(scalar, F::characteristic());
+        self.variable_base_mul_bits(&bits, base)
+    }
+
+    /// Decomposes `x` into as many bits as `modulus` has, least significant
+    /// first, and constrains them to be boolean and to encode an integer
+    /// below the odd `modulus`.
+    ///
+    /// The integer is compared with `modulus - 1` from the most significant
+    /// bit down, while an equality flag tracks whether the bits read so far
+    /// match it: a bit may only be set where `modulus - 1` has a zero once
+    /// the flag is cleared.
+    pub(crate) fn canonical_bits(
+        &mut self,
+        x: Variable,
+        modulus: &[u64],
+    ) -> Vec<Variable> {
+        let n = num_bits(modulus);
+        let repr = self.value(x).map(|x| x.into_repr());
+        let bits = (0..n)
+            .map(|i| self.add_boolean(repr.as_ref().map(|r| r.get_bit(i))))
+            .collect::<Vec<_>>();
+
+        let mut terms = Vec::with_capacity(n + 1);
+        let mut power = F::one();
+        for bit in bits.iter() {
+            terms.push((*bit, power));
+            power.double_in_place();
+        }
+        terms.push((x, -F::one()));
+        self.constrain_linear(terms, F::zero());
+
+        // modulus - 1 only differs from the odd modulus in its lowest bit
+        let mut eq: Option<Variable> = None;
+        for i in (0..n).rev() {
+            match (i > 0 && bit(modulus, i), eq) {
+                (true, None) => eq = Some(bits[i]),
+                (true, Some(flag)) => {
+                    eq = Some(self.arithmetic_gate(|gate| {
+                        gate.witness(flag, bits[i], None).mul(F::one())
+                    }));
+                }
+                (false, None) => {
+                    self.constrain_to_constant(bits[i], F::zero(), None)
+                }
+                (false, Some(flag)) => {
+                    let zero = self.zero_var;
+                    self.arithmetic_gate(|gate| {
+                        gate.witness(flag, bits[i], Some(zero))
+                            .mul(F::one())
+                            .out(F::zero())
+                    });
+                }
+            }
+        }
+        bits
+    }
+
+    /// Constrains `[k]base` for the bits of `k`, least significant first.
+    /// Takes nine rows per bit.
+    pub(crate) fn fixed_base_mul_bits(
+        &mut self,
+        bits: &[Variable],
+        base: GroupAffine<P>,
+    ) -> Point {
+        let zero = self.zero_var;
+        let mut multiple = base;
+        let mut acc: Option<Point> = None;
+        for bit in bits {
+            let (bx, by) = (multiple.x, multiple.y);
+            let selected = Point {
+                x: self.arithmetic_gate(|gate| {
+                    gate.witness(*bit, zero, None).add(bx, F::zero())
+                }),
+                y: self.arithmetic_gate(|gate| {
+                    gate.witness(*bit, zero, None)
+                        .add(by - F::one(), F::zero())
+                        .constant(F::one())
+                }),
+            };
+            acc = Some(match acc {
+                Some(acc) => self.point_addition(acc, selected),
+                None => selected,
+            });
+            multiple = multiple + multiple;
+        }
+        acc.unwrap_or_else(|| self.add_affine_constant(GroupAffine::zero()))
+    }
+
+    /// Constrains `[k]base` for the bits of `k`, least significant first,
+    /// by doubling and adding from the most significant bit. Takes sixteen
+    /// rows per bit.
+    pub(crate) fn variable_base_mul_bits(
+        &mut self,
+        bits: &[Variable],
+        base: Point,
+    ) -> Point {
+        let mut acc: Option<Point> = None;
+        for bit in bits.iter().rev() {
+            let selected = Point {
+                x: self.arithmetic_gate(|gate| {
+                    gate.witness(*bit, base.x, None).mul(F::one())
+                }),
+                y: self.arithmetic_gate(|gate| {
+                    gate.witness(*bit, base.y, None)
+                        .mul(F::one())
+                        .add(-F::one(), F::zero())
+                        .constant(F::one())
+                }),
+            };
+            acc = Some(match acc {
+                Some(acc) => {
+                    let double = self.point_addition(acc, acc);
+                    self.point_addition(double, selected)
+                }
+                None => selected,
+            });
+        }
+        acc.unwrap_or_else(|| self.add_affine_constant(GroupAffine::zero()))
+    }
+}
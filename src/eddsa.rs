@@ -0,0 +1,351 @@
+//! EdDSA signatures over the embedded curve `P`, verified in circuit.
+//!
+//! The base point `B` is the prime subgroup generator of `P`, keys are
+//! `A = [a]B` and a signature of the field elements `M` is `(R, s)` with
+//! `R = [r]B` and `s = r + c·a` modulo the subgroup order, where the
+//! challenge `c = Poseidon(R.x, R.y, A.x, A.y, M)` is a base field element.
+//! Verification checks `[s]B = R + [c]A` without multiplying by the
+//! cofactor, so public keys must be in the prime order subgroup. [`verify`]
+//! rejects the others, in circuit they are rejected by
+//! [`CircuitBuilder::assert_in_prime_subgroup`], which callers run on keys
+//! not already known to be valid.
+//!
+//! Signing is deterministic: the nonce `r` is the SHA-512 digest of the
+//! secret scalar and the message, reduced modulo the subgroup order.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    curves::EmbeddedCurve,
+    ecc::Point,
+    poseidon::PoseidonParameters,
+};
+use ark_ec::{
+    twisted_edwards_extended::GroupAffine, AffineCurve, ProjectiveCurve,
+};
+use ark_ff::{BigInteger, Field, PrimeField, UniformRand};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+/// Secret signing key. Its `Debug` output does not show the scalar.
+#[derive(derivative::Derivative)]
+#[derivative(Clone(bound = ""), Eq(bound = ""), PartialEq(bound = ""))]
+pub struct SecretKey<P>
+where
+    P: EmbeddedCurve,
+{
+    scalar: P::ScalarField,
+}
+
+impl<P> core::fmt::Debug for SecretKey<P>
+where
+    P: EmbeddedCurve,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SecretKey").finish_non_exhaustive()
+    }
+}
+
+/// Signature of a message.
+#[derive(derivative::Derivative)]
+#[derivative(
+    Clone(bound = ""),
+    Copy(bound = ""),
+    Debug(bound = ""),
+    Eq(bound = ""),
+    PartialEq(bound = "")
+)]
+pub struct Signature<P>
+where
+    P: EmbeddedCurve,
+{
+    /// Commitment to the nonce, `[r]B`.
+    pub r: GroupAffine<P>,
+    /// Response, `r + c·a`.
+    pub s: P::ScalarField,
+}
+
+/// Signature allocated in a circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct SignatureVar {
+    /// Commitment to the nonce.
+    pub r: Point,
+    /// Response, as a base field element.
+    pub s: Variable,
+}
+
+/// The scalar `s` as an element of the larger base field.
+fn scalar_to_base<F, P>(s: P::ScalarField) -> F
+where
+    F: PrimeField,
+    P: EmbeddedCurve<BaseField = F>,
+{
+    F::from_le_bytes_mod_order(&s.into_repr().to_bytes_le())
+}
+
+/// Challenge of a signature, `Poseidon(R.x, R.y, A.x, A.y, M)`.
+pub fn challenge<F, P>(
+    params: &PoseidonParameters<F>,
+    r: &GroupAffine<P>,
+    public_key: &GroupAffine<P>,
+    message: &[F],
+) -> F
+where
+    F: PrimeField,
+    P: EmbeddedCurve<BaseField = F>,
+{
+    let mut inputs = vec![r.x, r.y, public_key.x, public_key.y];
+    inputs.extend_from_slice(message);
+    crate::poseidon::hash(params, &inputs)
+}
+
+impl<P> SecretKey<P>
+where
+    P: EmbeddedCurve,
+{
+    /// Creates a key from its secret scalar.
+    pub fn new(scalar: P::ScalarField) -> Self {
+        Self { scalar }
+    }
+
+    /// Samples a uniformly random key.
+    pub fn generate<R>(rng: &mut R) -> Self
+    where
+        R: CryptoRng + RngCore + ?Sized,
+    {
+        Self::new(P::ScalarField::rand(rng))
+    }
+
+    /// Public key `[a]B`.
+    pub fn public_key(&self) -> GroupAffine<P> {
+        GroupAffine::<P>::prime_subgroup_generator()
+            .mul(self.scalar.into_repr())
+            .into_affine()
+    }
+
+    /// Signs `message`.
+    pub fn sign<F>(
+        &self,
+        params: &PoseidonParameters<F>,
+        message: &[F],
+    ) -> Signature<P>
+    where
+        F: PrimeField,
+        P: EmbeddedCurve<BaseField = F>,
+    {
+        let mut hasher = Sha512::new();
+        hasher.update(self.scalar.into_repr().to_bytes_le());
+        for m in message {
+            hasher.update(m.into_repr().to_bytes_le());
+        }
+        let nonce = P::ScalarField::from_le_bytes_mod_order(&hasher.finalize());
+
+        let r = GroupAffine::<P>::prime_subgroup_generator()
+            .mul(nonce.into_repr())
+            .into_affine();
+        let c = challenge(params, &r, &self.public_key(), message);
+        let c = P::ScalarField::from_le_bytes_mod_order(
+            &c.into_repr().to_bytes_le(),
+        );
+        Signature {
+            r,
+            s: nonce + c * self.scalar,
+        }
+    }
+}
+
+/// Verifies a signature natively, as the gadget does, rejecting public
+/// keys outside of the prime order subgroup.
+pub fn verify<F, P>(
+    params: &PoseidonParameters<F>,
+    public_key: &GroupAffine<P>,
+    message: &[F],
+    signature: &Signature<P>,
+) -> bool
+where
+    F: PrimeField,
+    P: EmbeddedCurve<BaseField = F>,
+{
+    if !public_key.is_on_curve()
+        || !public_key.is_in_correct_subgroup_assuming_on_curve()
+    {
+        return false;
+    }
+    let c = challenge(params, &signature.r, public_key, message);
+    let lhs = GroupAffine::<P>::prime_subgroup_generator()
+        .mul(signature.s.into_repr());
+    let rhs = public_key.into_projective().mul(c.into_repr())
+        + signature.r.into_projective();
+    lhs == rhs
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: EmbeddedCurve<BaseField = F>,
+{
+    /// Allocates a signature, its commitment being constrained to be on the
+    /// curve.
+    pub fn add_signature(
+        &mut self,
+        signature: Option<&Signature<P>>,
+    ) -> SignatureVar {
+        let r = self.add_affine(signature.map(|signature| signature.r));
+        let s = self.add_witness(
+            signature.map(|signature| scalar_to_base::<F, P>(signature.s)),
+        );
+        SignatureVar { r, s }
+    }
+
+    /// Constrains `signature` to be a valid signature of `message` under
+    /// `public_key`, checking `[s]B = R + [c]A`.
+    ///
+    /// `public_key` must be in the prime order subgroup, which is not
+    /// checked here: run [`Self::assert_in_prime_subgroup`] on keys that
+    /// are not constants or otherwise known to be valid.
+    ///
+    /// `s` is decomposed below the subgroup order and `c` below the base
+    /// field modulus, so both have a single encoding. `[s]B` uses the fixed
+    /// base gadget and `[c]A` the variable base one.
+    pub fn eddsa_verify(
+        &mut self,
+        params: &PoseidonParameters<F>,
+        public_key: Point,
+        message: &[Variable],
+        signature: &SignatureVar,
+    ) {
+        let r = signature.r;
+        let mut inputs = vec![r.x, r.y, public_key.x, public_key.y];
+        inputs.extend_from_slice(message);
+        let c = self.poseidon_hash(params, &inputs);
+
+        let s_bits =
+            self.canonical_bits(signature.s, P::ScalarField::characteristic());
+        let lhs = self.fixed_base_mul_bits(
+            &s_bits,
+            GroupAffine::prime_subgroup_generator(),
+        );
+        let c_a = self.variable_base_scalar_mul(c, public_key);
+        let rhs = self.point_addition(r, c_a);
+        self.assert_equal_point(lhs, rhs);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::{EdwardsAffine, EdwardsParameters, Fr as Fs};
+    use ark_ff::FpParameters;
+
+    type P = EdwardsParameters;
+
+    fn poseidon_params() -> PoseidonParameters<Fr> {
+        let rng = &mut ark_std::test_rng();
+        let mut rand_vec = |len| (0..len).map(|_| Fr::rand(rng)).collect();
+        let mds = (0..3).map(|_| rand_vec(3)).collect();
+        let ark = (0..8 + 57).map(|_| rand_vec(3)).collect();
+        PoseidonParameters::new(8, 57, 5, mds, ark)
+    }
+
+    /// Whether the circuit verifying `signature` of `message` is satisfied,
+    /// `s` being replaced by `s_override` when given.
+    fn verifies_in_circuit(
+        params: &PoseidonParameters<Fr>,
+        public_key: EdwardsAffine,
+        message: &[Fr],
+        signature: &Signature<P>,
+        s_override: Option<Fr>,
+    ) -> bool {
+        let mut cs = CircuitBuilder::<Fr, P>::new();
+        let public_key = cs.add_affine(Some(public_key));
+        let message =
+            message.iter().map(|m| cs.add_input(*m)).collect::<Vec<_>>();
+        let signature_var = cs.add_signature(Some(signature));
+        if let Some(s) = s_override {
+            cs.variables.insert(signature_var.s, s);
+        }
+        cs.eddsa_verify(params, public_key, &message, &signature_var);
+        cs.check_satisfied().is_ok()
+    }
+
+    #[test]
+    fn gadget_agrees_with_native_verification() {
+        let rng = &mut ark_std::test_rng();
+        let params = poseidon_params();
+        let key = SecretKey::<P>::generate(rng);
+        let public_key = key.public_key();
+        let message = [Fr::from(1u64), Fr::from(2u64)];
+        let signature = key.sign(&params, &message);
+
+        assert!(verify(&params, &public_key, &message, &signature));
+        assert!(verifies_in_circuit(
+            &params, public_key, &message, &signature, None
+        ));
+
+        let wrong_message = [Fr::from(1u64), Fr::from(3u64)];
+        assert!(!verify(&params, &public_key, &wrong_message, &signature));
+        assert!(!verifies_in_circuit(
+            &params,
+            public_key,
+            &wrong_message,
+            &signature,
+            None
+        ));
+
+        let mut wrong_r = signature;
+        wrong_r.r += &EdwardsAffine::prime_subgroup_generator();
+        assert!(!verify(&params, &public_key, &message, &wrong_r));
+        assert!(!verifies_in_circuit(
+            &params, public_key, &message, &wrong_r, None
+        ));
+
+        // s + l is the same scalar, but not the canonical encoding.
+        let order = Fr::from_le_bytes_mod_order(
+            &<Fs as PrimeField>::Params::MODULUS.to_bytes_le(),
+        );
+        let s = scalar_to_base::<Fr, P>(signature.s);
+        assert!(verifies_in_circuit(
+            &params,
+            public_key,
+            &message,
+            &signature,
+            Some(s)
+        ));
+        assert!(!verifies_in_circuit(
+            &params,
+            public_key,
+            &message,
+            &signature,
+            Some(s + order)
+        ));
+    }
+
+    #[test]
+    fn keys_outside_the_subgroup_are_rejected() {
+        let rng = &mut ark_std::test_rng();
+        let params = poseidon_params();
+        let key = SecretKey::<P>::generate(rng);
+        let message = [Fr::from(7u64)];
+        let signature = key.sign(&params, &message);
+
+        // A point of order 2 added to the key.
+        let torsion = EdwardsAffine::new(Fr::from(0u64), -Fr::from(1u64));
+        let public_key = key.public_key() + torsion;
+        assert!(!verify(&params, &public_key, &message, &signature));
+
+        let mut cs = CircuitBuilder::<Fr, P>::new();
+        let point = cs.add_affine(Some(public_key));
+        cs.assert_in_prime_subgroup(point);
+        assert!(cs.check_satisfied().is_err());
+
+        let mut cs = CircuitBuilder::<Fr, P>::new();
+        let point = cs.add_affine(Some(key.public_key()));
+        cs.assert_in_prime_subgroup(point);
+        assert_eq!(cs.check_satisfied(), Ok(()));
+    }
+
+    #[test]
+    fn secret_key_debug_is_redacted() {
+        let key = SecretKey::<P>::new(Fs::from(42u64));
+        assert_eq!(format!("{:?}", key), "SecretKey { .. }");
+    }
+}
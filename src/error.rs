@@ -0,0 +1,145 @@
+//! A collection of all possible errors encountered in PLONK.
+
+use crate::constraint_system::Variable;
+use ark_ff::PrimeField;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::PolynomialCommitment;
+use core::fmt;
+
+/// Defines all possible errors that can be encountered in PLONK.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Error {
+    // FFT errors
+    /// This error occurs when an error triggers on any of the fft module
+    /// functions.
+    InvalidEvalDomainSize {
+        /// Log size of the group
+        log_size_of_group: u32,
+        /// Two adicity generated
+        adicity: u32,
+    },
+
+    // Permutation errors
+    /// This error occurs when the cosets `H`, `K1·H`, `K2·H` and `K3·H` used
+    /// by the permutation argument are not disjoint on the evaluation domain.
+    PermutationCosetsOverlap,
+
+    // Prover/Verifier errors
+    /// This error occurs when a proof verification fails.
+    ProofVerificationError,
+    /// This error occurs when the circuit is not provided with all of the
+    /// required inputs.
+    CircuitInputsNotFound,
+    /// This error occurs when we want to verify a Proof but the pi_constructor
+    /// attribute is uninitialized.
+    UninitializedPIGenerator,
+    /// PublicInput serialization error
+    InvalidPublicInputBytes,
+    /// This error occurs when a public input is inserted at a position that
+    /// already holds one.
+    DuplicatePublicInput(usize),
+    /// This error occurs when the Prover structure already contains a
+    /// preprocessed circuit inside, but you call preprocess again.
+    CircuitAlreadyPreprocessed,
+    /// This error occurs when a variable synthesized without a value is
+    /// needed to prove.
+    MissingWitness(Variable),
+    /// This error occurs when an assignment does not give a value to exactly
+    /// the witness variables of the circuit.
+    AssignmentMismatch,
+
+    // Preprocessing errors
+    /// This error occurs when an error triggers during the preprocessing
+    /// stage.
+    MismatchedPolyLen,
+    /// This error occurs when a circuit uses a gate that the chosen backend
+    /// can not verify.
+    UnsupportedGate(String),
+    /// This error occurs when a circuit description can not be imported.
+    InvalidCircuitDescription,
+    /// This error occurs when the preprocessed keys can not be read from or
+    /// written to a [`KeyCache`](crate::key_cache::KeyCache).
+    KeyCache(String),
+
+    // Plookup errors
+    /// Query element not found in lookup table
+    ElementNotIndexed,
+
+    /// Polynomial Commitment errors
+    PCError {
+        /// Polynomial Commitment errors
+        error: String,
+    },
+}
+
+/// Convert an ark_poly_commit error
+pub fn to_pc_error<F, PC>(error: PC::Error) -> Error
+where
+    F: PrimeField,
+    PC: PolynomialCommitment<F, DensePolynomial<F>>,
+{
+    Error::PCError {
+        error: format!("Polynomial Commitment Error: {:?}", error),
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidEvalDomainSize {
+                log_size_of_group,
+                adicity,
+            } => write!(
+                f,
+                "Log-size of the EvaluationDomain group > TWO_ADICITY\
+                Size: {:?} > TWO_ADICITY = {:?}",
+                log_size_of_group, adicity
+            ),
+            Self::PermutationCosetsOverlap => {
+                write!(f, "permutation cosets overlap on this domain")
+            }
+            Self::ProofVerificationError => {
+                write!(f, "proof verification failed")
+            }
+            Self::CircuitInputsNotFound => {
+                write!(f, "circuit inputs not found")
+            }
+            Self::UninitializedPIGenerator => {
+                write!(f, "PI generator uninitialized")
+            }
+            Self::InvalidPublicInputBytes => {
+                write!(f, "invalid public input bytes")
+            }
+            Self::DuplicatePublicInput(pos) => {
+                write!(f, "a public input is already set at position {}", pos)
+            }
+            Self::CircuitAlreadyPreprocessed => {
+                write!(f, "circuit has already been preprocessed")
+            }
+            Self::MissingWitness(var) => {
+                write!(f, "no value was assigned to variable {}", var)
+            }
+            Self::AssignmentMismatch => write!(
+                f,
+                "the assignment does not match the witness variables of the \
+                circuit"
+            ),
+            Self::MismatchedPolyLen => {
+                write!(f, "the length of the wires is not the same")
+            }
+            Self::UnsupportedGate(gate) => {
+                write!(f, "{} gates are not supported", gate)
+            }
+            Self::InvalidCircuitDescription => {
+                write!(f, "invalid circuit description")
+            }
+            Self::KeyCache(error) => write!(f, "key cache error: {}", error),
+            Self::ElementNotIndexed => {
+                write!(f, "element not found in lookup table")
+            }
+            Self::PCError { error } => write!(f, "{:?}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,511 @@
+//! Expressions over [`Variable`]s built with the usual arithmetic operators
+//! and lowered into width-4 arithmetic gates.
+//!
+//! ```ignore
+//! let d = cs.eval(&((Expr::from(a) + b) * c - Expr::constant(F::one())));
+//! cs.constrain_zero(&(Expr::from(d) * F::from(2u64) - a));
+//! ```
+//!
+//! Building an [`Expr`] does not touch the [`CircuitBuilder`]: products of
+//! two non-constant expressions are kept as a tree until lowering, where
+//! each side is reduced to a single scaled variable and the product is
+//! placed on the `q_m` selector. Linear terms fill the `q_l`, `q_r` and `q_4`
+//! slots of the same gate, so a new variable is only allocated when an
+//! expression does not fit on one gate.
+use crate::constraint_system::{CircuitBuilder, Variable};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use core::ops::{Add, Mul, Neg, Sub};
+
+/// Arithmetic expression over circuit variables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expr<F>
+where
+    F: PrimeField,
+{
+    /// A variable of the circuit.
+    Var(Variable),
+    /// A constant, placed on `q_c`.
+    Const(F),
+    /// Sum of two expressions.
+    Add(Box<Expr<F>>, Box<Expr<F>>),
+    /// Expression multiplied by a constant.
+    Scale(Box<Expr<F>>, F),
+    /// Product of two expressions.
+    Mul(Box<Expr<F>>, Box<Expr<F>>),
+}
+
+impl<F> Expr<F>
+where
+    F: PrimeField,
+{
+    /// Constant expression.
+    pub fn constant(value: F) -> Self {
+        Expr::Const(value)
+    }
+}
+
+impl<F> From<Variable> for Expr<F>
+where
+    F: PrimeField,
+{
+    fn from(var: Variable) -> Self {
+        Expr::Var(var)
+    }
+}
+
+impl<F> Neg for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        self * -F::one()
+    }
+}
+
+impl<F> Add for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Expr::Add(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F> Sub for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl<F> Mul for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Expr::Mul(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F> Mul<F> for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: F) -> Self {
+        Expr::Scale(Box::new(self), rhs)
+    }
+}
+
+// Mixed operands: a `Variable` or a constant on either side of an `Expr`.
+macro_rules! impl_mixed_op {
+    ($trait:ident, $method:ident) => {
+        impl<F> $trait<Variable> for Expr<F>
+        where
+            F: PrimeField,
+        {
+            type Output = Self;
+
+            fn $method(self, rhs: Variable) -> Self {
+                self.$method(Expr::Var(rhs))
+            }
+        }
+
+        impl<F> $trait<Expr<F>> for Variable
+        where
+            F: PrimeField,
+        {
+            type Output = Expr<F>;
+
+            fn $method(self, rhs: Expr<F>) -> Expr<F> {
+                Expr::Var(self).$method(rhs)
+            }
+        }
+    };
+}
+
+impl_mixed_op!(Add, add);
+impl_mixed_op!(Sub, sub);
+impl_mixed_op!(Mul, mul);
+
+impl<F> Add<F> for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn add(self, rhs: F) -> Self {
+        self + Expr::Const(rhs)
+    }
+}
+
+impl<F> Sub<F> for Expr<F>
+where
+    F: PrimeField,
+{
+    type Output = Self;
+
+    fn sub(self, rhs: F) -> Self {
+        self + Expr::Const(-rhs)
+    }
+}
+
+/// Lowered form of an expression: at most one product, merged linear terms
+/// and a constant, i.e. what a single gate with enough wires could hold.
+#[derive(Clone, Debug)]
+struct Quadratic<F>
+where
+    F: PrimeField,
+{
+    product: Option<(Variable, Variable, F)>,
+    terms: Vec<(Variable, F)>,
+    constant: F,
+}
+
+impl<F> Quadratic<F>
+where
+    F: PrimeField,
+{
+    fn constant(constant: F) -> Self {
+        Self {
+            product: None,
+            terms: Vec::new(),
+            constant,
+        }
+    }
+
+    fn is_constant(&self) -> bool {
+        self.product.is_none() && self.terms.is_empty()
+    }
+
+    fn add_term(&mut self, var: Variable, coeff: F) {
+        match self.terms.iter_mut().find(|(v, _)| *v == var) {
+            Some(term) => term.1 += coeff,
+            None => self.terms.push((var, coeff)),
+        }
+        self.terms.retain(|(_, coeff)| !coeff.is_zero());
+    }
+
+    fn scale(mut self, k: F) -> Self {
+        if k.is_zero() {
+            return Self::constant(F::zero());
+        }
+        if let Some((_, _, coeff)) = self.product.as_mut() {
+            *coeff *= k;
+        }
+        self.terms.iter_mut().for_each(|(_, coeff)| *coeff *= k);
+        self.constant *= k;
+        self
+    }
+
+    /// Removes and returns the coefficient of `var`.
+    fn take_term(&mut self, var: Variable) -> F {
+        let mut coeff = F::zero();
+        self.terms.retain(|(v, c)| {
+            if *v == var {
+                coeff += c;
+            }
+            *v != var
+        });
+        coeff
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Adds the gates computing `expr` and returns the variable holding its
+    /// value. A bare variable is returned as is, without adding a gate.
+    pub fn eval(&mut self, expr: &Expr<F>) -> Variable {
+        let q = self.lower(expr);
+        self.materialize(q)
+    }
+
+    /// Constrains `expr` to be zero.
+    pub fn constrain_zero(&mut self, expr: &Expr<F>) {
+        let zero = self.zero_var();
+        let mut q = self.lower(expr);
+        let (a, b, q_m) = match q.product {
+            Some(product) => product,
+            None => return self.constrain_linear(q.terms, q.constant),
+        };
+        q.product = None;
+
+        // The output wire is free, leaving two slots for linear terms.
+        let q_l = q.take_term(a);
+        let q_r = q.take_term(b);
+        let mut slots = self.fit_terms(q.terms, 2);
+        slots.resize(2, (zero, F::zero()));
+        self.arithmetic_gate(|gate| {
+            gate.witness(a, b, Some(slots[0].0))
+                .mul(q_m)
+                .add(q_l, q_r)
+                .out(slots[0].1)
+                .fan_in_3(slots[1].1, slots[1].0)
+                .constant(q.constant)
+        });
+    }
+
+    /// Reduces `expr` to a single product plus linear terms, adding gates for
+    /// the sub-expressions that do not fit.
+    fn lower(&mut self, expr: &Expr<F>) -> Quadratic<F> {
+        match expr {
+            Expr::Var(var) => {
+                let mut q = Quadratic::constant(F::zero());
+                q.add_term(*var, F::one());
+                q
+            }
+            Expr::Const(value) => Quadratic::constant(*value),
+            Expr::Scale(expr, k) => self.lower(expr).scale(*k),
+            Expr::Add(lhs, rhs) => {
+                let mut lhs = self.lower(lhs);
+                let mut rhs = self.lower(rhs);
+                if lhs.product.is_some() && rhs.product.is_some() {
+                    let constant = rhs.constant;
+                    rhs.constant = F::zero();
+                    let var = self.materialize(rhs);
+                    rhs = Quadratic::constant(constant);
+                    rhs.add_term(var, F::one());
+                }
+                lhs.product = lhs.product.or(rhs.product);
+                for (var, coeff) in rhs.terms {
+                    lhs.add_term(var, coeff);
+                }
+                lhs.constant += rhs.constant;
+                lhs
+            }
+            Expr::Mul(lhs, rhs) => {
+                let lhs = self.lower(lhs);
+                let rhs = self.lower(rhs);
+                if lhs.is_constant() {
+                    return rhs.scale(lhs.constant);
+                }
+                if rhs.is_constant() {
+                    return lhs.scale(rhs.constant);
+                }
+
+                // (k_x·x + c_x)·(k_y·y + c_y)
+                let ((x, k_x), c_x) = self.linearize(lhs);
+                let ((y, k_y), c_y) = self.linearize(rhs);
+                let mut q = Quadratic::constant(c_x * c_y);
+                q.product = Some((x, y, k_x * k_y));
+                q.add_term(x, k_x * c_y);
+                q.add_term(y, k_y * c_x);
+                q
+            }
+        }
+    }
+
+    /// Rewrites a non-constant `q` as `k·var + c`, adding gates when it has
+    /// a product or more than one term.
+    fn linearize(&mut self, mut q: Quadratic<F>) -> ((Variable, F), F) {
+        let constant = q.constant;
+        if q.product.is_none() && q.terms.len() == 1 {
+            return (q.terms[0], constant);
+        }
+        q.constant = F::zero();
+        ((self.materialize(q), F::one()), constant)
+    }
+
+    /// Adds the gates computing `q` and returns its output variable.
+    fn materialize(&mut self, mut q: Quadratic<F>) -> Variable {
+        let zero = self.zero_var();
+        let (a, b, q_m) = match q.product {
+            Some(product) => product,
+            None if q.terms.is_empty() => {
+                let constant = q.constant;
+                return self.arithmetic_gate(|gate| {
+                    gate.witness(zero, zero, None).constant(constant)
+                });
+            }
+            None => return self.fold_linear(q.terms, q.constant),
+        };
+
+        let q_l = q.take_term(a);
+        let q_r = q.take_term(b);
+        let slot = self
+            .fit_terms(q.terms, 1)
+            .pop()
+            .unwrap_or((zero, F::zero()));
+        self.arithmetic_gate(|gate| {
+            gate.witness(a, b, None)
+                .mul(q_m)
+                .add(q_l, q_r)
+                .fan_in_3(slot.1, slot.0)
+                .constant(q.constant)
+        })
+    }
+
+    /// Brings `terms` down to at most `slots` terms, folding the tail into
+    /// one variable when there are too many.
+    fn fit_terms(
+        &mut self,
+        mut terms: Vec<(Variable, F)>,
+        slots: usize,
+    ) -> Vec<(Variable, F)> {
+        if terms.len() > slots {
+            let rest = terms.split_off(slots - 1);
+            let folded = self.fold_linear(rest, F::zero());
+            terms.push((folded, F::one()));
+        }
+        terms
+    }
+
+    /// Returns a variable equal to `sum(coeff * var) + constant`, reusing the
+    /// variable itself when the combination is a single unscaled term.
+    /// `terms` must not be empty.
+    pub(crate) fn fold_linear(
+        &mut self,
+        terms: Vec<(Variable, F)>,
+        constant: F,
+    ) -> Variable {
+        debug_assert!(!terms.is_empty(), "cannot fold an empty combination");
+        if terms.len() == 1 && terms[0].1.is_one() && constant.is_zero() {
+            return terms[0].0;
+        }
+        let zero = self.zero_var();
+        let mut terms = terms.into_iter();
+        let mut acc: Option<Variable> = None;
+        let mut constant = Some(constant);
+        loop {
+            // The accumulator takes the left wire, freeing one slot.
+            let mut slots = Vec::with_capacity(3);
+            if let Some(acc) = acc {
+                slots.push((acc, F::one()));
+            }
+            slots.extend(terms.by_ref().take(3 - slots.len()));
+            let remaining = terms.len();
+            if slots.is_empty() {
+                break;
+            }
+            slots.resize(3, (zero, F::zero()));
+            let q_c = constant.take().unwrap_or_else(F::zero);
+            let out = self.arithmetic_gate(|gate| {
+                gate.witness(slots[0].0, slots[1].0, None)
+                    .add(slots[0].1, slots[1].1)
+                    .fan_in_3(slots[2].1, slots[2].0)
+                    .constant(q_c)
+            });
+            acc = Some(out);
+            if remaining == 0 {
+                break;
+            }
+        }
+        acc.unwrap_or(zero)
+    }
+
+    /// Constrains `sum(coeff * var) + constant = 0`, the last gate of the
+    /// chain checking the equation instead of producing an output.
+    pub(crate) fn constrain_linear(
+        &mut self,
+        terms: Vec<(Variable, F)>,
+        constant: F,
+    ) {
+        let zero = self.zero_var();
+        let (head, last) = match terms.len() > 3 {
+            true => {
+                let split = terms.len() - 2;
+                let mut head = terms;
+                let last = head.split_off(split);
+                (Some(head), last)
+            }
+            false => (None, terms),
+        };
+
+        let mut slots = Vec::with_capacity(3);
+        let mut constant = constant;
+        if let Some(head) = head {
+            slots.push((self.fold_linear(head, constant), F::one()));
+            constant = F::zero();
+        }
+        slots.extend(last);
+        slots.resize(3, (zero, F::zero()));
+        self.arithmetic_gate(|gate| {
+            gate.witness(slots[0].0, slots[1].0, Some(zero))
+                .add(slots[0].1, slots[1].1)
+                .fan_in_3(slots[2].1, slots[2].0)
+                .constant(constant)
+                .out(F::zero())
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+
+    fn inputs(
+        values: &[u64],
+    ) -> (CircuitBuilder<Fr, EdwardsParameters>, Vec<Variable>) {
+        let mut cs = CircuitBuilder::new();
+        let vars = values
+            .iter()
+            .map(|value| cs.add_input(Fr::from(*value)))
+            .collect();
+        (cs, vars)
+    }
+
+    #[test]
+    fn eval_computes_the_expression() {
+        let (mut cs, v) = inputs(&[2, 3, 5, 7, 11]);
+        let one = Fr::from(1u64);
+
+        let cases: Vec<(Expr<Fr>, u64)> = vec![
+            ((Expr::from(v[0]) + v[1]) * v[2] - one, 24),
+            (Expr::from(v[0]) * v[1] + Expr::from(v[2]) * v[3], 41),
+            (
+                Expr::from(v[0]) + v[1] + v[2] + v[3] + v[4] + Fr::from(4u64),
+                32,
+            ),
+            ((Expr::from(v[0]) * v[1]) * (Expr::from(v[2]) * v[3]), 210),
+            (
+                Expr::from(v[4]) * Fr::from(3u64) - v[0] * Expr::from(v[1]),
+                27,
+            ),
+            (Expr::constant(Fr::from(9u64)), 9),
+        ];
+        for (expr, expected) in cases {
+            let var = cs.eval(&expr);
+            assert_eq!(cs.variables[&var], Fr::from(expected), "{:?}", expr);
+        }
+        assert_eq!(cs.check_satisfied(), Ok(()));
+    }
+
+    #[test]
+    fn eval_of_a_variable_adds_no_gate() {
+        let (mut cs, v) = inputs(&[2]);
+        let n = cs.circuit_size();
+        assert_eq!(cs.eval(&Expr::from(v[0])), v[0]);
+        assert_eq!(cs.circuit_size(), n);
+    }
+
+    #[test]
+    fn constrain_zero_checks_the_expression() {
+        let (mut cs, v) = inputs(&[2, 3, 5, 7, 11]);
+        // 2·3 + 5 + 7 + 11 - 29 = 0, the linear terms overflowing the gate.
+        cs.constrain_zero(
+            &(Expr::from(v[0]) * v[1] + v[2] + v[3] + v[4] - Fr::from(29u64)),
+        );
+        cs.constrain_zero(&(Expr::from(v[0]) + v[1] - v[2]));
+        assert_eq!(cs.check_satisfied(), Ok(()));
+
+        cs.constrain_zero(&(Expr::from(v[0]) * v[1] - v[3]));
+        assert!(cs.check_satisfied().is_err());
+    }
+}
@@ -0,0 +1,286 @@
+//! Keccak-f[1600] and the Keccak sponge over byte variables, built on lookup
+//! tables.
+//!
+//! Lanes are 64-bit words of sixteen 4-bit limbs, see [`crate::limbs`], and
+//! bytes enter and leave them little-endian. The sponge pads with the
+//! original Keccak rule, `0x01 0x00 ... 0x80`, as Ethereum's Keccak-256 does,
+//! not with the SHA-3 domain byte.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    limbs::LimbTables,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+
+/// A 64-bit lane as sixteen 4-bit limbs, least significant first.
+type Lane = [Variable; 16];
+
+/// Round constants of ι.
+const RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Left rotation of ρ for the lane `x + 5y`.
+const RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8,
+    18, 2, 61, 56, 14,
+];
+
+/// Rate of Keccak-256, in bytes.
+pub const KECCAK_256_RATE: usize = 136;
+
+/// Keccak sponge over byte variables of a circuit.
+///
+/// Bytes are range checked when absorbed. Once squeezing has started, no
+/// more bytes can be absorbed.
+pub struct KeccakSponge {
+    state: [Lane; 25],
+    /// Rate in bytes.
+    rate: usize,
+    /// Limbs of the bytes absorbed since the last permutation.
+    pending: Vec<[Variable; 2]>,
+    /// Next byte of the rate to squeeze, once the input has been padded.
+    squeezed: Option<usize>,
+    tables: LimbTables,
+}
+
+impl KeccakSponge {
+    /// Creates a sponge with a rate of `rate` bytes, adding the table rows
+    /// it needs to the lookup table of `cs`.
+    ///
+    /// # Panics
+    ///
+    /// If the rate is not a positive number of lanes below the state size.
+    pub fn new<F, P>(cs: &mut CircuitBuilder<F, P>, rate: usize) -> Self
+    where
+        F: PrimeField,
+        P: TEModelParameters<BaseField = F>,
+    {
+        assert!(
+            rate > 0 && rate.is_multiple_of(8) && rate < 200,
+            "the rate must be a number of lanes below the state size"
+        );
+        Self {
+            state: [[cs.zero_var(); 16]; 25],
+            rate,
+            pending: Vec::with_capacity(rate),
+            squeezed: None,
+            tables: cs.limb_tables(),
+        }
+    }
+
+    /// Absorbs `bytes`, permuting the state whenever a block is full.
+    ///
+    /// # Panics
+    ///
+    /// If the sponge is already squeezing.
+    pub fn absorb<F, P>(
+        &mut self,
+        cs: &mut CircuitBuilder<F, P>,
+        bytes: &[Variable],
+    ) where
+        F: PrimeField,
+        P: TEModelParameters<BaseField = F>,
+    {
+        assert!(self.squeezed.is_none(), "the sponge is already squeezing");
+        for byte in bytes {
+            let limbs = cs.byte_limbs(&self.tables, *byte);
+            self.pending.push(limbs);
+            if self.pending.len() == self.rate {
+                self.absorb_block(cs);
+            }
+        }
+    }
+
+    /// Squeezes `n` bytes, padding the input first if needed.
+    pub fn squeeze<F, P>(
+        &mut self,
+        cs: &mut CircuitBuilder<F, P>,
+        n: usize,
+    ) -> Vec<Variable>
+    where
+        F: PrimeField,
+        P: TEModelParameters<BaseField = F>,
+    {
+        if self.squeezed.is_none() {
+            let mut padding = vec![0u8; self.rate - self.pending.len()];
+            padding[0] |= 0x01;
+            *padding.last_mut().expect("a block is never full here") |= 0x80;
+            for byte in padding {
+                let lo = cs.limb_constant(&mut self.tables, byte as u64 & 15);
+                let hi = cs.limb_constant(&mut self.tables, byte as u64 >> 4);
+                self.pending.push([lo, hi]);
+            }
+            self.absorb_block(cs);
+            self.squeezed = Some(0);
+        }
+
+        let mut out = Vec::with_capacity(n);
+        let mut pos = self.squeezed.unwrap_or_default();
+        while out.len() < n {
+            if pos == self.rate {
+                cs.keccak_permutation(&mut self.tables, &mut self.state);
+                pos = 0;
+            }
+            let lane = &self.state[pos / 8];
+            let (lo, hi) = (lane[2 * (pos % 8)], lane[2 * (pos % 8) + 1]);
+            out.push(cs.limbs_byte(lo, hi));
+            pos += 1;
+        }
+        self.squeezed = Some(pos);
+        out
+    }
+
+    /// XORs the pending block into the rate and permutes.
+    fn absorb_block<F, P>(&mut self, cs: &mut CircuitBuilder<F, P>)
+    where
+        F: PrimeField,
+        P: TEModelParameters<BaseField = F>,
+    {
+        for (i, bytes) in self.pending.chunks(8).enumerate() {
+            let mut lane = [cs.zero_var(); 16];
+            for (j, limbs) in bytes.iter().enumerate() {
+                lane[2 * j] = limbs[0];
+                lane[2 * j + 1] = limbs[1];
+            }
+            self.state[i] = cs.word_xor(&self.tables, &self.state[i], &lane);
+        }
+        self.pending.clear();
+        cs.keccak_permutation(&mut self.tables, &mut self.state);
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Constrains the Keccak-256 digest of `bytes`, as computed by
+    /// `sha3::Keccak256`.
+    pub fn keccak256(&mut self, bytes: &[Variable]) -> [Variable; 32] {
+        let mut sponge = KeccakSponge::new(self, KECCAK_256_RATE);
+        sponge.absorb(self, bytes);
+        let mut digest = [self.zero_var; 32];
+        digest.copy_from_slice(&sponge.squeeze(self, 32));
+        digest
+    }
+
+    /// Applies Keccak-f[1600] to `state`, lane `(x, y)` being `x + 5y`.
+    fn keccak_permutation(
+        &mut self,
+        tables: &mut LimbTables,
+        state: &mut [Lane; 25],
+    ) {
+        let zero = self.zero_var;
+        for rc in RC {
+            // θ
+            let mut c = [[zero; 16]; 5];
+            for (x, column) in c.iter_mut().enumerate() {
+                *column = state[x];
+                for y in 1..5 {
+                    *column = self.word_xor(tables, column, &state[x + 5 * y]);
+                }
+            }
+            for x in 0..5 {
+                let rotated = self.word_rotations(
+                    tables,
+                    &c[(x + 1) % 5],
+                    &[(63, false)],
+                );
+                let d = self.word_xor(tables, &c[(x + 4) % 5], &rotated[0]);
+                for y in 0..5 {
+                    state[x + 5 * y] =
+                        self.word_xor(tables, &state[x + 5 * y], &d);
+                }
+            }
+
+            // ρ and π: B[y, 2x + 3y] = rot(A[x, y], r[x, y])
+            let mut b = [[zero; 16]; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    let lane = &state[x + 5 * y];
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = match RHO[x + 5 * y] {
+                        0 => *lane,
+                        r => self.word_rotations(
+                            tables,
+                            lane,
+                            &[(64 - r, false)],
+                        )[0],
+                    };
+                }
+            }
+
+            // χ
+            for y in 0..5 {
+                for x in 0..5 {
+                    let not_next = self.word_not(&b[(x + 1) % 5 + 5 * y]);
+                    let and = self.word_and(
+                        tables,
+                        &not_next,
+                        &b[(x + 2) % 5 + 5 * y],
+                    );
+                    state[x + 5 * y] =
+                        self.word_xor(tables, &b[x + 5 * y], &and);
+                }
+            }
+
+            // ι
+            let rc = self.word_constant(tables, rc);
+            state[0] = self.word_xor(tables, &state[0], &rc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::limbs::to_u64;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::rand::RngCore;
+    use sha3::{Digest, Keccak256};
+
+    #[test]
+    fn digest_matches_sha3_around_the_rate() {
+        let rng = &mut ark_std::test_rng();
+        for len in [0, 135, 136, 137, 300] {
+            let mut bytes = vec![0u8; len];
+            rng.fill_bytes(&mut bytes);
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let vars = bytes
+                .iter()
+                .map(|byte| cs.add_input(Fr::from(*byte as u64)))
+                .collect::<Vec<_>>();
+            let digest = cs
+                .keccak256(&vars)
+                .iter()
+                .map(|byte| to_u64(cs.value(*byte).unwrap()) as u8)
+                .collect::<Vec<_>>();
+            assert_eq!(digest, Keccak256::digest(&bytes).to_vec(), "{}", len);
+            assert_eq!(cs.check_satisfied(), Ok(()), "{}", len);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! On-disk cache of preprocessed keys, keyed by a digest of the circuit.
+//!
+//! A [`ProverKey`] holds every selector polynomial together with its
+//! evaluations over the 8n coset, and computing it dominates the setup of
+//! large circuits. [`Prover::preprocess_cached`](crate::prover::Prover)
+//! writes the key once and reads the file back on later runs, seeding the
+//! transcript from the stored [`PlonkVerifierKey`] instead of committing to
+//! the selectors again.
+//!
+//! Entries are named after a digest of the circuit, the commitment key and
+//! the types they were computed with, so one directory can be shared by
+//! several circuits, curves and SRSs. They are memory mapped and the keys
+//! deserialized straight from the mapping, so loading never holds a copy of
+//! the file next to the keys.
+use crate::{
+    commitment::HomomorphicCommitment,
+    constraint_system::CircuitBuilder,
+    proof_system::{PlonkVerifierKey, ProverKey},
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use blake2::{Blake2s, Digest};
+use memmap2::Mmap;
+use std::{
+    any::type_name,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Magic bytes opening every cache entry.
+const MAGIC: &[u8; 4] = b"pkey";
+
+/// Version of the entry layout, bumped whenever the serialization of the
+/// keys changes so old entries are treated as stale.
+const VERSION: u32 = 2;
+
+/// Length of the entry header: magic, version, circuit digest and body
+/// checksum.
+const HEADER_LEN: usize = 4 + 4 + 32 + 32;
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Digest of everything preprocessing depends on: selectors, wiring,
+    /// lookup table, the commitment key and the field, curve and commitment
+    /// scheme types. Witness values, public input values, namespaces and
+    /// variable names are left out.
+    ///
+    /// The whole commitment key is hashed, which costs far less than
+    /// committing to the selectors with it.
+    pub fn digest<PC>(&self, commit_key: &PC::CommitterKey) -> [u8; 32]
+    where
+        PC: HomomorphicCommitment<F>,
+    {
+        let mut desc = self.export();
+        desc.pi_values.clear();
+        desc.region_paths.clear();
+        desc.region_starts.clear();
+        desc.region_ends.clear();
+
+        let mut circuit = Vec::new();
+        desc.serialize(&mut circuit)
+            .expect("serializing to a vector can not fail");
+        let mut key = Vec::new();
+        commit_key
+            .serialize(&mut key)
+            .expect("serializing to a vector can not fail");
+
+        let mut hasher = Blake2s::new();
+        hasher.update(b"plonk circuit digest");
+        for name in [type_name::<F>(), type_name::<P>(), type_name::<PC>()] {
+            hasher.update((name.len() as u64).to_le_bytes());
+            hasher.update(name.as_bytes());
+        }
+        for bytes in [&circuit, &key] {
+            hasher.update((bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Directory of preprocessed keys, one file per circuit digest.
+#[derive(Clone, Debug)]
+pub struct KeyCache {
+    dir: PathBuf,
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+impl KeyCache {
+    /// Opens the cache stored in `dir`, creating the directory if needed.
+    pub fn new<D: AsRef<Path>>(dir: D) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Path of the entry of a circuit.
+    pub fn path(&self, digest: &[u8; 32]) -> PathBuf {
+        let name = digest
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        self.dir.join(name).with_extension("pkey")
+    }
+
+    /// Loads the keys of the circuit with the given digest.
+    ///
+    /// Returns `None` when there is no entry, or when its header does not
+    /// match the digest or the current layout version, in which case the
+    /// entry is stale and should be computed again. An entry whose body
+    /// does not match its checksum, or does not hold valid keys, is an
+    /// error.
+    #[allow(clippy::type_complexity)] // NOTE: The pair of keys is clearer inline.
+    pub fn load<F, PC>(
+        &self,
+        digest: &[u8; 32],
+    ) -> io::Result<Option<(ProverKey<F>, PlonkVerifierKey<F, PC>)>>
+    where
+        F: PrimeField,
+        PC: HomomorphicCommitment<F>,
+    {
+        let file = match fs::File::open(self.path(digest)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // SAFETY: `store` never writes an entry in place, it renames a new
+        // file over it, so the mapped file is not modified while in use.
+        let entry = unsafe { Mmap::map(&file)? };
+        if entry.len() < HEADER_LEN
+            || &entry[0..4] != MAGIC
+            || entry[4..8] != VERSION.to_le_bytes()
+            || &entry[8..40] != digest
+        {
+            return Ok(None);
+        }
+
+        let mut body = &entry[HEADER_LEN..];
+        if entry[40..HEADER_LEN] != checksum(body) {
+            return Err(invalid("checksum mismatch"));
+        }
+        let verifier_key =
+            PlonkVerifierKey::deserialize_uncompressed(&mut body)
+                .map_err(|_| invalid("corrupted verifier key"))?;
+        let prover_key = ProverKey::deserialize_uncompressed(&mut body)
+            .map_err(|_| invalid("corrupted prover key"))?;
+        if !body.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+        Ok(Some((prover_key, verifier_key)))
+    }
+
+    /// Stores the keys of the circuit with the given digest, replacing any
+    /// previous entry.
+    pub fn store<F, PC>(
+        &self,
+        digest: &[u8; 32],
+        prover_key: &ProverKey<F>,
+        verifier_key: &PlonkVerifierKey<F, PC>,
+    ) -> io::Result<()>
+    where
+        F: PrimeField,
+        PC: HomomorphicCommitment<F>,
+    {
+        let mut body = Vec::new();
+        verifier_key
+            .serialize_uncompressed(&mut body)
+            .map_err(|_| invalid("could not serialize verifier key"))?;
+        prover_key
+            .serialize_uncompressed(&mut body)
+            .map_err(|_| invalid("could not serialize prover key"))?;
+
+        // Entries are never written in place: a reader sees either the old
+        // entry or the new one.
+        let path = self.path(digest);
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        {
+            let mut file = fs::File::create(&tmp)?;
+            file.write_all(MAGIC)?;
+            file.write_all(&VERSION.to_le_bytes())?;
+            file.write_all(digest)?;
+            file.write_all(&checksum(&body))?;
+            file.write_all(&body)?;
+            file.sync_all()?;
+        }
+        fs::rename(tmp, path)
+    }
+}
+
+/// Checksum of an entry body.
+fn checksum(body: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2s::new();
+    hasher.update(b"plonk key cache entry");
+    hasher.update(body);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment::KZG10, error::Error, prover::Prover, verifier::Verifier,
+    };
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_poly_commit::PolynomialCommitment;
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+    };
+
+    type PC = KZG10<Bls12_381>;
+
+    /// Counts the bytes allocated and not yet freed by the current thread,
+    /// and the peak of that count, so a test can see what one call holds
+    /// at once.
+    struct Counting;
+
+    thread_local! {
+        static LIVE: Cell<usize> = const { Cell::new(0) };
+        static PEAK: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let live = LIVE.with(|live| {
+                live.set(live.get() + layout.size());
+                live.get()
+            });
+            PEAK.with(|peak| peak.set(peak.get().max(live)));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            LIVE.with(|live| {
+                live.set(live.get().saturating_sub(layout.size()))
+            });
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    /// Runs `f` and returns its result with the most bytes it held at once.
+    fn peak_allocated<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let start = LIVE.with(Cell::get);
+        PEAK.with(|peak| peak.set(start));
+        let result = f();
+        (result, PEAK.with(Cell::get) - start)
+    }
+
+    /// `a * b = c` with `c` public.
+    fn mul_circuit(
+        cs: &mut CircuitBuilder<Fr, EdwardsParameters>,
+        a: u64,
+        b: u64,
+    ) {
+        let a = cs.add_input(Fr::from(a));
+        let b = cs.add_input(Fr::from(b));
+        let c = cs.arithmetic_gate(|gate| {
+            gate.witness(a, b, None).mul(Fr::from(1u64))
+        });
+        let value = cs.value(c).unwrap();
+        cs.constrain_to_constant(c, Fr::from(0u64), Some(-value));
+    }
+
+    fn prover(a: u64, b: u64) -> Prover<Fr, EdwardsParameters, PC> {
+        let mut prover = Prover::new(b"mul");
+        mul_circuit(prover.mut_cs(), a, b);
+        prover
+    }
+
+    #[test]
+    fn cached_keys_are_reused_for_the_same_circuit_and_key() {
+        let rng = &mut ark_std::test_rng();
+        let dir = tempdir::TempDir::new("key-cache").unwrap();
+        let cache = KeyCache::new(dir.path()).unwrap();
+
+        // Room for the larger circuit below.
+        let mut first = prover(3, 5);
+        let n = 2 * first.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+        assert_eq!(first.preprocess_cached(&ck, &cache), Ok(false));
+        assert!(cache.path(&first.mut_cs().digest::<PC>(&ck)).exists());
+
+        let mut second = prover(4, 6);
+        assert_eq!(second.preprocess_cached(&ck, &cache), Ok(true));
+        assert_eq!(second.prover_key, first.prover_key);
+
+        let proof = second.prove(&ck).unwrap();
+        let pi = second.mut_cs().get_pi().clone();
+        let mut verifier = Verifier::<Fr, EdwardsParameters, PC>::new(b"mul");
+        mul_circuit(verifier.mut_cs(), 0, 0);
+        verifier.preprocess(&ck).unwrap();
+        assert_eq!(verifier.verify(&proof, &vk, &pi), Ok(()));
+
+        // Another SRS or another circuit misses the cache.
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (other_ck, _) = PC::trim(&pp, n, 0, None).unwrap();
+        assert_eq!(
+            prover(3, 5).preprocess_cached(&other_ck, &cache),
+            Ok(false)
+        );
+        let mut larger = prover(3, 5);
+        let one = larger.mut_cs().add_input(Fr::from(1u64));
+        larger
+            .mut_cs()
+            .constrain_to_constant(one, Fr::from(1u64), None);
+        assert_eq!(larger.preprocess_cached(&ck, &cache), Ok(false));
+    }
+
+    #[test]
+    fn corrupted_entries_are_rejected_and_stale_ones_recomputed() {
+        let rng = &mut ark_std::test_rng();
+        let dir = tempdir::TempDir::new("key-cache").unwrap();
+        let cache = KeyCache::new(dir.path()).unwrap();
+
+        let mut first = prover(3, 5);
+        let n = first.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, _) = PC::trim(&pp, n, 0, None).unwrap();
+        assert_eq!(first.preprocess_cached(&ck, &cache), Ok(false));
+        let path = cache.path(&first.mut_cs().digest::<PC>(&ck));
+        let entry = fs::read(&path).unwrap();
+
+        let mut corrupted = entry.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        fs::write(&path, &corrupted).unwrap();
+        assert!(matches!(
+            prover(3, 5).preprocess_cached(&ck, &cache),
+            Err(Error::KeyCache(_))
+        ));
+
+        let mut stale = entry;
+        stale[4] ^= 1;
+        fs::write(&path, &stale).unwrap();
+        assert_eq!(prover(3, 5).preprocess_cached(&ck, &cache), Ok(false));
+        assert_eq!(prover(3, 5).preprocess_cached(&ck, &cache), Ok(true));
+    }
+
+    #[test]
+    fn entries_are_not_copied_to_memory_when_loaded() {
+        let rng = &mut ark_std::test_rng();
+        let dir = tempdir::TempDir::new("key-cache").unwrap();
+        let cache = KeyCache::new(dir.path()).unwrap();
+
+        let mut first = prover(3, 5);
+        for i in 0..500u64 {
+            let x = first.mut_cs().add_input(Fr::from(i));
+            first.mut_cs().constrain_to_constant(x, Fr::from(i), None);
+        }
+        let n = first.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, _) = PC::trim(&pp, n, 0, None).unwrap();
+        assert_eq!(first.preprocess_cached(&ck, &cache), Ok(false));
+        let digest = first.mut_cs().digest::<PC>(&ck);
+        let entry_len = fs::metadata(cache.path(&digest)).unwrap().len();
+
+        // The keys themselves take about as much memory as their encoding,
+        // reading the whole entry first would double that.
+        let (keys, peak) =
+            peak_allocated(|| cache.load::<Fr, PC>(&digest).unwrap());
+        let (prover_key, _) = keys.unwrap();
+        assert_eq!(Some(prover_key), first.prover_key);
+        assert!((peak as u64) < entry_len * 3 / 2);
+    }
+}
@@ -0,0 +1,39 @@
+//! PLONK proving system with plookup lookups, over arkworks curves and
+//! polynomial commitment schemes.
+
+mod arithmetic_gate;
+pub mod assignment;
+pub mod blake2s;
+mod circuit_builder;
+pub mod circom;
+pub mod circuit_description;
+pub mod commitment;
+pub mod constraint_system;
+pub mod curves;
+pub mod ecc;
+pub mod eddsa;
+pub mod error;
+pub mod expr;
+pub mod keccak;
+pub mod key_cache;
+pub mod limbs;
+pub mod merkle;
+pub mod lookup;
+pub mod lookup_table;
+pub mod mimc;
+pub mod mock_prover;
+mod permutation;
+pub mod optimize;
+pub mod poseidon;
+pub mod proof_system;
+pub mod rescue;
+pub mod prover;
+pub mod satisfiability;
+pub mod sha256;
+pub mod solidity;
+pub mod sparse_merkle;
+pub mod stats;
+pub mod transcript;
+mod util;
+mod variable;
+pub mod verifier;
@@ -0,0 +1,315 @@
+//! Words of 4-bit limbs checked with lookup tables, the building blocks of
+//! the SHA-256, Keccak and BLAKE2s gadgets.
+//!
+//! A word of `N` limbs is `[Variable; N]`, least significant limb first,
+//! every limb being range checked by a lookup in the XOR table as
+//! `limb ^ 0 = limb`. XOR and AND go limb by limb through the 4-bit XOR and
+//! AND tables. Rotating or shifting by `4q + s` bits looks every limb up in
+//! the split table of `s`, giving its low `s` bits `lo_i` and high `4 - s`
+//! bits `hi_i`, and the limbs of the result are
+//! `hi_{i+q} + 2^(4-s)·lo_{i+q+1}`. Additions modulo `2^(4N)` are one linear
+//! constraint on the limbs, with the new limbs and a carry below 8 range
+//! checked.
+use crate::constraint_system::{CircuitBuilder, Variable};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use core::ops::{BitAnd, BitXor};
+use hashbrown::HashMap;
+
+/// Variables holding the table indices, and the constant limbs created so
+/// far, shared by the gadgets built on the same circuit.
+pub struct LimbTables {
+    xor: Variable,
+    and: Variable,
+    split: [Variable; 3],
+    constants: HashMap<u64, Variable>,
+}
+
+pub(crate) fn to_u64<F: PrimeField>(x: F) -> u64 {
+    x.into_repr().as_ref()[0]
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Adds the 4-bit XOR, AND and split rows missing from the lookup table
+    /// and allocates the table indices.
+    pub(crate) fn limb_tables(&mut self) -> LimbTables {
+        let (fifteen, xor, and) = (F::from(15u64), -F::one(), F::from(2u64));
+        if self.lookup_table.lookup(fifteen, fifteen, xor).is_err() {
+            self.lookup_table.insert_multi_xor(0, 4);
+        }
+        if self.lookup_table.lookup(fifteen, fifteen, and).is_err() {
+            self.lookup_table.insert_multi_and(0, 4);
+        }
+        for s in 1..4u32 {
+            let lo = F::from((1u64 << s) - 1);
+            let hi = F::from((1u64 << (4 - s)) - 1);
+            let index = F::from(2 + s as u64);
+            if self.lookup_table.lookup(lo, hi, index).is_err() {
+                self.lookup_table.insert_nibble_split(s);
+            }
+        }
+
+        LimbTables {
+            xor: self.constant_variable(xor),
+            and: self.constant_variable(and),
+            split: [1u64, 2, 3].map(|s| self.constant_variable(F::from(2 + s))),
+            constants: HashMap::new(),
+        }
+    }
+
+    fn constant_variable(&mut self, value: F) -> Variable {
+        let var = self.add_input(value);
+        self.constrain_to_constant(var, value, None);
+        var
+    }
+
+    pub(crate) fn limb_constant(
+        &mut self,
+        tables: &mut LimbTables,
+        value: u64,
+    ) -> Variable {
+        if value == 0 {
+            return self.zero_var;
+        }
+        if let Some(var) = tables.constants.get(&value) {
+            return *var;
+        }
+        let var = self.constant_variable(F::from(value));
+        tables.constants.insert(value, var);
+        var
+    }
+
+    pub(crate) fn word_constant<const N: usize>(
+        &mut self,
+        tables: &mut LimbTables,
+        value: u64,
+    ) -> [Variable; N] {
+        let mut word = [self.zero_var; N];
+        for (i, limb) in word.iter_mut().enumerate() {
+            *limb = self.limb_constant(tables, (value >> (4 * i)) & 15);
+        }
+        word
+    }
+
+    pub(crate) fn word_value<const N: usize>(
+        &self,
+        word: &[Variable; N],
+    ) -> Option<u64> {
+        word.iter().rev().try_fold(0, |acc, limb| {
+            self.value(*limb).map(|limb| (acc << 4) + to_u64(limb))
+        })
+    }
+
+    /// Allocates a limb range checked to 4 bits.
+    pub(crate) fn range_limb(
+        &mut self,
+        tables: &LimbTables,
+        value: Option<u64>,
+    ) -> Variable {
+        let limb = self.add_witness(value.map(F::from));
+        let zero = self.zero_var;
+        self.lookup_gate(limb, zero, limb, Some(tables.xor), None)
+    }
+
+    /// Splits a byte into its range checked low and high limbs.
+    pub(crate) fn byte_limbs(
+        &mut self,
+        tables: &LimbTables,
+        byte: Variable,
+    ) -> [Variable; 2] {
+        let value = self.value(byte).map(to_u64);
+        let lo = self.range_limb(tables, value.map(|v| v & 15));
+        let hi = self.range_limb(tables, value.map(|v| v >> 4));
+        self.arithmetic_gate(|gate| {
+            gate.witness(lo, hi, Some(byte))
+                .add(F::one(), F::from(16u64))
+                .out(-F::one())
+        });
+        [lo, hi]
+    }
+
+    /// The byte made of a low and a high limb.
+    pub(crate) fn limbs_byte(
+        &mut self,
+        lo: Variable,
+        hi: Variable,
+    ) -> Variable {
+        self.arithmetic_gate(|gate| {
+            gate.witness(lo, hi, None).add(F::one(), F::from(16u64))
+        })
+    }
+
+    /// Looks `op(a, b)` up in the table with the given index.
+    fn limb_lookup(
+        &mut self,
+        index: Variable,
+        a: Variable,
+        b: Variable,
+        op: fn(u64, u64) -> u64,
+    ) -> Variable {
+        let value = self
+            .value(a)
+            .zip(self.value(b))
+            .map(|(a, b)| F::from(op(to_u64(a), to_u64(b))));
+        let c = self.add_witness(value);
+        self.lookup_gate(a, b, c, Some(index), None)
+    }
+
+    /// XOR of two words. Limbs XORed with the zero variable are kept as
+    /// they are.
+    pub(crate) fn word_xor<const N: usize>(
+        &mut self,
+        tables: &LimbTables,
+        x: &[Variable; N],
+        y: &[Variable; N],
+    ) -> [Variable; N] {
+        let mut out = [self.zero_var; N];
+        for (i, limb) in out.iter_mut().enumerate() {
+            *limb = match (x[i] == self.zero_var, y[i] == self.zero_var) {
+                (true, _) => y[i],
+                (false, true) => x[i],
+                _ => self.limb_lookup(tables.xor, x[i], y[i], u64::bitxor),
+            };
+        }
+        out
+    }
+
+    pub(crate) fn word_and<const N: usize>(
+        &mut self,
+        tables: &LimbTables,
+        x: &[Variable; N],
+        y: &[Variable; N],
+    ) -> [Variable; N] {
+        let mut out = [self.zero_var; N];
+        for (i, limb) in out.iter_mut().enumerate() {
+            *limb = self.limb_lookup(tables.and, x[i], y[i], u64::bitand);
+        }
+        out
+    }
+
+    pub(crate) fn word_not<const N: usize>(
+        &mut self,
+        x: &[Variable; N],
+    ) -> [Variable; N] {
+        let zero = self.zero_var;
+        x.map(|limb| {
+            self.arithmetic_gate(|gate| {
+                gate.witness(limb, zero, None)
+                    .add(-F::one(), F::zero())
+                    .constant(F::from(15u64))
+            })
+        })
+    }
+
+    /// Splits every limb of `x` into its low `s` and high `4 - s` bits.
+    fn split_limbs(
+        &mut self,
+        tables: &LimbTables,
+        x: &[Variable],
+        s: u32,
+    ) -> Vec<(Variable, Variable)> {
+        x.iter()
+            .map(|limb| {
+                let value = self.value(*limb).map(to_u64);
+                let lo = self
+                    .add_witness(value.map(|v| F::from(v & ((1 << s) - 1))));
+                let hi = self.add_witness(value.map(|v| F::from(v >> s)));
+                let index = tables.split[s as usize - 1];
+                self.lookup_gate(lo, hi, *limb, Some(index), None);
+                (lo, hi)
+            })
+            .collect()
+    }
+
+    /// Right rotations of `x`, or right shifts when marked `true`, the limb
+    /// splits being shared between amounts with the same remainder modulo 4.
+    pub(crate) fn word_rotations<const N: usize>(
+        &mut self,
+        tables: &LimbTables,
+        x: &[Variable; N],
+        amounts: &[(u32, bool)],
+    ) -> Vec<[Variable; N]> {
+        let zero = self.zero_var;
+        let mut splits: [Option<Vec<(Variable, Variable)>>; 4] =
+            Default::default();
+        let mut rotated = Vec::with_capacity(amounts.len());
+        for (r, shift) in amounts.iter().copied() {
+            let (q, s) = ((r / 4) as usize, r % 4);
+            if s != 0 && splits[s as usize].is_none() {
+                splits[s as usize] = Some(self.split_limbs(tables, x, s));
+            }
+            let mut out = [zero; N];
+            for (j, limb) in out.iter_mut().enumerate() {
+                let k = j + q;
+                if shift && k >= N {
+                    continue;
+                }
+                let parts = match &splits[s as usize] {
+                    Some(parts) => parts,
+                    None => {
+                        *limb = x[k % N];
+                        continue;
+                    }
+                };
+                let hi = parts[k % N].1;
+                *limb = match shift && k + 1 >= N {
+                    true => hi,
+                    false => {
+                        let lo = parts[(k + 1) % N].0;
+                        self.arithmetic_gate(|gate| {
+                            gate.witness(hi, lo, None)
+                                .add(F::one(), F::from(1u64 << (4 - s)))
+                        })
+                    }
+                };
+            }
+            rotated.push(out);
+        }
+        rotated
+    }
+
+    /// Sum of `words` and `constant` modulo `2^(4N)`, for words of at most
+    /// 32 bits.
+    pub(crate) fn word_add<const N: usize>(
+        &mut self,
+        tables: &mut LimbTables,
+        words: &[&[Variable; N]],
+        constant: u64,
+    ) -> [Variable; N] {
+        assert!(N <= 8, "words must fit in 32 bits");
+        assert!(words.len() < 8, "the carry must stay below 8");
+        let bits = 4 * N as u32;
+        let sum = words
+            .iter()
+            .map(|word| self.word_value(word))
+            .sum::<Option<u64>>()
+            .map(|sum| sum + constant);
+
+        let mut out = [self.zero_var; N];
+        for (i, limb) in out.iter_mut().enumerate() {
+            *limb = self.range_limb(tables, sum.map(|v| (v >> (4 * i)) & 15));
+        }
+        let carry = self.add_witness(sum.map(|v| F::from(v >> bits)));
+        let seven = self.limb_constant(tables, 7);
+        self.lookup_gate(carry, seven, carry, Some(tables.and), None);
+
+        let mut terms = Vec::with_capacity(N * words.len() + N + 1);
+        for word in words {
+            for (i, limb) in word.iter().enumerate() {
+                if *limb != self.zero_var {
+                    terms.push((*limb, F::from(1u64 << (4 * i))));
+                }
+            }
+        }
+        for (i, limb) in out.iter().enumerate() {
+            terms.push((*limb, -F::from(1u64 << (4 * i))));
+        }
+        terms.push((carry, -F::from(1u64 << bits)));
+        self.constrain_linear(terms, F::from(constant));
+        out
+    }
+}
@@ -0,0 +1,146 @@
+//! Multisets of field elements used by the plookup argument.
+
+use crate::error::Error;
+use ark_ff::Field;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+use hashbrown::HashMap;
+
+/// MultiSet is struct containing vectors of scalars, which
+/// individually represents either a wire value or an index
+/// of a PlookUp table
+#[derive(
+    CanonicalDeserialize,
+    CanonicalSerialize,
+    Clone,
+    Debug,
+    Default,
+    Eq,
+    PartialEq,
+)]
+pub struct MultiSet<F>(pub Vec<F>)
+where
+    F: Field;
+
+impl<F> MultiSet<F>
+where
+    F: Field,
+{
+    /// Creates an empty vector with a multiset wrapper around it
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates an empty multiset with the given capacity
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    /// Pushes chosen value onto the end of the Multiset
+    pub fn push(&mut self, value: F) {
+        self.0.push(value)
+    }
+
+    /// Returns the number of elements in the multiset
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the multiset has no elements
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Extends the length of the multiset to `n` by repeating its first
+    /// element. An empty multiset is padded with zeroes.
+    pub fn pad(&mut self, n: usize) {
+        let first = self.0.first().copied().unwrap_or_else(F::zero);
+        self.0.resize(n.max(self.len()), first);
+    }
+
+    /// Compresses the multisets `a`, `b`, `c`, `d` of the same length into
+    /// `a + ζ·b + ζ²·c + ζ³·d`, element by element.
+    pub fn compress(multisets: &[MultiSet<F>; 4], zeta: F) -> MultiSet<F> {
+        let len = multisets[0].len();
+        debug_assert!(multisets.iter().all(|m| m.len() == len));
+        MultiSet(
+            (0..len)
+                .map(|i| {
+                    multisets
+                        .iter()
+                        .rev()
+                        .fold(F::zero(), |acc, m| acc * zeta + m.0[i])
+                })
+                .collect(),
+        )
+    }
+
+    /// Concatenates `f` with `self`, the table, sorting the result by the
+    /// order in which the values first appear in the table, and splits it
+    /// into its even and odd indexed halves `(h_1, h_2)`.
+    ///
+    /// Returns [`Error::ElementNotIndexed`] if a value of `f` is not in the
+    /// table.
+    pub fn combine_split(
+        &self,
+        f: &MultiSet<F>,
+    ) -> Result<(MultiSet<F>, MultiSet<F>), Error> {
+        // Distinct table values in order of first appearance, each with the
+        // number of times it appears in `self` and `f` together.
+        let mut index: HashMap<F, usize> = HashMap::with_capacity(self.len());
+        let mut counters: Vec<(F, usize)> = Vec::with_capacity(self.len());
+        for value in &self.0 {
+            match index.get(value) {
+                Some(&i) => counters[i].1 += 1,
+                None => {
+                    index.insert(*value, counters.len());
+                    counters.push((*value, 1));
+                }
+            }
+        }
+        for value in &f.0 {
+            let i = *index.get(value).ok_or(Error::ElementNotIndexed)?;
+            counters[i].1 += 1;
+        }
+
+        let mut evens = MultiSet::with_capacity(self.len());
+        let mut odds = MultiSet::with_capacity(self.len());
+        counters
+            .iter()
+            .flat_map(|(value, count)| core::iter::repeat_n(*value, *count))
+            .enumerate()
+            .for_each(|(i, value)| match i % 2 {
+                0 => evens.push(value),
+                _ => odds.push(value),
+            });
+        Ok((evens, odds))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+
+    fn set(values: &[u64]) -> MultiSet<Fr> {
+        MultiSet(values.iter().map(|v| Fr::from(*v)).collect())
+    }
+
+    #[test]
+    fn combine_split_follows_table_order() {
+        let t = set(&[4, 1, 7, 4]);
+        let f = set(&[7, 4, 1, 4]);
+        let (h_1, h_2) = t.combine_split(&f).unwrap();
+        // s = [4, 4, 4, 4, 1, 1, 7, 7]
+        assert_eq!(h_1, set(&[4, 4, 1, 7]));
+        assert_eq!(h_2, set(&[4, 4, 1, 7]));
+    }
+
+    #[test]
+    fn combine_split_rejects_missing_values() {
+        let t = set(&[1, 2]);
+        let f = set(&[3, 1]);
+        assert_eq!(t.combine_split(&f), Err(Error::ElementNotIndexed));
+    }
+}
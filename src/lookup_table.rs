@@ -145,6 +145,24 @@ where
         }
     }
 
+    // Function builds the rows splitting every 4-bit value into its low `s`
+    // bits and its high `4 - s` bits, for 0 < s < 4. The 4th wire stores the
+    // index 2 + s, so that the splits do not collide with the other tables.
+    // The rows are: (lo, hi, lo + 2^s * hi, 2 + s)
+    pub fn insert_nibble_split(&mut self, s: u32) {
+        assert!((1..4).contains(&s), "split must be of 1, 2 or 3 bits");
+        for hi in 0..(1u64 << (4 - s)) {
+            for lo in 0..(1u64 << s) {
+                self.insert_row(
+                    F::from(lo),
+                    F::from(hi),
+                    F::from(lo + (hi << s)),
+                    F::from(2 + s as u64),
+                );
+            }
+        }
+    }
+
     // Takes in a table, which is a vector of slices containing
     // 4 elements, and turns them into 4 distinct multisets for
     // a, b, c and d.
@@ -196,4 +214,4 @@ where
         table.insert_multi_mul(lower_bound, n);
         table
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,478 @@
+//! Merkle trees of fixed depth, with membership proven in circuit.
+//!
+//! The two-to-one hash is pluggable through [`MerkleHash`], implemented for
+//! Poseidon, whose nodes are field elements, and for SHA-256, whose nodes are
+//! 32-byte digests of the concatenated children. In circuit a node is the
+//! list of its variables, one per field element or byte.
+//!
+//! Paths go from the leaf up. The direction bit of a level is set when the
+//! node on the path is the right child, so that the bits of a leaf are the
+//! binary digits of its index, least significant first.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    limbs::LimbTables,
+    poseidon::PoseidonParameters,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha256};
+
+/// Two-to-one hash of the inner nodes of a tree, natively and in circuit.
+pub trait MerkleHash<F>
+where
+    F: PrimeField,
+{
+    /// Node of a native tree.
+    type Node: Clone + core::fmt::Debug + Eq;
+
+    /// State shared by the hashes of a path in circuit, such as the
+    /// lookup table indices.
+    type GadgetState;
+
+    /// Number of variables holding a node in circuit.
+    const NODE_LEN: usize;
+
+    /// Hashes two children.
+    fn hash(&self, left: &Self::Node, right: &Self::Node) -> Self::Node;
+
+    /// Values of the variables holding `node`.
+    fn node_values(node: &Self::Node) -> Vec<F>;
+
+    /// Prepares the state passed to [`MerkleHash::hash_gadget`].
+    fn gadget_state<P>(
+        &self,
+        cs: &mut CircuitBuilder<F, P>,
+    ) -> Self::GadgetState
+    where
+        P: TEModelParameters<BaseField = F>;
+
+    /// Constrains the hash of two children.
+    fn hash_gadget<P>(
+        &self,
+        cs: &mut CircuitBuilder<F, P>,
+        state: &mut Self::GadgetState,
+        left: &[Variable],
+        right: &[Variable],
+    ) -> Vec<Variable>
+    where
+        P: TEModelParameters<BaseField = F>;
+}
+
+impl<F> MerkleHash<F> for PoseidonParameters<F>
+where
+    F: PrimeField,
+{
+    type Node = F;
+    type GadgetState = ();
+
+    const NODE_LEN: usize = 1;
+
+    fn hash(&self, left: &F, right: &F) -> F {
+        crate::poseidon::hash(self, &[*left, *right])
+    }
+
+    fn node_values(node: &F) -> Vec<F> {
+        vec![*node]
+    }
+
+    fn gadget_state<P>(&self, _: &mut CircuitBuilder<F, P>)
+    where
+        P: TEModelParameters<BaseField = F>,
+    {
+    }
+
+    fn hash_gadget<P>(
+        &self,
+        cs: &mut CircuitBuilder<F, P>,
+        _: &mut (),
+        left: &[Variable],
+        right: &[Variable],
+    ) -> Vec<Variable>
+    where
+        P: TEModelParameters<BaseField = F>,
+    {
+        vec![cs.poseidon_hash(self, &[left[0], right[0]])]
+    }
+}
+
+/// SHA-256 of the concatenated children, nodes being digests.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Sha256Compression;
+
+impl<F> MerkleHash<F> for Sha256Compression
+where
+    F: PrimeField,
+{
+    type Node = [u8; 32];
+    type GadgetState = LimbTables;
+
+    const NODE_LEN: usize = 32;
+
+    fn hash(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(left);
+        hasher.update(right);
+        hasher.finalize().into()
+    }
+
+    fn node_values(node: &[u8; 32]) -> Vec<F> {
+        node.iter().map(|byte| F::from(*byte)).collect()
+    }
+
+    fn gadget_state<P>(&self, cs: &mut CircuitBuilder<F, P>) -> LimbTables
+    where
+        P: TEModelParameters<BaseField = F>,
+    {
+        cs.limb_tables()
+    }
+
+    fn hash_gadget<P>(
+        &self,
+        cs: &mut CircuitBuilder<F, P>,
+        tables: &mut LimbTables,
+        left: &[Variable],
+        right: &[Variable],
+    ) -> Vec<Variable>
+    where
+        P: TEModelParameters<BaseField = F>,
+    {
+        cs.sha256_with_tables(tables, &[left, right].concat())
+            .to_vec()
+    }
+}
+
+/// Authentication path of a leaf.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerklePath<N> {
+    /// Index of the leaf.
+    pub index: u64,
+    /// Siblings of the nodes on the path, from the leaf up.
+    pub siblings: Vec<N>,
+}
+
+impl<N> MerklePath<N>
+where
+    N: Clone + core::fmt::Debug + Eq,
+{
+    /// Root of the tree holding `leaf` at this path.
+    pub fn root<F, H>(&self, hash: &H, leaf: &N) -> N
+    where
+        F: PrimeField,
+        H: MerkleHash<F, Node = N>,
+    {
+        self.siblings.iter().enumerate().fold(
+            leaf.clone(),
+            |node, (level, sibling)| match (self.index >> level) & 1 == 1 {
+                true => hash.hash(sibling, &node),
+                false => hash.hash(&node, sibling),
+            },
+        )
+    }
+}
+
+/// Merkle tree of fixed depth, missing leaves being `empty_leaf`.
+#[derive(Clone, Debug)]
+pub struct MerkleTree<F, H>
+where
+    F: PrimeField,
+    H: MerkleHash<F>,
+{
+    hash: H,
+    /// Nodes of every level, from the leaves up to the root.
+    levels: Vec<Vec<H::Node>>,
+    __: core::marker::PhantomData<F>,
+}
+
+impl<F, H> MerkleTree<F, H>
+where
+    F: PrimeField,
+    H: MerkleHash<F>,
+{
+    /// Builds a tree of `2^depth` leaves.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `2^depth` leaves.
+    pub fn new(
+        hash: H,
+        depth: usize,
+        leaves: Vec<H::Node>,
+        empty_leaf: H::Node,
+    ) -> Self {
+        assert!(depth < 64, "the tree is too deep");
+        assert!(leaves.len() as u64 <= 1 << depth, "too many leaves");
+        let mut level = leaves;
+        level.resize(1 << depth, empty_leaf);
+
+        let mut levels = Vec::with_capacity(depth + 1);
+        for _ in 0..depth {
+            let next = level
+                .chunks(2)
+                .map(|pair| hash.hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+            level = next;
+        }
+        levels.push(level);
+        Self {
+            hash,
+            levels,
+            __: core::marker::PhantomData,
+        }
+    }
+
+    /// Depth of the tree.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    /// Root of the tree.
+    pub fn root(&self) -> &H::Node {
+        &self.levels[self.depth()][0]
+    }
+
+    /// Leaf at `index`.
+    pub fn leaf(&self, index: u64) -> &H::Node {
+        &self.levels[0][index as usize]
+    }
+
+    /// Authentication path of the leaf at `index`.
+    pub fn path(&self, index: u64) -> MerklePath<H::Node> {
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, nodes)| {
+                nodes[((index >> level) ^ 1) as usize].clone()
+            })
+            .collect();
+        MerklePath { index, siblings }
+    }
+
+    /// Replaces the leaf at `index`, updating the nodes above it.
+    pub fn update(&mut self, index: u64, leaf: H::Node) {
+        self.levels[0][index as usize] = leaf;
+        for level in 0..self.depth() {
+            let pos = (index >> (level + 1)) as usize;
+            let node = self.hash.hash(
+                &self.levels[level][2 * pos],
+                &self.levels[level][2 * pos + 1],
+            );
+            self.levels[level + 1][pos] = node;
+        }
+    }
+}
+
+/// Authentication path allocated in a circuit.
+#[derive(Clone, Debug)]
+pub struct MerklePathVar {
+    /// Direction bits, from the leaf up.
+    pub directions: Vec<Variable>,
+    /// Siblings, from the leaf up.
+    pub siblings: Vec<Vec<Variable>>,
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Allocates a node, its value being unknown without a witness.
+    pub fn add_merkle_node<H>(
+        &mut self,
+        node: Option<&H::Node>,
+    ) -> Vec<Variable>
+    where
+        H: MerkleHash<F>,
+    {
+        match node {
+            Some(node) => H::node_values(node)
+                .into_iter()
+                .map(|value| self.add_witness(Some(value)))
+                .collect(),
+            None => (0..H::NODE_LEN).map(|_| self.add_witness(None)).collect(),
+        }
+    }
+
+    /// Allocates a variable constrained to be boolean.
+    pub(crate) fn add_boolean(&mut self, value: Option<bool>) -> Variable {
+        let zero = self.zero_var;
+        let bit = self.add_witness(value.map(F::from));
+        // bit^2 - bit = 0
+        self.arithmetic_gate(|gate| {
+            gate.witness(bit, bit, Some(zero))
+                .mul(F::one())
+                .add(-F::one(), F::zero())
+                .out(F::zero())
+        });
+        bit
+    }
+
+    /// Allocates an authentication path of `depth` levels. The direction
+    /// bits are constrained to be boolean.
+    pub fn add_merkle_path<H>(
+        &mut self,
+        depth: usize,
+        path: Option<&MerklePath<H::Node>>,
+    ) -> MerklePathVar
+    where
+        H: MerkleHash<F>,
+    {
+        let mut directions = Vec::with_capacity(depth);
+        let mut siblings = Vec::with_capacity(depth);
+        for level in 0..depth {
+            let bit = path.map(|path| (path.index >> level) & 1 == 1);
+            directions.push(self.add_boolean(bit));
+            let sibling = path.map(|path| &path.siblings[level]);
+            siblings.push(self.add_merkle_node::<H>(sibling));
+        }
+        MerklePathVar {
+            directions,
+            siblings,
+        }
+    }
+
+    /// Constrains the root of the tree holding `leaf` at `path`.
+    pub fn merkle_root<H>(
+        &mut self,
+        hash: &H,
+        leaf: &[Variable],
+        path: &MerklePathVar,
+    ) -> Vec<Variable>
+    where
+        H: MerkleHash<F>,
+    {
+        let mut state = hash.gadget_state(self);
+        let mut node = leaf.to_vec();
+        for (bit, sibling) in path.directions.iter().zip(path.siblings.iter()) {
+            let (left, right): (Vec<_>, Vec<_>) = node
+                .iter()
+                .zip(sibling.iter())
+                .map(|(node, sibling)| {
+                    (
+                        self.conditional_select(*bit, *sibling, *node),
+                        self.conditional_select(*bit, *node, *sibling),
+                    )
+                })
+                .unzip();
+            node = hash.hash_gadget(self, &mut state, &left, &right);
+        }
+        node
+    }
+
+    /// Constrains `leaf` to be in the tree whose root is exposed as public
+    /// inputs, and returns the root.
+    pub fn merkle_membership<H>(
+        &mut self,
+        hash: &H,
+        leaf: &[Variable],
+        path: &MerklePathVar,
+    ) -> Vec<Variable>
+    where
+        H: MerkleHash<F>,
+    {
+        let root = self.merkle_root(hash, leaf, path);
+        let zero = self.zero_var;
+        // -root + PI = 0. Without a witness only the PI positions matter,
+        // their values are given when proving.
+        for var in root.iter() {
+            let value = self.value(*var).unwrap_or_default();
+            let pos = self.n;
+            self.arithmetic_gate(|gate| {
+                gate.witness(*var, zero, Some(zero))
+                    .add(-F::one(), F::zero())
+                    .out(F::zero())
+            });
+            self.add_pi(pos, &value).unwrap_or_else(|_| {
+                panic!("Could not insert PI {:?} at {}", value, pos)
+            });
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::UniformRand;
+
+    fn poseidon_params() -> PoseidonParameters<Fr> {
+        let rng = &mut ark_std::test_rng();
+        let mut rand_vec = |len| (0..len).map(|_| Fr::rand(rng)).collect();
+        let mds = (0..3).map(|_| rand_vec(3)).collect();
+        let ark = (0..8 + 57).map(|_| rand_vec(3)).collect();
+        PoseidonParameters::new(8, 57, 5, mds, ark)
+    }
+
+    /// Proves membership of the leaf at `index` and returns the circuit
+    /// with the root it computes.
+    fn membership<H>(
+        hash: &H,
+        tree: &MerkleTree<Fr, H>,
+        index: u64,
+    ) -> (CircuitBuilder<Fr, EdwardsParameters>, Vec<Fr>)
+    where
+        H: MerkleHash<Fr>,
+    {
+        let mut cs = CircuitBuilder::new();
+        let leaf = cs.add_merkle_node::<H>(Some(tree.leaf(index)));
+        let path =
+            cs.add_merkle_path::<H>(tree.depth(), Some(&tree.path(index)));
+        let root = cs.merkle_membership(hash, &leaf, &path);
+        let root = root.iter().map(|var| cs.value(*var).unwrap()).collect();
+        (cs, root)
+    }
+
+    #[test]
+    fn poseidon_gadget_matches_native_tree() {
+        let params = poseidon_params();
+        let leaves = (0..5u64).map(Fr::from).collect::<Vec<_>>();
+        let mut tree =
+            MerkleTree::new(params.clone(), 3, leaves, Fr::from(0u64));
+        tree.update(6, Fr::from(42u64));
+
+        for index in [0, 4, 6, 7] {
+            let path = tree.path(index);
+            assert_eq!(&path.root(&params, tree.leaf(index)), tree.root());
+
+            let (cs, root) = membership(&params, &tree, index);
+            assert_eq!(root, vec![*tree.root()]);
+            assert_eq!(
+                cs.get_pi().get_vals().copied().collect::<Vec<_>>(),
+                root
+            );
+            assert_eq!(cs.check_satisfied(), Ok(()));
+        }
+
+        // A leaf of another tree does not open the expected root.
+        let (mut cs, _) = membership(&params, &tree, 6);
+        let pos = *cs.get_pi().get_pos().next().unwrap();
+        let other_root = *tree.root() + Fr::from(1u64);
+        cs.public_inputs.update(pos, other_root).unwrap();
+        assert!(cs.check_satisfied().is_err());
+    }
+
+    #[test]
+    fn sha256_gadget_matches_native_tree() {
+        let leaves = (0..3u8).map(|i| [i; 32]).collect::<Vec<_>>();
+        let tree =
+            MerkleTree::<Fr, _>::new(Sha256Compression, 2, leaves, [0; 32]);
+
+        let path = tree.path(2);
+        assert_eq!(
+            &path.root::<Fr, _>(&Sha256Compression, tree.leaf(2)),
+            tree.root()
+        );
+
+        let (cs, root) = membership(&Sha256Compression, &tree, 2);
+        let expected =
+            <Sha256Compression as MerkleHash<Fr>>::node_values(tree.root());
+        assert_eq!(root, expected);
+        assert_eq!(
+            cs.get_pi().get_vals().copied().collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(cs.check_satisfied(), Ok(()));
+    }
+}
@@ -0,0 +1,356 @@
+//! MiMC-7 and MiMC-Feistel hashes, in circuit and natively.
+//!
+//! Both follow the circomlib constructions, so that commitments computed by
+//! circuits written with it can be checked here with the same constants:
+//!
+//! - MiMC-7 computes `t_i = x_i + k + c_i`, `x_{i+1} = t_i^7` and outputs
+//!   `x_n + k`. [`multi_mimc7`] chains it over several inputs.
+//! - MiMC-Feistel computes `t_i = xL_i + k + c_i` and
+//!   `(xL_{i+1}, xR_{i+1}) = (xR_i + t_i^5, xL_i)`, except in the last round
+//!   where the halves are not swapped. [`sponge`] absorbs into `xL`.
+//!
+//! circomlib sets the first and last round constants to zero, they are
+//! taken as given here.
+use crate::constraint_system::{CircuitBuilder, Variable};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+
+/// Round constants of a MiMC permutation, one per round.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MimcParameters<F>
+where
+    F: PrimeField,
+{
+    /// Constant added in each round.
+    pub round_constants: Vec<F>,
+}
+
+impl<F> MimcParameters<F>
+where
+    F: PrimeField,
+{
+    /// Creates the parameters from the round constants.
+    pub fn new(round_constants: Vec<F>) -> Self {
+        Self { round_constants }
+    }
+
+    /// Number of rounds.
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+}
+
+/// MiMC-7 encryption of `x` under the key `k`.
+pub fn mimc7<F: PrimeField>(params: &MimcParameters<F>, x: F, k: F) -> F {
+    params
+        .round_constants
+        .iter()
+        .fold(x, |x, c| (x + k + c).pow([7]))
+        + k
+}
+
+/// Hashes `inputs` with MiMC-7 in Miyaguchi-Preneel mode, starting from the
+/// key `k`.
+pub fn multi_mimc7<F: PrimeField>(
+    params: &MimcParameters<F>,
+    inputs: &[F],
+    k: F,
+) -> F {
+    inputs.iter().fold(k, |r, x| r + x + mimc7(params, *x, r))
+}
+
+/// MiMC-Feistel permutation of `(xl, xr)` under the key `k`.
+pub fn feistel<F: PrimeField>(
+    params: &MimcParameters<F>,
+    xl: F,
+    xr: F,
+    k: F,
+) -> (F, F) {
+    let rounds = params.rounds();
+    params.round_constants.iter().enumerate().fold(
+        (xl, xr),
+        |(xl, xr), (i, c)| {
+            let t5 = (xl + k + c).pow([5]);
+            match i + 1 < rounds {
+                true => (xr + t5, xl),
+                false => (xl, xr + t5),
+            }
+        },
+    )
+}
+
+/// Absorbs `inputs` into `xL` of the MiMC-Feistel sponge keyed with `k`,
+/// then squeezes `outputs` elements from it.
+pub fn sponge<F: PrimeField>(
+    params: &MimcParameters<F>,
+    inputs: &[F],
+    k: F,
+    outputs: usize,
+) -> Vec<F> {
+    let (mut xl, mut xr) = (F::zero(), F::zero());
+    for input in inputs {
+        let state = feistel(params, xl + input, xr, k);
+        xl = state.0;
+        xr = state.1;
+    }
+    let mut out = Vec::with_capacity(outputs);
+    for n in 0..outputs {
+        if n > 0 {
+            let state = feistel(params, xl, xr, k);
+            xl = state.0;
+            xr = state.1;
+        }
+        out.push(xl);
+    }
+    out
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Constrains the MiMC-7 encryption of `x` under the key `k`, matching
+    /// [`mimc7`]. `x^7` does not fit in a single row, each round takes three:
+    /// `t^2`, `t^5`, and their product with the next key and constant.
+    pub fn mimc7(
+        &mut self,
+        params: &MimcParameters<F>,
+        x: Variable,
+        k: Variable,
+    ) -> Variable {
+        let zero = self.zero_var();
+        let constants = &params.round_constants;
+        if constants.is_empty() {
+            return self.arithmetic_gate(|gate| {
+                gate.witness(x, k, None).add(F::one(), F::one())
+            });
+        }
+
+        // t = x + k + c_0
+        let c = constants[0];
+        let mut t = self.arithmetic_gate(|gate| {
+            gate.witness(x, k, None).add(F::one(), F::one()).constant(c)
+        });
+        for i in 0..constants.len() {
+            let t_in = t;
+            let t2 = self.arithmetic_gate(|gate| {
+                gate.witness(t_in, t_in, None).mul(F::one())
+            });
+            let t5 = self.arithmetic_gate(|gate| {
+                gate.witness(t_in, zero, None).high_degree(
+                    F::one(),
+                    F::zero(),
+                    F::zero(),
+                )
+            });
+            // t' = t^7 + k + c_{i+1}, or the output x_n + k
+            let c = constants.get(i + 1).copied().unwrap_or_else(F::zero);
+            t = self.arithmetic_gate(|gate| {
+                gate.witness(t5, t2, None)
+                    .mul(F::one())
+                    .fan_in_3(F::one(), k)
+                    .constant(c)
+            });
+        }
+        t
+    }
+
+    /// Constrains the MiMC-7 hash of `inputs` starting from the key `k`,
+    /// matching [`multi_mimc7`].
+    pub fn multi_mimc7(
+        &mut self,
+        params: &MimcParameters<F>,
+        inputs: &[Variable],
+        k: Variable,
+    ) -> Variable {
+        inputs.iter().fold(k, |r, x| {
+            let h = self.mimc7(params, *x, r);
+            self.arithmetic_gate(|gate| {
+                gate.witness(r, *x, None)
+                    .add(F::one(), F::one())
+                    .fan_in_3(F::one(), h)
+            })
+        })
+    }
+
+    /// Constrains the MiMC-Feistel permutation of `(xl, xr)` under the key
+    /// `k`, matching [`feistel`]. Takes one row per round plus two.
+    ///
+    /// Only `t_i = xL_i + k + c_i` is kept in circuit. As
+    /// `xR_{i+1} = xL_i = t_i - k - c_i`, the next one is
+    /// `t_{i+1} = t_{i-1} + t_i^5 + c_{i+1} - c_{i-1}`, a single row.
+    pub fn mimc_feistel(
+        &mut self,
+        params: &MimcParameters<F>,
+        xl: Variable,
+        xr: Variable,
+        k: Variable,
+    ) -> (Variable, Variable) {
+        let constants = &params.round_constants;
+        if constants.is_empty() {
+            return (xl, xr);
+        }
+
+        // t_0 = xL_0 + k + c_0
+        let c = constants[0];
+        let mut t = self.arithmetic_gate(|gate| {
+            gate.witness(xl, k, None)
+                .add(F::one(), F::one())
+                .constant(c)
+        });
+        // xR_i = r + s·k + e
+        let (mut r, mut s, mut e) = (xr, F::zero(), F::zero());
+        for i in 1..constants.len() {
+            let (t_in, c) = (t, constants[i]);
+            // t_i = xR_{i-1} + t_{i-1}^5 + k + c_i
+            t = self.arithmetic_gate(|gate| {
+                gate.witness(t_in, r, None)
+                    .add(F::zero(), F::one())
+                    .fan_in_3(s + F::one(), k)
+                    .high_degree(F::one(), F::zero(), F::zero())
+                    .constant(e + c)
+            });
+            r = t_in;
+            s = -F::one();
+            e = -constants[i - 1];
+        }
+
+        // the last round does not swap the halves
+        let c = constants[constants.len() - 1];
+        let xl_out = self.arithmetic_gate(|gate| {
+            gate.witness(t, k, None)
+                .add(F::one(), -F::one())
+                .constant(-c)
+        });
+        let xr_out = self.arithmetic_gate(|gate| {
+            gate.witness(t, r, None)
+                .add(F::zero(), F::one())
+                .fan_in_3(s, k)
+                .high_degree(F::one(), F::zero(), F::zero())
+                .constant(e)
+        });
+        (xl_out, xr_out)
+    }
+
+    /// Constrains the MiMC-Feistel sponge of `inputs` keyed with `k`,
+    /// matching [`sponge`].
+    pub fn mimc_sponge(
+        &mut self,
+        params: &MimcParameters<F>,
+        inputs: &[Variable],
+        k: Variable,
+        outputs: usize,
+    ) -> Vec<Variable> {
+        let zero = self.zero_var();
+        let (mut xl, mut xr) = (zero, zero);
+        for input in inputs {
+            let xl_in = xl;
+            let xl_in = self.arithmetic_gate(|gate| {
+                gate.witness(xl_in, *input, None).add(F::one(), F::one())
+            });
+            let state = self.mimc_feistel(params, xl_in, xr, k);
+            xl = state.0;
+            xr = state.1;
+        }
+        let mut out = Vec::with_capacity(outputs);
+        for n in 0..outputs {
+            if n > 0 {
+                let state = self.mimc_feistel(params, xl, xr, k);
+                xl = state.0;
+                xr = state.1;
+            }
+            out.push(xl);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::UniformRand;
+
+    /// Random constants, the first and last being zero as in circomlib.
+    fn params(rounds: usize) -> MimcParameters<Fr> {
+        let rng = &mut ark_std::test_rng();
+        let mut constants =
+            (0..rounds).map(|_| Fr::rand(rng)).collect::<Vec<_>>();
+        constants[0] = Fr::from(0u64);
+        constants[rounds - 1] = Fr::from(0u64);
+        MimcParameters::new(constants)
+    }
+
+    fn inputs(len: usize) -> Vec<Fr> {
+        (0..len).map(|i| Fr::from(i as u64 + 3)).collect()
+    }
+
+    #[test]
+    fn mimc7_gadget_matches_native() {
+        let params = params(91);
+        let k = Fr::from(11u64);
+        for len in [1, 3] {
+            let inputs = inputs(len);
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let k_var = cs.add_input(k);
+            let vars = inputs
+                .iter()
+                .map(|input| cs.add_input(*input))
+                .collect::<Vec<_>>();
+            let single = cs.mimc7(&params, vars[0], k_var);
+            let multi = cs.multi_mimc7(&params, &vars, k_var);
+            assert_eq!(cs.value(single), Some(mimc7(&params, inputs[0], k)));
+            assert_eq!(cs.value(multi), Some(multi_mimc7(&params, &inputs, k)));
+            assert_eq!(cs.check_satisfied(), Ok(()));
+
+            cs.constrain_to_constant(multi, Fr::from(0u64), None);
+            assert!(cs.check_satisfied().is_err());
+        }
+    }
+
+    #[test]
+    fn feistel_gadget_matches_native() {
+        let k = Fr::from(5u64);
+        for rounds in [1, 2, 220] {
+            let params = params(rounds);
+            let (xl, xr) = (Fr::from(1u64), Fr::from(2u64));
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let xl_var = cs.add_input(xl);
+            let xr_var = cs.add_input(xr);
+            let k_var = cs.add_input(k);
+            let (l, r) = cs.mimc_feistel(&params, xl_var, xr_var, k_var);
+            let expected = feistel(&params, xl, xr, k);
+            assert_eq!(
+                (cs.value(l), cs.value(r)),
+                (Some(expected.0), Some(expected.1))
+            );
+            assert_eq!(cs.check_satisfied(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn sponge_gadget_matches_native() {
+        let params = params(220);
+        let k = Fr::from(0u64);
+        for (len, outputs) in [(0, 1), (1, 1), (3, 2)] {
+            let inputs = inputs(len);
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let k_var = cs.add_input(k);
+            let vars = inputs
+                .iter()
+                .map(|input| cs.add_input(*input))
+                .collect::<Vec<_>>();
+            let out = cs.mimc_sponge(&params, &vars, k_var, outputs);
+            let values =
+                out.iter().map(|var| cs.value(*var)).collect::<Vec<_>>();
+            let expected = sponge(&params, &inputs, k, outputs);
+            assert_eq!(
+                values,
+                expected.into_iter().map(Some).collect::<Vec<_>>()
+            );
+            assert_eq!(cs.check_satisfied(), Ok(()));
+        }
+    }
+}
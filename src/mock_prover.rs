@@ -0,0 +1,428 @@
+//! A prover that checks the PLONK identities directly on the evaluation
+//! domain, without an SRS or any polynomial commitment.
+//!
+//! It is meant for fast test loops: it finds the same problems a real proof
+//! would fail on, and reports them instead of producing a proof.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    error::Error,
+    lookup::MultiSet,
+    permutation::constants::{K1, K2, K3},
+    satisfiability::Unsatisfied,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::{FftField, PrimeField};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    UVPolynomial,
+};
+use rand_core::{CryptoRng, RngCore};
+
+/// A single identity of the proof system that does not hold.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Violation<F>
+where
+    F: PrimeField,
+{
+    /// A gate or lookup row is not satisfied.
+    Constraint(Unsatisfied<F>),
+
+    /// The numerator of the quotient polynomial does not vanish at the
+    /// `index`-th element of the domain, so it is not divisible by the
+    /// vanishing polynomial.
+    Quotient {
+        /// Index of the domain element, the gate index for gate rows.
+        index: usize,
+        /// Gate term, the arithmetic equation plus the public input.
+        gate: F,
+        /// Permutation term, already scaled by its separation challenges.
+        permutation: F,
+        /// Lookup term, already scaled by its separation challenges.
+        lookup: F,
+    },
+
+    /// `MultiSet::combine_split` rejected the compressed queries, some of
+    /// them are not in the compressed table.
+    LookupMultiset,
+}
+
+/// Checks a [`CircuitBuilder`] without committing to anything.
+pub struct MockProver<'a, F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    cs: &'a CircuitBuilder<F, P>,
+    domain: GeneralEvaluationDomain<F>,
+}
+
+/// Challenges of the quotient polynomial.
+struct Challenges<F> {
+    alpha: F,
+    beta: F,
+    gamma: F,
+    delta: F,
+    epsilon: F,
+    zeta: F,
+    lookup_sep: F,
+}
+
+impl<'a, F, P> MockProver<'a, F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Creates a mock prover for the circuit, over the same domain
+    /// [`Prover`](crate::prover::Prover) would use.
+    pub fn new(cs: &'a CircuitBuilder<F, P>) -> Result<Self, Error> {
+        let n = cs.circuit_bound();
+        let domain = GeneralEvaluationDomain::new(n).ok_or(
+            Error::InvalidEvalDomainSize {
+                log_size_of_group: n.trailing_zeros(),
+                adicity: <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            },
+        )?;
+        Ok(Self { cs, domain })
+    }
+
+    /// Checks every gate and lookup row, then evaluates the numerator of
+    /// the quotient polynomial on the domain, with challenges sampled from
+    /// `rng`.
+    ///
+    /// Returns every violation found, an empty list means a real proof of
+    /// this witness would verify. A circuit with missing witness values is
+    /// reported as such, without running the other checks.
+    pub fn verify<R>(&self, rng: &mut R) -> Vec<Violation<F>>
+    where
+        R: CryptoRng + RngCore + ?Sized,
+    {
+        if let Err(missing) = self.cs.check_witness_complete() {
+            return vec![Violation::Constraint(missing)];
+        }
+        let challenges = Challenges {
+            alpha: F::rand(rng),
+            beta: F::rand(rng),
+            gamma: F::rand(rng),
+            delta: F::rand(rng),
+            epsilon: F::rand(rng),
+            zeta: F::rand(rng),
+            lookup_sep: F::rand(rng),
+        };
+        let mut violations = self.check_gates();
+        violations.extend(self.check_quotient(&challenges));
+        violations
+    }
+
+    /// Gate rows, reported one by one.
+    fn check_gates(&self) -> Vec<Violation<F>> {
+        let pi = self.cs.public_inputs.as_evals(self.domain.size());
+        let table = self.cs.lookup_rows();
+        (0..self.cs.n)
+            .filter_map(|index| self.cs.check_gate(index, &pi, &table).err())
+            .map(Violation::Constraint)
+            .collect()
+    }
+
+    /// Values of a selector or wire column, padded with zeroes to the
+    /// domain size.
+    fn pad(&self, values: &[F]) -> Vec<F> {
+        let mut values = values.to_vec();
+        values.resize(self.domain.size(), F::zero());
+        values
+    }
+
+    /// Witness values of one wire column, padded to the domain size.
+    fn column(&self, wires: &[Variable]) -> Vec<F> {
+        self.pad(
+            &wires
+                .iter()
+                .map(|var| self.cs.variables[var])
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Evaluates the quotient numerator the prover divides by the vanishing
+    /// polynomial at every element of the domain, from the same columns,
+    /// sigma, permutation and lookup polynomials, and reports the elements
+    /// where it does not vanish.
+    fn check_quotient(&self, challenges: &Challenges<F>) -> Vec<Violation<F>> {
+        let cs = self.cs;
+        let domain = &self.domain;
+        let n = domain.size();
+        let Challenges {
+            alpha,
+            beta,
+            gamma,
+            delta,
+            epsilon,
+            zeta,
+            lookup_sep,
+        } = *challenges;
+
+        let wires = [
+            self.column(&cs.w_l),
+            self.column(&cs.w_r),
+            self.column(&cs.w_o),
+            self.column(&cs.w_4),
+        ];
+        let [q_m, q_l, q_r, q_o, q_4, q_c, q_hl, q_hr, q_h4, q_arith, q_lookup] =
+            [
+                &cs.q_m,
+                &cs.q_l,
+                &cs.q_r,
+                &cs.q_o,
+                &cs.q_4,
+                &cs.q_c,
+                &cs.q_hl,
+                &cs.q_hr,
+                &cs.q_h4,
+                &cs.q_arith,
+                &cs.q_lookup,
+            ]
+            .map(|selector| self.pad(selector));
+        let pi = cs.public_inputs.as_evals(n);
+
+        // Permutation polynomial.
+        let sigmas = cs
+            .perm
+            .compute_sigma_permutations(n)
+            .map(|sigma| cs.perm.compute_permutation_lagrange(&sigma, domain));
+        let sigma_polys = sigmas.clone().map(|sigma| {
+            DensePolynomial::from_coefficients_vec(domain.ifft(&sigma))
+        });
+        let z = domain.fft(&cs.perm.compute_permutation_poly(
+            domain,
+            (&wires[0], &wires[1], &wires[2], &wires[3]),
+            beta,
+            gamma,
+            (
+                &sigma_polys[0],
+                &sigma_polys[1],
+                &sigma_polys[2],
+                &sigma_polys[3],
+            ),
+        ));
+
+        // Lookup polynomials, with the padding of the prover key.
+        let mut table = cs.lookup_table.vec_to_multiset();
+        table.iter_mut().for_each(|column| column.pad(n));
+        let table: [MultiSet<F>; 4] = match table.try_into() {
+            Ok(table) => table,
+            Err(_) => return vec![Violation::LookupMultiset],
+        };
+        let t = MultiSet::compress(&table, zeta);
+
+        // Rows without the lookup selector query the first table row, as in
+        // `Prover::prove_with_preprocessed`.
+        let mut queries: [MultiSet<F>; 4] =
+            core::array::from_fn(|_| MultiSet::with_capacity(n));
+        for i in 0..n {
+            if q_lookup[i].is_zero() {
+                queries[0].push(t.0[0]);
+                queries.iter_mut().skip(1).for_each(|f| f.push(F::zero()));
+            } else {
+                queries
+                    .iter_mut()
+                    .zip(&wires)
+                    .for_each(|(f, column)| f.push(column[i]));
+            }
+        }
+        let f = MultiSet::compress(&queries, zeta);
+        let (h_1, h_2) = match t.combine_split(&f) {
+            Ok(halves) => halves,
+            Err(_) => return vec![Violation::LookupMultiset],
+        };
+        let z_2 = domain.fft(&cs.perm.compute_lookup_permutation_poly(
+            domain, &f.0, &t.0, &h_1.0, &h_2.0, delta, epsilon,
+        ));
+
+        let ks = [F::one(), K1::<F>(), K2::<F>(), K3::<F>()];
+        let one_plus_delta = F::one() + delta;
+        let epsilon_one_plus_delta = epsilon * one_plus_delta;
+        let (zeta_sq, sep_sq) = (zeta.square(), lookup_sep.square());
+
+        let mut violations = Vec::new();
+        for (i, root) in domain.elements().enumerate() {
+            let next = (i + 1) % n;
+            let [a, b, c, d] = [0, 1, 2, 3].map(|j| wires[j][i]);
+            // `L_1` is one at the first element and zero elsewhere.
+            let l1 = if i == 0 { F::one() } else { F::zero() };
+
+            let gate = q_arith[i]
+                * (q_m[i] * a * b
+                    + q_l[i] * a
+                    + q_r[i] * b
+                    + q_o[i] * c
+                    + q_4[i] * d
+                    + q_hl[i] * a.pow([5u64])
+                    + q_hr[i] * b.pow([5u64])
+                    + q_h4[i] * d.pow([5u64])
+                    + q_c[i])
+                + pi[i];
+
+            let identity = [a, b, c, d]
+                .iter()
+                .zip(ks)
+                .map(|(w, k)| *w + beta * k * root + gamma)
+                .product::<F>();
+            let copy = [a, b, c, d]
+                .iter()
+                .zip(&sigmas)
+                .map(|(w, sigma)| *w + beta * sigma[i] + gamma)
+                .product::<F>();
+            let permutation = alpha * (identity * z[i] - copy * z[next])
+                + alpha.square() * l1 * (z[i] - F::one());
+
+            let compressed = a + zeta * b + zeta_sq * c + zeta_sq * zeta * d;
+            let numerator = z_2[i]
+                * one_plus_delta
+                * (epsilon + f.0[i])
+                * (epsilon_one_plus_delta + t.0[i] + delta * t.0[next]);
+            let denominator = z_2[next]
+                * (epsilon_one_plus_delta + h_1.0[i] + delta * h_2.0[i])
+                * (epsilon_one_plus_delta + h_2.0[i] + delta * h_1.0[next]);
+            let lookup = lookup_sep * q_lookup[i] * (compressed - f.0[i])
+                + sep_sq * (numerator - denominator)
+                + sep_sq * lookup_sep * l1 * (z_2[i] - F::one());
+
+            if !(gate + permutation + lookup).is_zero() {
+                violations.push(Violation::Quotient {
+                    index: i,
+                    gate,
+                    permutation,
+                    lookup,
+                });
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constraint_system::WireData;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_ff::Zero;
+
+    type Builder = CircuitBuilder<Fr, EdwardsParameters>;
+
+    /// `a * b = c` with `c` public, plus a lookup of `(a, b, c)`. Returns
+    /// the index of the multiplication gate.
+    fn mul_circuit(cs: &mut Builder, a: u64, b: u64, c: u64) -> usize {
+        let a_var = cs.add_input(Fr::from(a));
+        let b_var = cs.add_input(Fr::from(b));
+        let index = cs.n;
+        let c_var = cs.arithmetic_gate(|gate| {
+            gate.witness(a_var, b_var, None).mul(Fr::from(1u64))
+        });
+        cs.constrain_to_constant(c_var, Fr::from(0u64), Some(-Fr::from(c)));
+
+        cs.lookup_table_mut().insert_row(
+            Fr::from(a),
+            Fr::from(b),
+            Fr::from(a * b),
+            Fr::from(0u64),
+        );
+        cs.lookup_gate(a_var, b_var, c_var, None, None);
+        index
+    }
+
+    fn verify(cs: &Builder) -> Vec<Violation<Fr>> {
+        MockProver::new(cs)
+            .unwrap()
+            .verify(&mut ark_std::test_rng())
+    }
+
+    #[test]
+    fn satisfied_circuit_has_no_violation() {
+        let mut cs = Builder::new();
+        mul_circuit(&mut cs, 3, 5, 15);
+        assert_eq!(verify(&cs), vec![]);
+    }
+
+    #[test]
+    fn violated_gate_fails_the_quotient_at_its_row() {
+        let mut cs = Builder::new();
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        let c = cs.add_input(Fr::from(16u64));
+        let (one, zero) = (Fr::from(1u64), Fr::from(0u64));
+        let index = cs.n;
+        cs.poly_gate(a, b, c, one, zero, zero, -one, zero, None);
+
+        let violations = verify(&cs);
+        assert!(violations.iter().any(|v| matches!(
+            v,
+            Violation::Constraint(Unsatisfied::Gate { index: i, .. })
+                if *i == index
+        )));
+        assert!(violations.iter().any(|v| matches!(
+            v,
+            Violation::Quotient { index: i, gate, .. }
+                if *i == index && *gate == -one
+        )));
+    }
+
+    #[test]
+    fn broken_copy_constraint_fails_the_quotient() {
+        let mut cs = Builder::new();
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        let zero = cs.zero_var();
+        let z = Fr::from(0u64);
+        cs.poly_gate(a, zero, zero, z, z, z, z, z, None);
+        let index = cs.n;
+        cs.poly_gate(a, zero, zero, z, z, z, z, z, None);
+        cs.w_l[index] = b;
+        assert!(cs
+            .perm
+            .variable_map
+            .get(&a)
+            .unwrap()
+            .contains(&WireData::Left(index)));
+
+        // Every row holds, only the permutation term catches the swap.
+        let violations = verify(&cs);
+        assert!(!violations.is_empty());
+        assert!(violations.iter().all(|v| matches!(
+            v,
+            Violation::Quotient { gate, lookup, .. }
+                if gate.is_zero() && lookup.is_zero()
+        )));
+    }
+
+    #[test]
+    fn query_outside_the_table_is_reported() {
+        let mut cs = Builder::new();
+        mul_circuit(&mut cs, 3, 5, 15);
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        let c = cs.add_input(Fr::from(16u64));
+        let index = cs.n;
+        cs.lookup_gate(a, b, c, None, None);
+        let violations = verify(&cs);
+        assert!(violations.contains(&Violation::LookupMultiset));
+        assert!(violations.iter().any(|v| matches!(
+            v,
+            Violation::Constraint(Unsatisfied::Lookup { index: i, .. })
+                if *i == index
+        )));
+    }
+
+    #[test]
+    fn missing_witness_is_reported_alone() {
+        let mut cs = Builder::new();
+        let a = cs.add_witness(None);
+        let zero = cs.zero_var();
+        let z = Fr::from(0u64);
+        cs.poly_gate(a, zero, zero, z, z, z, z, z, None);
+        assert_eq!(
+            verify(&cs),
+            vec![Violation::Constraint(Unsatisfied::MissingWitness {
+                variable: a
+            })]
+        );
+    }
+}
@@ -0,0 +1,560 @@
+//! Optimisation pass over a finished [`CircuitBuilder`], run before
+//! preprocessing.
+//!
+//! Gadgets favour simple code over tight circuits: `assert_equal` spends a
+//! row on what a copy constraint can express, constants are pinned once per
+//! use, and linear combinations are computed one row at a time. The pass
+//! rewrites the arithmetic rows so the circuit accepts the same assignments
+//! of the remaining variables with fewer rows. Lookup, range, logic, curve,
+//! dummy and blinding rows, and rows holding a public input, are never
+//! rewritten or removed.
+use crate::{
+    constraint_system::{CircuitBuilder, GateKind, Variable},
+    proof_system::pi::PublicInputs,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use hashbrown::{HashMap, HashSet};
+
+/// Number of rows removed by each step of [`CircuitBuilder::optimize`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct OptimizeReport {
+    /// Equality rows replaced by merging both variables into one
+    /// permutation cycle.
+    pub merged_equalities: usize,
+    /// Rows pinning a variable to a constant already pinned by another row.
+    pub deduplicated_constants: usize,
+    /// Linear rows substituted into the only other row using their output.
+    pub fused_rows: usize,
+    /// Rows constraining nothing but a variable used nowhere else, and rows
+    /// without any constraint at all.
+    pub dead_rows: usize,
+}
+
+impl OptimizeReport {
+    /// Total number of rows removed.
+    pub fn removed_rows(&self) -> usize {
+        self.merged_equalities
+            + self.deduplicated_constants
+            + self.fused_rows
+            + self.dead_rows
+    }
+}
+
+/// Linear row `sum(coeff * var) + constant = 0`, terms merged per variable.
+struct LinearRow<F>
+where
+    F: PrimeField,
+{
+    terms: Vec<(Variable, F)>,
+    constant: F,
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Optimises the circuit in place and returns how many rows each step
+    /// removed.
+    ///
+    /// Variables merged into one cycle keep the value of the variable they
+    /// are merged into, so the pass should only be run once the circuit is
+    /// complete and, when a witness is present, satisfied by it: a witness
+    /// breaking one of the removed equalities can not be detected anymore.
+    /// The resulting circuit only depends on the rows, never on witness
+    /// values, so prover and verifier obtain the same keys.
+    pub fn optimize(&mut self) -> OptimizeReport {
+        let mut report = OptimizeReport::default();
+        let mut removed = vec![false; self.n];
+        let pi_rows = self.intended_pi_pos.iter().copied().collect();
+
+        self.merge_equal_variables(&pi_rows, &mut removed, &mut report);
+        self.fuse_linear_rows(&pi_rows, &mut removed, &mut report);
+        self.remove_dead_rows(&pi_rows, &mut removed, &mut report);
+        self.compact_rows(&removed);
+        report
+    }
+
+    /// Returns gate `index` as a linear row, if it is an arithmetic row
+    /// without product nor public input.
+    fn linear_row(
+        &self,
+        index: usize,
+        pi_rows: &HashSet<usize>,
+    ) -> Option<LinearRow<F>> {
+        let rewritable = self.gate_kinds[index] == GateKind::Arithmetic
+            && self.q_lookup[index].is_zero()
+            && self.q_m[index].is_zero()
+            && self.q_hl[index].is_zero()
+            && self.q_hr[index].is_zero()
+            && self.q_h4[index].is_zero()
+            && !pi_rows.contains(&index);
+        if !rewritable {
+            return None;
+        }
+
+        let mut terms: Vec<(Variable, F)> = Vec::with_capacity(4);
+        let wires = [
+            (self.w_l[index], self.q_l[index]),
+            (self.w_r[index], self.q_r[index]),
+            (self.w_o[index], self.q_o[index]),
+            (self.w_4[index], self.q_4[index]),
+        ];
+        for (var, coeff) in wires {
+            match terms.iter_mut().find(|(v, _)| *v == var) {
+                Some(term) => term.1 += coeff,
+                None => terms.push((var, coeff)),
+            }
+        }
+        terms.retain(|(_, coeff)| !coeff.is_zero());
+        Some(LinearRow {
+            terms,
+            constant: self.q_c[index],
+        })
+    }
+
+    /// Overwrites gate `index` with a linear row of at most four terms.
+    fn set_linear_row(&mut self, index: usize, row: LinearRow<F>) {
+        debug_assert!(row.terms.len() <= 4, "linear row does not fit");
+        let mut terms = row.terms;
+        terms.resize(4, (self.zero_var, F::zero()));
+        self.w_l[index] = terms[0].0;
+        self.w_r[index] = terms[1].0;
+        self.w_o[index] = terms[2].0;
+        self.w_4[index] = terms[3].0;
+        self.q_l[index] = terms[0].1;
+        self.q_r[index] = terms[1].1;
+        self.q_o[index] = terms[2].1;
+        self.q_4[index] = terms[3].1;
+        self.q_m[index] = F::zero();
+        self.q_hl[index] = F::zero();
+        self.q_hr[index] = F::zero();
+        self.q_h4[index] = F::zero();
+        self.q_c[index] = row.constant;
+    }
+
+    /// Variables on the four wires of gate `index`.
+    fn row_variables(&self, index: usize) -> [Variable; 4] {
+        [
+            self.w_l[index],
+            self.w_r[index],
+            self.w_o[index],
+            self.w_4[index],
+        ]
+    }
+
+    /// Rows each variable is placed on, removed rows excluded.
+    fn variable_rows(
+        &self,
+        removed: &[bool],
+    ) -> HashMap<Variable, HashSet<usize>> {
+        let mut rows: HashMap<Variable, HashSet<usize>> = HashMap::new();
+        for index in (0..self.n).filter(|i| !removed[*i]) {
+            for var in self.row_variables(index) {
+                rows.entry(var).or_default().insert(index);
+            }
+        }
+        rows
+    }
+
+    /// Replaces rows `a - b = 0` by a single permutation cycle holding both
+    /// variables, and keeps one row per pinned constant.
+    fn merge_equal_variables(
+        &mut self,
+        pi_rows: &HashSet<usize>,
+        removed: &mut [bool],
+        report: &mut OptimizeReport,
+    ) {
+        let zero = self.zero_var;
+        let mut parent: HashMap<Variable, Variable> = HashMap::new();
+        fn find(
+            parent: &HashMap<Variable, Variable>,
+            mut var: Variable,
+        ) -> Variable {
+            while let Some(next) = parent.get(&var) {
+                var = *next;
+            }
+            var
+        }
+        // The zero variable, then the oldest one, represents a cycle.
+        let union = |parent: &mut HashMap<Variable, Variable>,
+                     a: Variable,
+                     b: Variable| {
+            let (a, b) = (find(parent, a), find(parent, b));
+            let (root, child) = match (a == zero, b == zero) {
+                (true, _) => (a, b),
+                (_, true) => (b, a),
+                _ if a.0 <= b.0 => (a, b),
+                _ => (b, a),
+            };
+            if root != child {
+                parent.insert(child, root);
+            }
+        };
+
+        let mut pinned: HashMap<F, Variable> = HashMap::new();
+        for (index, removed) in removed.iter_mut().enumerate() {
+            let row = match self.linear_row(index, pi_rows) {
+                Some(row) => row,
+                None => continue,
+            };
+            let mut terms: Vec<(Variable, F)> = Vec::with_capacity(4);
+            for (var, coeff) in row.terms {
+                let var = find(&parent, var);
+                match terms.iter_mut().find(|(v, _)| *v == var) {
+                    Some(term) => term.1 += coeff,
+                    None => terms.push((var, coeff)),
+                }
+            }
+            terms.retain(|(_, coeff)| !coeff.is_zero());
+
+            match terms.as_slice() {
+                [] if row.constant.is_zero() => {
+                    *removed = true;
+                    report.dead_rows += 1;
+                }
+                [(a, k_a), (b, k_b)]
+                    if (*k_a + k_b).is_zero() && row.constant.is_zero() =>
+                {
+                    union(&mut parent, *a, *b);
+                    *removed = true;
+                    report.merged_equalities += 1;
+                }
+                [(a, k)] => {
+                    let value = -row.constant * k.inverse().unwrap();
+                    match pinned.get(&value) {
+                        Some(b) => {
+                            union(&mut parent, *a, *b);
+                            *removed = true;
+                            report.deduplicated_constants += 1;
+                        }
+                        None => {
+                            pinned.insert(value, *a);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let columns =
+            [&mut self.w_l, &mut self.w_r, &mut self.w_o, &mut self.w_4];
+        for wires in columns {
+            wires.iter_mut().for_each(|var| *var = find(&parent, *var));
+        }
+    }
+
+    /// Substitutes a linear row into the only other row using one of its
+    /// variables, when the result still fits on four wires.
+    fn fuse_linear_rows(
+        &mut self,
+        pi_rows: &HashSet<usize>,
+        removed: &mut [bool],
+        report: &mut OptimizeReport,
+    ) {
+        let mut uses = self.variable_rows(removed);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, removed_i) in removed.iter_mut().enumerate() {
+                if *removed_i {
+                    continue;
+                }
+                let row_i = match self.linear_row(i, pi_rows) {
+                    Some(row) => row,
+                    None => continue,
+                };
+                for (o, k_o) in row_i.terms.iter() {
+                    let rows = match uses.get(o) {
+                        Some(rows) if *o != self.zero_var => rows,
+                        _ => continue,
+                    };
+                    if rows.len() != 2 {
+                        continue;
+                    }
+                    let j = *rows.iter().find(|j| **j != i).unwrap();
+                    let row_j = match self.linear_row(j, pi_rows) {
+                        Some(row) => row,
+                        None => continue,
+                    };
+                    let m = match row_j.terms.iter().find(|(v, _)| v == o) {
+                        Some((_, m)) => *m,
+                        None => continue,
+                    };
+
+                    // m·o = -(m / k_o)·(other terms of row i + c_i)
+                    let factor = -m * k_o.inverse().unwrap();
+                    let mut terms = row_j.terms.clone();
+                    terms.retain(|(v, _)| v != o);
+                    let others = row_i.terms.iter().filter(|(v, _)| v != o);
+                    for (var, coeff) in others {
+                        match terms.iter_mut().find(|(v, _)| v == var) {
+                            Some(term) => term.1 += factor * coeff,
+                            None => terms.push((*var, factor * coeff)),
+                        }
+                    }
+                    terms.retain(|(_, coeff)| !coeff.is_zero());
+                    if terms.len() > 4 {
+                        continue;
+                    }
+
+                    for index in [i, j] {
+                        for var in self.row_variables(index) {
+                            if let Some(rows) = uses.get_mut(&var) {
+                                rows.remove(&index);
+                            }
+                        }
+                    }
+                    let constant = row_j.constant + factor * row_i.constant;
+                    self.set_linear_row(j, LinearRow { terms, constant });
+                    for var in self.row_variables(j) {
+                        uses.entry(var).or_default().insert(j);
+                    }
+                    *removed_i = true;
+                    report.fused_rows += 1;
+                    changed = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Removes arithmetic rows holding a variable used nowhere else with a
+    /// nonzero linear coefficient: any values of the other wires can be
+    /// completed by that variable, so the row constrains nothing.
+    fn remove_dead_rows(
+        &mut self,
+        pi_rows: &HashSet<usize>,
+        removed: &mut [bool],
+        report: &mut OptimizeReport,
+    ) {
+        let mut uses = self.variable_rows(removed);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (index, removed) in removed.iter_mut().enumerate() {
+                let rewritable = !*removed
+                    && self.gate_kinds[index] == GateKind::Arithmetic
+                    && self.q_lookup[index].is_zero()
+                    && !pi_rows.contains(&index);
+                if !rewritable {
+                    continue;
+                }
+
+                let vars = self.row_variables(index);
+                let coeffs = [
+                    self.q_l[index],
+                    self.q_r[index],
+                    self.q_o[index],
+                    self.q_4[index],
+                ];
+                // A variable under a product or a power is not free to
+                // complete the row.
+                let in_product = |var: Variable| {
+                    (!self.q_m[index].is_zero()
+                        && (var == vars[0] || var == vars[1]))
+                        || (!self.q_hl[index].is_zero() && var == vars[0])
+                        || (!self.q_hr[index].is_zero() && var == vars[1])
+                        || (!self.q_h4[index].is_zero() && var == vars[3])
+                };
+                let dead = vars.iter().any(|var| {
+                    let coeff = vars
+                        .iter()
+                        .zip(coeffs.iter())
+                        .filter(|(v, _)| *v == var)
+                        .fold(F::zero(), |acc, (_, c)| acc + c);
+                    *var != self.zero_var
+                        && uses[var].len() == 1
+                        && !in_product(*var)
+                        && !coeff.is_zero()
+                });
+                if dead {
+                    for var in vars {
+                        if let Some(rows) = uses.get_mut(&var) {
+                            rows.remove(&index);
+                        }
+                    }
+                    *removed = true;
+                    report.dead_rows += 1;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    /// Drops the removed rows, then renumbers the rows of the public inputs
+    /// and regions and rebuilds the permutation.
+    fn compact_rows(&mut self, removed: &[bool]) {
+        fn retain<T>(column: &mut Vec<T>, removed: &[bool]) {
+            let mut index = 0;
+            column.retain(|_| {
+                index += 1;
+                !removed[index - 1]
+            });
+        }
+
+        // new_index[i] is the index of the first row kept at or after i.
+        let mut new_index = Vec::with_capacity(self.n + 1);
+        let mut kept = 0;
+        for is_removed in removed.iter() {
+            new_index.push(kept);
+            kept += !is_removed as usize;
+        }
+        new_index.push(kept);
+
+        retain(&mut self.q_m, removed);
+        retain(&mut self.q_l, removed);
+        retain(&mut self.q_r, removed);
+        retain(&mut self.q_o, removed);
+        retain(&mut self.q_4, removed);
+        retain(&mut self.q_c, removed);
+        retain(&mut self.q_arith, removed);
+        retain(&mut self.q_hl, removed);
+        retain(&mut self.q_hr, removed);
+        retain(&mut self.q_h4, removed);
+        retain(&mut self.q_lookup, removed);
+        retain(&mut self.w_l, removed);
+        retain(&mut self.w_r, removed);
+        retain(&mut self.w_o, removed);
+        retain(&mut self.w_4, removed);
+        retain(&mut self.gate_kinds, removed);
+        self.n = kept;
+
+        let pis = self
+            .public_inputs
+            .get_pos()
+            .zip(self.public_inputs.get_vals())
+            .map(|(pos, value)| (new_index[*pos], *value))
+            .collect::<Vec<_>>();
+        self.public_inputs = PublicInputs::new();
+        self.intended_pi_pos.clear();
+        for (pos, value) in pis {
+            self.add_pi(pos, &value).unwrap_or_else(|_| {
+                panic!("Could not insert PI {:?} at {}", value, pos)
+            });
+        }
+
+        for (_, start, end) in self.regions.iter_mut() {
+            *start = new_index[*start];
+            *end = new_index[*end];
+        }
+
+        self.perm
+            .variable_map
+            .values_mut()
+            .for_each(|wires| wires.clear());
+        for index in 0..self.n {
+            let [a, b, c, d] = self.row_variables(index);
+            self.perm.add_variables_to_map(a, b, c, d, index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        commitment::KZG10, expr::Expr, prover::Prover, verifier::Verifier,
+    };
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_poly_commit::PolynomialCommitment;
+
+    type PC = KZG10<Bls12_381>;
+
+    /// `claimed = (3a + b + 3)·b` with `claimed` public, a repeated
+    /// equality, a constant pinned twice, a linear chain and an unused
+    /// linear row.
+    fn circuit(
+        x: u64,
+        y: u64,
+        claimed: u64,
+    ) -> CircuitBuilder<Fr, EdwardsParameters> {
+        let mut cs = CircuitBuilder::new();
+        let a = cs.add_input(Fr::from(x));
+        let a_copy = cs.add_input(Fr::from(x));
+        let b = cs.add_input(Fr::from(y));
+        let c = cs.add_input(Fr::from(claimed));
+        cs.assert_equal(a, a_copy);
+        cs.constrain_to_constant(c, Fr::from(0u64), Some(-Fr::from(claimed)));
+
+        for _ in 0..2 {
+            let five = cs.add_input(Fr::from(5u64));
+            cs.constrain_to_constant(five, Fr::from(5u64), None);
+        }
+
+        let s = cs.eval(&(Expr::from(a) * Fr::from(2u64) + b + Fr::from(3u64)));
+        let t = cs.eval(&(Expr::from(s) + a_copy));
+        cs.constrain_zero(&(Expr::from(t) * b - c));
+        cs.eval(&(Expr::from(a) + b));
+        cs
+    }
+
+    fn kinds(
+        cs: &CircuitBuilder<Fr, EdwardsParameters>,
+        kind: GateKind,
+    ) -> usize {
+        cs.gate_kinds.iter().filter(|k| **k == kind).count()
+    }
+
+    #[test]
+    fn optimized_circuit_accepts_the_same_witnesses() {
+        for (x, y, claimed) in
+            [(1, 2, 16), (1, 2, 15), (4, 0, 0), (4, 0, 1), (7, 9, 297)]
+        {
+            let plain = circuit(x, y, claimed);
+            let mut optimized = circuit(x, y, claimed);
+            let report = optimized.optimize();
+
+            assert!(report.merged_equalities >= 1);
+            assert_eq!(report.deduplicated_constants, 1);
+            assert!(report.fused_rows >= 1);
+            assert!(report.dead_rows >= 1);
+            assert_eq!(
+                optimized.circuit_size(),
+                plain.circuit_size() - report.removed_rows()
+            );
+            assert_eq!(
+                optimized.check_satisfied().is_ok(),
+                plain.check_satisfied().is_ok(),
+                "{:?}",
+                (x, y, claimed)
+            );
+            assert_eq!(
+                plain.check_satisfied().is_ok(),
+                (3 * x + y + 3) * y == claimed
+            );
+        }
+    }
+
+    #[test]
+    fn dummy_and_blinding_rows_are_kept() {
+        let plain = circuit(1, 2, 16);
+        let mut optimized = circuit(1, 2, 16);
+        optimized.optimize();
+        for kind in [GateKind::Dummy, GateKind::Blinding] {
+            assert_eq!(kinds(&optimized, kind), kinds(&plain, kind));
+        }
+    }
+
+    #[test]
+    fn optimized_circuit_proves_and_verifies() {
+        let rng = &mut ark_std::test_rng();
+
+        let mut prover = Prover::<Fr, EdwardsParameters, PC>::new(b"opt");
+        *prover.mut_cs() = circuit(7, 9, 297);
+        prover.mut_cs().optimize();
+        let n = prover.circuit_bound();
+        let pp = PC::setup(n, None, rng).unwrap();
+        let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+        let proof = prover.prove(&ck).unwrap();
+
+        let mut verifier = Verifier::<Fr, EdwardsParameters, PC>::new(b"opt");
+        *verifier.mut_cs() = circuit(0, 0, 297);
+        verifier.mut_cs().optimize();
+        let pi = verifier.mut_cs().get_pi().clone();
+        verifier.preprocess(&ck).unwrap();
+        assert_eq!(verifier.verify(&proof, &vk, &pi), Ok(()));
+    }
+}
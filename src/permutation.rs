@@ -1,6 +1,9 @@
 pub(crate) mod constants;
 
-use crate::constraint_system::{Variable, WireData};
+use crate::{
+    constraint_system::{Variable, WireData},
+    error::Error,
+};
 use ark_ff::FftField;
 use ark_poly::{
     domain::{EvaluationDomain, GeneralEvaluationDomain},
@@ -11,7 +14,6 @@ use constants::*;
 use hashbrown::HashMap;
 use itertools::izip;
 
-
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
 pub(crate) struct Permutation {
@@ -20,11 +22,6 @@ pub(crate) struct Permutation {
 }
 
 impl Permutation {
-    // Creates a Permutation struct with an expected capacity of zero.
-    pub fn new() -> Self {
-        Permutation::with_capacity(0)
-    }
-
     // Creates a Permutation struct with an expected capacity of `n`.
     pub fn with_capacity(expected_size: usize) -> Self {
         Self {
@@ -88,8 +85,8 @@ impl Permutation {
     }
     // Performs shift by one permutation and computes `sigma_1`, `sigma_2` and
     // `sigma_3`, `sigma_4` permutations from the variable maps.
-    pub(super) fn compute_sigma_permutations(
-        &mut self,
+    pub(crate) fn compute_sigma_permutations(
+        &self,
         n: usize,
     ) -> [Vec<WireData>; 4] {
         let sigma_1 = (0..n).map(WireData::Left).collect::<Vec<_>>();
@@ -117,6 +114,7 @@ impl Permutation {
                     WireData::Left(index) => sigmas[0][*index] = *next_wire,
                     WireData::Right(index) => sigmas[1][*index] = *next_wire,
                     WireData::Output(index) => sigmas[2][*index] = *next_wire,
+                    WireData::Fourth(index) => sigmas[3][*index] = *next_wire,
                 };
             }
         }
@@ -125,7 +123,7 @@ impl Permutation {
     }
 }
 impl Permutation {
-    fn compute_permutation_lagrange<F: FftField>(
+    pub(crate) fn compute_permutation_lagrange<F: FftField>(
         &self,
         sigma_mapping: &[WireData],
         domain: &GeneralEvaluationDomain<F>,
@@ -158,17 +156,17 @@ impl Permutation {
     }
 
     // Computes the sigma polynomials which are used to build the permutation
-    // polynomial.
+    // polynomial. Fails with `Error::PermutationCosetsOverlap` if the cosets
+    // of `domain` given by the constants are not disjoint.
     pub fn compute_sigma_polynomials<F: FftField>(
         &mut self,
         n: usize,
         domain: &GeneralEvaluationDomain<F>,
-    ) -> (
-        DensePolynomial<F>,
-        DensePolynomial<F>,
-        DensePolynomial<F>,
-        DensePolynomial<F>,
-    ) {
+    ) -> Result<[DensePolynomial<F>; 4], Error> {
+        if !cosets_are_disjoint(domain) {
+            return Err(Error::PermutationCosetsOverlap);
+        }
+
         // Compute sigma mappings
         let sigmas = self.compute_sigma_permutations(n);
 
@@ -193,12 +191,110 @@ impl Permutation {
         let fourth_sigma_poly =
             DensePolynomial::from_coefficients_vec(domain.ifft(&fourth_sigma));
 
-        (
+        Ok([
             left_sigma_poly,
             right_sigma_poly,
             out_sigma_poly,
             fourth_sigma_poly,
-        )
+        ])
+    }
+
+    // Computes the permutation polynomial `z(X)` over the domain from the
+    // wire values and the sigma polynomials, with `z(1) = 1` and
+    // `z(ω^(i+1)) = z(ω^i) * Π (w_j + β k_j ω^i + γ) / (w_j + β σ_j(ω^i) + γ)`
+    // where `k_0 = 1`.
+    pub(crate) fn compute_permutation_poly<F: FftField>(
+        &self,
+        domain: &GeneralEvaluationDomain<F>,
+        wires: (&[F], &[F], &[F], &[F]),
+        beta: F,
+        gamma: F,
+        sigma_polys: (
+            &DensePolynomial<F>,
+            &DensePolynomial<F>,
+            &DensePolynomial<F>,
+            &DensePolynomial<F>,
+        ),
+    ) -> DensePolynomial<F> {
+        let n = domain.size();
+        let (w_l, w_r, w_o, w_4) = wires;
+        let sigmas =
+            [sigma_polys.0, sigma_polys.1, sigma_polys.2, sigma_polys.3]
+                .map(|poly| domain.fft(poly));
+        let ks = [F::one(), K1::<F>(), K2::<F>(), K3::<F>()];
+
+        let mut numerators = Vec::with_capacity(n);
+        let mut denominators = Vec::with_capacity(n);
+        for (i, (root, a, b, c, d)) in
+            izip!(domain.elements(), w_l, w_r, w_o, w_4).enumerate()
+        {
+            let wires = [*a, *b, *c, *d];
+            numerators.push(
+                izip!(wires, ks)
+                    .map(|(w, k)| w + beta * k * root + gamma)
+                    .product::<F>(),
+            );
+            denominators.push(
+                izip!(wires, &sigmas)
+                    .map(|(w, sigma)| w + beta * sigma[i] + gamma)
+                    .product::<F>(),
+            );
+        }
+        ark_ff::batch_inversion(&mut denominators);
+
+        let mut z = Vec::with_capacity(n);
+        z.push(F::one());
+        for i in 0..n - 1 {
+            z.push(z[i] * numerators[i] * denominators[i]);
+        }
+        DensePolynomial::from_coefficients_vec(domain.ifft(&z))
     }
 
-}
\ No newline at end of file
+    // Computes the coefficients of the lookup permutation polynomial `z_2(X)`
+    // from the compressed query `f`, the compressed table `t` and the sorted
+    // halves `h_1`, `h_2`, with `z_2(1) = 1` and
+    // `z_2(ω^(i+1)) = z_2(ω^i) * (1 + δ)(ε + f_i)(ε(1 + δ) + t_i + δ t_(i+1))
+    //   / ((ε(1 + δ) + h1_i + δ h2_i)(ε(1 + δ) + h2_i + δ h1_(i+1)))`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_lookup_permutation_poly<F: FftField>(
+        &self,
+        domain: &GeneralEvaluationDomain<F>,
+        f: &[F],
+        t: &[F],
+        h_1: &[F],
+        h_2: &[F],
+        delta: F,
+        epsilon: F,
+    ) -> Vec<F> {
+        let n = domain.size();
+        assert_eq!(f.len(), n);
+        assert_eq!(t.len(), n);
+        assert_eq!(h_1.len(), n);
+        assert_eq!(h_2.len(), n);
+
+        let one_plus_delta = F::one() + delta;
+        let epsilon_one_plus_delta = epsilon * one_plus_delta;
+        let mut numerators = Vec::with_capacity(n);
+        let mut denominators = Vec::with_capacity(n);
+        for i in 0..n {
+            let next = (i + 1) % n;
+            numerators.push(
+                one_plus_delta
+                    * (epsilon + f[i])
+                    * (epsilon_one_plus_delta + t[i] + delta * t[next]),
+            );
+            denominators.push(
+                (epsilon_one_plus_delta + h_1[i] + delta * h_2[i])
+                    * (epsilon_one_plus_delta + h_2[i] + delta * h_1[next]),
+            );
+        }
+        ark_ff::batch_inversion(&mut denominators);
+
+        let mut z_2 = Vec::with_capacity(n);
+        z_2.push(F::one());
+        for i in 0..n - 1 {
+            z_2.push(z_2[i] * numerators[i] * denominators[i]);
+        }
+        domain.ifft(&z_2)
+    }
+}
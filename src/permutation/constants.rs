@@ -0,0 +1,37 @@
+//! Constants used in the permutation argument to generate the cosets of H
+//! for the right, output and fourth wires.
+#![allow(non_snake_case)]
+
+use ark_ff::FftField;
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+
+// On the BLS12-381, BLS12-377, BN254 and Pasta scalar fields no ratio of these
+// values lies in the largest 2-adic subgroup, which keeps `k_i * H` pairwise
+// disjoint for every domain size supported by those curves.
+
+pub(crate) fn K1<F: FftField>() -> F {
+    F::from(7_u64)
+}
+
+pub(crate) fn K2<F: FftField>() -> F {
+    F::from(13_u64)
+}
+
+pub(crate) fn K3<F: FftField>() -> F {
+    F::from(17_u64)
+}
+
+// Checks that `H`, `K1 * H`, `K2 * H` and `K3 * H` are pairwise distinct
+// cosets of the given domain, i.e. that no ratio of two constants is an
+// n-th root of unity.
+pub(crate) fn cosets_are_disjoint<F: FftField>(
+    domain: &GeneralEvaluationDomain<F>,
+) -> bool {
+    let ks = [F::one(), K1::<F>(), K2::<F>(), K3::<F>()];
+    ks.iter().enumerate().all(|(i, k_i)| {
+        ks.iter().skip(i + 1).all(|k_j| {
+            let ratio = *k_i * k_j.inverse().unwrap();
+            domain.evaluate_vanishing_polynomial(ratio) != F::zero()
+        })
+    })
+}
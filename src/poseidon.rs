@@ -0,0 +1,317 @@
+//! Poseidon permutation and sponge, in circuit and natively.
+//!
+//! Both follow `ark-sponge`'s
+//! [`PoseidonSponge`](ark_sponge::poseidon::PoseidonSponge) exactly: half of
+//! the full rounds, then the partial rounds, then the other half, each round
+//! adding its constants, applying the S-box, to the last state element only
+//! in partial rounds, and multiplying by the MDS matrix. The sponge absorbs
+//! into and squeezes from the first state elements, the last one being the
+//! capacity.
+//!
+//! In circuit, the state is kept after the round constants have been added,
+//! so that every state element of the next round is one row:
+//! `u'_i = sum_j M[i][j]·S(u_j) + c_i`, with the `x^5` S-boxes on the high
+//! degree selectors and the state on the left, right and fourth wires.
+use crate::constraint_system::{CircuitBuilder, Variable};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+
+/// Widest state laid out on the left, right and fourth wires of a row.
+pub const MAX_WIDTH: usize = 3;
+
+/// Poseidon parameters over `F`.
+///
+/// `ark-sponge` keeps the fields of its own parameters private, so the
+/// parameters are held here and converted with
+/// [`PoseidonParameters::to_sponge_parameters`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoseidonParameters<F>
+where
+    F: PrimeField,
+{
+    /// Number of rounds applying the S-box to the whole state.
+    pub full_rounds: usize,
+    /// Number of rounds applying the S-box to the last state element.
+    pub partial_rounds: usize,
+    /// S-box exponent.
+    pub alpha: u64,
+    /// MDS matrix, its size being the state width.
+    pub mds: Vec<Vec<F>>,
+    /// Round constants, one vector of the state width per round.
+    pub ark: Vec<Vec<F>>,
+}
+
+impl<F> PoseidonParameters<F>
+where
+    F: PrimeField,
+{
+    /// Creates the parameters.
+    ///
+    /// # Panics
+    ///
+    /// If the number of full rounds is odd, or the constants do not match
+    /// the rounds and width.
+    pub fn new(
+        full_rounds: usize,
+        partial_rounds: usize,
+        alpha: u64,
+        mds: Vec<Vec<F>>,
+        ark: Vec<Vec<F>>,
+    ) -> Self {
+        let width = mds.len();
+        assert!(width > 1, "state leaves no rate");
+        assert!(full_rounds.is_multiple_of(2), "full rounds must be even");
+        assert!(mds.iter().all(|row| row.len() == width), "MDS not square");
+        assert!(
+            ark.len() == full_rounds + partial_rounds
+                && ark.iter().all(|c| c.len() == width),
+            "round constants do not match the rounds and width"
+        );
+        Self {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            mds,
+            ark,
+        }
+    }
+
+    /// State width.
+    pub fn width(&self) -> usize {
+        self.mds.len()
+    }
+
+    /// Number of elements absorbed and squeezed per permutation.
+    pub fn rate(&self) -> usize {
+        self.width() - 1
+    }
+
+    /// The same parameters for `ark-sponge`, which only supports a state of
+    /// width 3.
+    pub fn to_sponge_parameters(
+        &self,
+    ) -> ark_sponge::poseidon::PoseidonParameters<F> {
+        ark_sponge::poseidon::PoseidonParameters::new(
+            self.full_rounds as u32,
+            self.partial_rounds as u32,
+            self.alpha,
+            self.mds.clone(),
+            self.ark.clone(),
+        )
+    }
+
+    fn num_rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    fn is_full_round(&self, round: usize) -> bool {
+        let half = self.full_rounds / 2;
+        round < half || round >= half + self.partial_rounds
+    }
+
+    /// Whether the S-box is applied to state element `i` in `round`.
+    fn has_sbox(&self, round: usize, i: usize) -> bool {
+        self.is_full_round(round) || i == self.width() - 1
+    }
+}
+
+/// Applies the Poseidon permutation to `state`.
+pub fn permute<F: PrimeField>(params: &PoseidonParameters<F>, state: &mut [F]) {
+    for round in 0..params.num_rounds() {
+        for (elem, c) in state.iter_mut().zip(params.ark[round].iter()) {
+            *elem += c;
+        }
+        for (i, elem) in state.iter_mut().enumerate() {
+            if params.has_sbox(round, i) {
+                *elem = elem.pow([params.alpha]);
+            }
+        }
+        let next = params
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(F::zero(), |acc, (m, x)| acc + *m * x)
+            })
+            .collect::<Vec<_>>();
+        state.copy_from_slice(&next);
+    }
+}
+
+/// Hashes `inputs` to one element, as absorbing them into a fresh
+/// `PoseidonSponge` and squeezing one field element would.
+pub fn hash<F: PrimeField>(params: &PoseidonParameters<F>, inputs: &[F]) -> F {
+    let mut state = vec![F::zero(); params.width()];
+    for (n, chunk) in inputs.chunks(params.rate()).enumerate() {
+        if n > 0 {
+            permute(params, &mut state);
+        }
+        for (elem, input) in state.iter_mut().zip(chunk) {
+            *elem += input;
+        }
+    }
+    permute(params, &mut state);
+    state[0]
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Constrains the Poseidon permutation of `state` and returns the
+    /// permuted state. Takes one row per state element and round, plus one
+    /// per state element for the first round constants.
+    ///
+    /// # Panics
+    ///
+    /// If the parameters do not use the `x^5` S-box, or the state is wider
+    /// than [`MAX_WIDTH`].
+    pub fn poseidon_permutation(
+        &mut self,
+        params: &PoseidonParameters<F>,
+        state: &[Variable],
+    ) -> Vec<Variable> {
+        assert_eq!(params.alpha, 5, "only the x^5 S-box is supported");
+        assert_eq!(state.len(), params.width(), "state width mismatch");
+        assert!(state.len() <= MAX_WIDTH, "state is too wide");
+        let zero = self.zero_var();
+        let width = state.len();
+
+        // u_j = s_j + c_j
+        let mut u = state
+            .iter()
+            .zip(params.ark[0].iter())
+            .map(|(s, c)| {
+                let c = *c;
+                self.arithmetic_gate(|gate| {
+                    gate.witness(*s, zero, None)
+                        .add(F::one(), F::zero())
+                        .constant(c)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let rounds = params.num_rounds();
+        for round in 0..rounds {
+            let mut wires = u.clone();
+            wires.resize(MAX_WIDTH, zero);
+            u = (0..width)
+                .map(|i| {
+                    let mut linear = [F::zero(); MAX_WIDTH];
+                    let mut high = [F::zero(); MAX_WIDTH];
+                    for (j, m) in params.mds[i].iter().enumerate() {
+                        match params.has_sbox(round, j) {
+                            true => high[j] = *m,
+                            false => linear[j] = *m,
+                        }
+                    }
+                    let constant = match round + 1 < rounds {
+                        true => params.ark[round + 1][i],
+                        false => F::zero(),
+                    };
+                    self.arithmetic_gate(|gate| {
+                        gate.witness(wires[0], wires[1], None)
+                            .add(linear[0], linear[1])
+                            .fan_in_3(linear[2], wires[2])
+                            .high_degree(high[0], high[1], high[2])
+                            .constant(constant)
+                    })
+                })
+                .collect();
+        }
+        u
+    }
+
+    /// Constrains the hash of `inputs`, matching [`hash`].
+    pub fn poseidon_hash(
+        &mut self,
+        params: &PoseidonParameters<F>,
+        inputs: &[Variable],
+    ) -> Variable {
+        let zero = self.zero_var();
+        let mut state = vec![zero; params.width()];
+        for (n, chunk) in inputs.chunks(params.rate()).enumerate() {
+            if n > 0 {
+                state = self.poseidon_permutation(params, &state);
+            }
+            for (elem, input) in state.iter_mut().zip(chunk) {
+                let elem_in = *elem;
+                *elem = self.arithmetic_gate(|gate| {
+                    gate.witness(elem_in, *input, None).add(F::one(), F::one())
+                });
+            }
+        }
+        self.poseidon_permutation(params, &state)[0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_sponge::{
+        poseidon::PoseidonSponge, CryptographicSponge,
+        FieldBasedCryptographicSponge,
+    };
+    use ark_std::UniformRand;
+
+    /// Random width 3 parameters with the usual round numbers.
+    fn params() -> PoseidonParameters<Fr> {
+        let rng = &mut ark_std::test_rng();
+        let mut rand_vec = |len| (0..len).map(|_| Fr::rand(rng)).collect();
+        let mds = (0..3).map(|_| rand_vec(3)).collect();
+        let ark = (0..8 + 57).map(|_| rand_vec(3)).collect();
+        PoseidonParameters::new(8, 57, 5, mds, ark)
+    }
+
+    fn inputs(len: usize) -> Vec<Fr> {
+        (0..len).map(|i| Fr::from(i as u64 + 7)).collect()
+    }
+
+    #[test]
+    fn hash_matches_ark_sponge() {
+        let params = params();
+        let sponge_params = params.to_sponge_parameters();
+        for len in 0..=5 {
+            let inputs = inputs(len);
+            let mut sponge = PoseidonSponge::new(&sponge_params);
+            for input in &inputs {
+                sponge.absorb(input);
+            }
+            let expected = sponge.squeeze_native_field_elements(1)[0];
+            assert_eq!(hash(&params, &inputs), expected, "length {}", len);
+        }
+    }
+
+    #[test]
+    fn gadget_matches_native_hash() {
+        let params = params();
+        for len in [0, 1, 2, 3] {
+            let inputs = inputs(len);
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let vars = inputs
+                .iter()
+                .map(|input| cs.add_input(*input))
+                .collect::<Vec<_>>();
+            let out = cs.poseidon_hash(&params, &vars);
+            assert_eq!(cs.value(out), Some(hash(&params, &inputs)));
+            assert_eq!(cs.check_satisfied(), Ok(()));
+
+            // A wrong digest does not satisfy the circuit.
+            if len > 0 {
+                let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+                let vars = inputs
+                    .iter()
+                    .map(|input| cs.add_input(*input))
+                    .collect::<Vec<_>>();
+                let out = cs.poseidon_hash(&params, &vars);
+                let expected = hash(&params, &inputs) + Fr::from(1u64);
+                cs.constrain_to_constant(out, expected, None);
+                assert!(cs.check_satisfied().is_err());
+            }
+        }
+    }
+}
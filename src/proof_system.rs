@@ -0,0 +1,13 @@
+//! Proving system: preprocessing, keys, proofs and the polynomials built
+//! while proving.
+
+pub(crate) mod linearisation_poly;
+pub mod pi;
+pub mod preprocess;
+pub mod proof;
+pub(crate) mod quotient_poly;
+pub mod widget;
+
+pub use linearisation_poly::ProofEvaluations;
+pub use proof::Proof;
+pub use widget::{ProverKey, VerifierKey as PlonkVerifierKey};
@@ -1,5 +1,19 @@
-
-
+//! Linearisation polynomial and the evaluations sent in the proof.
+
+use crate::{error::Error, label_eval, proof_system::ProverKey};
+use ark_ff::{FftField, Field, PrimeField};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    Polynomial, UVPolynomial,
+};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// Subset of the [`ProofEvaluations`]. Evaluations at `z` of the wire
+/// polynomials
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct WireEvaluations<F>
 where
     F: Field,
@@ -130,7 +144,8 @@ where
 }
 
 /// Compute the linearisation polynomial.
-pub fn compute<F, P>(
+#[allow(clippy::too_many_arguments)]
+pub fn compute<F>(
     domain: &GeneralEvaluationDomain<F>,
     prover_key: &ProverKey<F>,
     alpha: &F,
@@ -139,10 +154,6 @@ pub fn compute<F, P>(
     delta: &F,
     epsilon: &F,
     zeta: &F,
-    range_separation_challenge: &F,
-    logic_separation_challenge: &F,
-    fixed_base_separation_challenge: &F,
-    var_base_separation_challenge: &F,
     lookup_separation_challenge: &F,
     z_challenge: &F,
     w_l_poly: &DensePolynomial<F>,
@@ -166,10 +177,9 @@ pub fn compute<F, P>(
 ) -> Result<(DensePolynomial<F>, ProofEvaluations<F>), Error>
 where
     F: PrimeField,
-    P: TEModelParameters<BaseField = F>,
 {
     let n = domain.size();
-    let omega = domain.group_gen();
+    let omega = domain.element(1);
     let shifted_z_challenge = *z_challenge * omega;
 
     // Wire evaluations
@@ -248,7 +258,7 @@ where
     let vanishing_poly_eval =
         domain.evaluate_vanishing_polynomial(*z_challenge);
     let z_challenge_to_n = vanishing_poly_eval + F::one();
-    let l1_eval = proof::compute_first_lagrange_evaluation(
+    let l1_eval = compute_first_lagrange_evaluation(
         domain,
         &vanishing_poly_eval,
         z_challenge,
@@ -265,14 +275,9 @@ where
         table_next_eval,
     };
 
-    let gate_constraints = compute_gate_constraint_satisfiability::<F, P>(
-        range_separation_challenge,
-        logic_separation_challenge,
-        fixed_base_separation_challenge,
-        var_base_separation_challenge,
+    let gate_constraints = compute_gate_constraint_linearisation(
         &wire_evals,
         q_arith_eval,
-        &custom_evals,
         prover_key,
     );
 
@@ -338,188 +343,64 @@ where
     ))
 }
 
-
-
-
-
-// ================ quotiont polys =============
-pub fn compute_quo<F, P>(
-    domain: &GeneralEvaluationDomain<F>,
+/// Linearisation of the gate constraints: the arithmetic equation, high
+/// degree terms included, with the wires replaced by their evaluations and
+/// scaled by `q_arith(z)`.
+fn compute_gate_constraint_linearisation<F>(
+    wire_evals: &WireEvaluations<F>,
+    q_arith_eval: F,
     prover_key: &ProverKey<F>,
-    z_poly: &DensePolynomial<F>,
-    z2_poly: &DensePolynomial<F>,
-    w_l_poly: &DensePolynomial<F>,
-    w_r_poly: &DensePolynomial<F>,
-    w_o_poly: &DensePolynomial<F>,
-    w_4_poly: &DensePolynomial<F>,
-    public_inputs_poly: &DensePolynomial<F>,
-    f_poly: &DensePolynomial<F>,
-    table_poly: &DensePolynomial<F>,
-    h1_poly: &DensePolynomial<F>,
-    h2_poly: &DensePolynomial<F>,
-    alpha: &F,
-    beta: &F,
-    gamma: &F,
-    delta: &F,
-    epsilon: &F,
-    zeta: &F,
-    range_challenge: &F,
-    logic_challenge: &F,
-    fixed_base_challenge: &F,
-    var_base_challenge: &F,
-    lookup_challenge: &F,
-) -> Result<DensePolynomial<F>, Error>
+) -> DensePolynomial<F>
 where
     F: PrimeField,
-    P: TEModelParameters<BaseField = F>,
 {
-    let domain_8n = GeneralEvaluationDomain::<F>::new(8 * domain.size())
-        .ok_or(Error::InvalidEvalDomainSize {
-        log_size_of_group: (8 * domain.size()).trailing_zeros(),
-        adicity:
-            <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
-    })?;
-
-    let l1_poly = compute_first_lagrange_poly_scaled(domain, F::one());
-    let l1_eval_8n = domain_8n.coset_fft(&l1_poly);
-
-    let mut z_eval_8n = domain_8n.coset_fft(z_poly);
-    z_eval_8n.push(z_eval_8n[0]);
-    z_eval_8n.push(z_eval_8n[1]);
-    z_eval_8n.push(z_eval_8n[2]);
-    z_eval_8n.push(z_eval_8n[3]);
-    z_eval_8n.push(z_eval_8n[4]);
-    z_eval_8n.push(z_eval_8n[5]);
-    z_eval_8n.push(z_eval_8n[6]);
-    z_eval_8n.push(z_eval_8n[7]);
-
-    let mut wl_eval_8n = domain_8n.coset_fft(w_l_poly);
-    wl_eval_8n.push(wl_eval_8n[0]);
-    wl_eval_8n.push(wl_eval_8n[1]);
-    wl_eval_8n.push(wl_eval_8n[2]);
-    wl_eval_8n.push(wl_eval_8n[3]);
-    wl_eval_8n.push(wl_eval_8n[4]);
-    wl_eval_8n.push(wl_eval_8n[5]);
-    wl_eval_8n.push(wl_eval_8n[6]);
-    wl_eval_8n.push(wl_eval_8n[7]);
-
-    let mut wr_eval_8n = domain_8n.coset_fft(w_r_poly);
-    wr_eval_8n.push(wr_eval_8n[0]);
-    wr_eval_8n.push(wr_eval_8n[1]);
-    wr_eval_8n.push(wr_eval_8n[2]);
-    wr_eval_8n.push(wr_eval_8n[3]);
-    wr_eval_8n.push(wr_eval_8n[4]);
-    wr_eval_8n.push(wr_eval_8n[5]);
-    wr_eval_8n.push(wr_eval_8n[6]);
-    wr_eval_8n.push(wr_eval_8n[7]);
-
-    let wo_eval_8n = domain_8n.coset_fft(w_o_poly);
-
-    let mut w4_eval_8n = domain_8n.coset_fft(w_4_poly);
-    w4_eval_8n.push(w4_eval_8n[0]);
-    w4_eval_8n.push(w4_eval_8n[1]);
-    w4_eval_8n.push(w4_eval_8n[2]);
-    w4_eval_8n.push(w4_eval_8n[3]);
-    w4_eval_8n.push(w4_eval_8n[4]);
-    w4_eval_8n.push(w4_eval_8n[5]);
-    w4_eval_8n.push(w4_eval_8n[6]);
-    w4_eval_8n.push(w4_eval_8n[7]);
-
-    let mut z2_eval_8n = domain_8n.coset_fft(z2_poly);
-    z2_eval_8n.push(z2_eval_8n[0]);
-    z2_eval_8n.push(z2_eval_8n[1]);
-    z2_eval_8n.push(z2_eval_8n[2]);
-    z2_eval_8n.push(z2_eval_8n[3]);
-    z2_eval_8n.push(z2_eval_8n[4]);
-    z2_eval_8n.push(z2_eval_8n[5]);
-    z2_eval_8n.push(z2_eval_8n[6]);
-    z2_eval_8n.push(z2_eval_8n[7]);
-
-    let f_eval_8n = domain_8n.coset_fft(f_poly);
-
-    let mut table_eval_8n = domain_8n.coset_fft(table_poly);
-    table_eval_8n.push(table_eval_8n[0]);
-    table_eval_8n.push(table_eval_8n[1]);
-    table_eval_8n.push(table_eval_8n[2]);
-    table_eval_8n.push(table_eval_8n[3]);
-    table_eval_8n.push(table_eval_8n[4]);
-    table_eval_8n.push(table_eval_8n[5]);
-    table_eval_8n.push(table_eval_8n[6]);
-    table_eval_8n.push(table_eval_8n[7]);
-
-    let mut h1_eval_8n = domain_8n.coset_fft(h1_poly);
-    h1_eval_8n.push(h1_eval_8n[0]);
-    h1_eval_8n.push(h1_eval_8n[1]);
-    h1_eval_8n.push(h1_eval_8n[2]);
-    h1_eval_8n.push(h1_eval_8n[3]);
-    h1_eval_8n.push(h1_eval_8n[4]);
-    h1_eval_8n.push(h1_eval_8n[5]);
-    h1_eval_8n.push(h1_eval_8n[6]);
-    h1_eval_8n.push(h1_eval_8n[7]);
-
-    let h2_eval_8n = domain_8n.coset_fft(h2_poly);
-
-    let gate_constraints = compute_gate_constraint_satisfiability::<F, P>(
-        domain,
-        *range_challenge,
-        *logic_challenge,
-        *fixed_base_challenge,
-        *var_base_challenge,
-        prover_key,
-        &wl_eval_8n,
-        &wr_eval_8n,
-        &wo_eval_8n,
-        &w4_eval_8n,
-        public_inputs_poly,
-    )?;
-
-    let permutation = compute_permutation_checks::<F>(
-        domain,
-        prover_key,
-        &wl_eval_8n,
-        &wr_eval_8n,
-        &wo_eval_8n,
-        &w4_eval_8n,
-        &z_eval_8n,
-        *alpha,
-        *beta,
-        *gamma,
-    )?;
-
-    let lookup = prover_key.lookup.compute_lookup_quotient_term(
-        domain,
-        &wl_eval_8n,
-        &wr_eval_8n,
-        &wo_eval_8n,
-        &w4_eval_8n,
-        &f_eval_8n,
-        &table_eval_8n,
-        &h1_eval_8n,
-        &h2_eval_8n,
-        &z2_eval_8n,
-        &l1_eval_8n,
-        *delta,
-        *epsilon,
-        *zeta,
-        *lookup_challenge,
-    )?;
-
-    let quotient = (0..domain_8n.size())
-        .map(|i| {
-            let numerator = gate_constraints[i] + permutation[i] + lookup[i];
-            let denominator = prover_key.v_h_coset_8n()[i];
-            numerator * denominator.inverse().unwrap()
-        })
-        .collect::<Vec<_>>();
-
-    Ok(DensePolynomial::from_coefficients_vec(
-        domain_8n.coset_ifft(&quotient),
-    ))
+    let arith = &prover_key.arithmetic;
+    let a = wire_evals.a_eval;
+    let b = wire_evals.b_eval;
+    let c = wire_evals.c_eval;
+    let d = wire_evals.d_eval;
+    let terms = [
+        (&arith.q_m.0, a * b),
+        (&arith.q_l.0, a),
+        (&arith.q_r.0, b),
+        (&arith.q_o.0, c),
+        (&arith.q_4.0, d),
+        (&arith.q_hl.0, a.pow([5u64])),
+        (&arith.q_hr.0, b.pow([5u64])),
+        (&arith.q_h4.0, d.pow([5u64])),
+    ];
+    let sum = terms
+        .iter()
+        .fold(arith.q_c.0.clone(), |acc, (q, x)| &acc + &(*q * *x));
+    &sum * q_arith_eval
 }
 
+/// Evaluation of the first Lagrange polynomial `L_1(X) = Z_H(X) / (n(X - 1))`
+/// at `z`, given `Z_H(z)`.
+pub(crate) fn compute_first_lagrange_evaluation<F>(
+    domain: &GeneralEvaluationDomain<F>,
+    z_h_eval: &F,
+    z_challenge: &F,
+) -> F
+where
+    F: FftField,
+{
+    let n_fr = F::from(domain.size() as u64);
+    let denom = n_fr * (*z_challenge - F::one());
+    *z_h_eval * denom.inverse().unwrap()
+}
 
-
-// TODO 
-// compute gate constraint satisfiability for both linearization poly and quotient poly
-
-
+/// Computes `scale·L_1(X)`, the first Lagrange polynomial of the domain
+/// scaled by `scale`.
+pub(crate) fn compute_first_lagrange_poly_scaled<F>(
+    domain: &GeneralEvaluationDomain<F>,
+    scale: F,
+) -> DensePolynomial<F>
+where
+    F: FftField,
+{
+    let mut x_evals = vec![F::zero(); domain.size()];
+    x_evals[0] = scale;
+    domain.ifft_in_place(&mut x_evals);
+    DensePolynomial::from_coefficients_vec(x_evals)
+}
@@ -0,0 +1,120 @@
+//! Public inputs of a circuit, placed at the gates holding them.
+
+use crate::error::Error;
+use ark_ff::{FftField, ToConstraintField};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    UVPolynomial,
+};
+use std::collections::BTreeMap;
+
+/// Values of the public inputs by gate position.
+///
+/// Zero values are kept, so the positions are exactly the gates that were
+/// declared as holding a public input, whatever their values.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PublicInputs<F>
+where
+    F: FftField,
+{
+    // Maps each position to its value.
+    values: BTreeMap<usize, F>,
+}
+
+impl<F> PublicInputs<F>
+where
+    F: FftField,
+{
+    /// Creates a set of empty public inputs.
+    pub fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Inserts the field elements of `item` at the positions starting at
+    /// `pos` and returns how many positions were used.
+    ///
+    /// Returns [`Error::DuplicatePublicInput`] if one of the positions
+    /// already holds a public input, in which case nothing is inserted.
+    pub fn add_input<T>(&mut self, pos: usize, item: &T) -> Result<usize, Error>
+    where
+        T: ToConstraintField<F>,
+    {
+        let elements = item
+            .to_field_elements()
+            .ok_or(Error::InvalidPublicInputBytes)?;
+        let positions = pos..pos + elements.len();
+        if let Some(taken) =
+            positions.clone().find(|p| self.values.contains_key(p))
+        {
+            return Err(Error::DuplicatePublicInput(taken));
+        }
+        self.values.extend(positions.zip(elements.iter().copied()));
+        Ok(elements.len())
+    }
+
+    /// Sets the value at an existing position.
+    ///
+    /// Returns [`Error::InvalidPublicInputBytes`] if `pos` holds no public
+    /// input.
+    pub fn update(&mut self, pos: usize, value: F) -> Result<(), Error> {
+        match self.values.get_mut(&pos) {
+            Some(slot) => {
+                *slot = value;
+                Ok(())
+            }
+            None => Err(Error::InvalidPublicInputBytes),
+        }
+    }
+
+    /// Returns the number of public inputs.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if there are no public inputs.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the positions of the public inputs, in increasing order.
+    pub fn get_pos(&self) -> impl Iterator<Item = &usize> {
+        self.values.keys()
+    }
+
+    /// Returns the values of the public inputs, by increasing position.
+    pub fn get_vals(&self) -> impl Iterator<Item = &F> {
+        self.values.values()
+    }
+
+    /// Returns the public inputs as the evaluations over a domain of size
+    /// `n`, zero on every position without a public input.
+    pub fn as_evals(&self, n: usize) -> Vec<F> {
+        let mut evals = vec![F::zero(); n];
+        self.values
+            .iter()
+            .for_each(|(pos, value)| evals[*pos] = *value);
+        evals
+    }
+
+    /// Interpolates the public inputs over a domain of size `n`.
+    pub fn into_dense_poly(&self, n: usize) -> DensePolynomial<F> {
+        let domain = GeneralEvaluationDomain::<F>::new(n).unwrap();
+        DensePolynomial::from_coefficients_vec(domain.ifft(&self.as_evals(n)))
+    }
+}
+
+impl<F> FromIterator<(usize, F)> for PublicInputs<F>
+where
+    F: FftField,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, F)>,
+    {
+        Self {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Methods to preprocess the constraint system for use in a proof.
+
+use crate::{
+    commitment::HomomorphicCommitment,
+    constraint_system::CircuitBuilder,
+    error::{to_pc_error, Error},
+    label_polynomial,
+    lookup::MultiSet,
+    proof_system::{widget, PlonkVerifierKey, ProverKey},
+    transcript::TranscriptProtocol,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    UVPolynomial,
+};
+use core::marker::PhantomData;
+
+/// Selector, sigma and table polynomials of a circuit, in coefficient form.
+struct SelectorPolynomials<F>
+where
+    F: PrimeField,
+{
+    q_m: DensePolynomial<F>,
+    q_l: DensePolynomial<F>,
+    q_r: DensePolynomial<F>,
+    q_o: DensePolynomial<F>,
+    q_c: DensePolynomial<F>,
+    q_4: DensePolynomial<F>,
+    q_hl: DensePolynomial<F>,
+    q_hr: DensePolynomial<F>,
+    q_h4: DensePolynomial<F>,
+    q_arith: DensePolynomial<F>,
+    q_lookup: DensePolynomial<F>,
+    left_sigma: DensePolynomial<F>,
+    right_sigma: DensePolynomial<F>,
+    out_sigma: DensePolynomial<F>,
+    fourth_sigma: DensePolynomial<F>,
+    table_1: DensePolynomial<F>,
+    table_2: DensePolynomial<F>,
+    table_3: DensePolynomial<F>,
+    table_4: DensePolynomial<F>,
+    table: [MultiSet<F>; 4],
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Returns the evaluation domain of the circuit, of size
+    /// [`CircuitBuilder::circuit_bound`].
+    pub(crate) fn domain(&self) -> Result<GeneralEvaluationDomain<F>, Error> {
+        let n = self.circuit_bound();
+        GeneralEvaluationDomain::new(n).ok_or(Error::InvalidEvalDomainSize {
+            log_size_of_group: n.trailing_zeros(),
+            adicity: <<F as ark_ff::FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+        })
+    }
+
+    /// Checks that all of the wires and selectors of the circuit have the
+    /// same length.
+    fn check_poly_same_len(&self) -> Result<(), Error> {
+        let k = self.q_m.len();
+        let same_len = [
+            self.q_l.len(),
+            self.q_r.len(),
+            self.q_o.len(),
+            self.q_c.len(),
+            self.q_4.len(),
+            self.q_hl.len(),
+            self.q_hr.len(),
+            self.q_h4.len(),
+            self.q_arith.len(),
+            self.q_lookup.len(),
+            self.w_l.len(),
+            self.w_r.len(),
+            self.w_o.len(),
+            self.w_4.len(),
+        ]
+        .iter()
+        .all(|len| *len == k);
+        match same_len && k == self.n {
+            true => Ok(()),
+            false => Err(Error::MismatchedPolyLen),
+        }
+    }
+
+    /// Interpolates the selector, sigma and table polynomials of the
+    /// circuit over `domain`. Selectors are padded with zeroes and the table
+    /// with its first row.
+    fn selector_polynomials(
+        &mut self,
+        domain: &GeneralEvaluationDomain<F>,
+    ) -> Result<SelectorPolynomials<F>, Error> {
+        self.check_poly_same_len()?;
+        let n = domain.size();
+        let interpolate = |evals: &[F]| {
+            let mut evals = evals.to_vec();
+            evals.resize(n, F::zero());
+            DensePolynomial::from_coefficients_vec(domain.ifft(&evals))
+        };
+
+        let [left_sigma, right_sigma, out_sigma, fourth_sigma] =
+            self.perm.compute_sigma_polynomials(n, domain)?;
+
+        let mut columns = self.lookup_table.vec_to_multiset();
+        columns.iter_mut().for_each(|column| column.pad(n));
+        let [t_1, t_2, t_3, t_4]: [MultiSet<F>; 4] =
+            columns.try_into().map_err(|_| Error::MismatchedPolyLen)?;
+
+        Ok(SelectorPolynomials {
+            q_m: interpolate(&self.q_m),
+            q_l: interpolate(&self.q_l),
+            q_r: interpolate(&self.q_r),
+            q_o: interpolate(&self.q_o),
+            q_c: interpolate(&self.q_c),
+            q_4: interpolate(&self.q_4),
+            q_hl: interpolate(&self.q_hl),
+            q_hr: interpolate(&self.q_hr),
+            q_h4: interpolate(&self.q_h4),
+            q_arith: interpolate(&self.q_arith),
+            q_lookup: interpolate(&self.q_lookup),
+            left_sigma,
+            right_sigma,
+            out_sigma,
+            fourth_sigma,
+            table_1: interpolate(&t_1.0),
+            table_2: interpolate(&t_2.0),
+            table_3: interpolate(&t_3.0),
+            table_4: interpolate(&t_4.0),
+            table: [t_1, t_2, t_3, t_4],
+        })
+    }
+
+    /// Commits to the preprocessed polynomials and seeds `transcript` with
+    /// the commitments.
+    fn commit_selectors<PC, T>(
+        &self,
+        commit_key: &PC::CommitterKey,
+        transcript: &mut T,
+        domain: &GeneralEvaluationDomain<F>,
+        polys: &SelectorPolynomials<F>,
+    ) -> Result<PlonkVerifierKey<F, PC>, Error>
+    where
+        PC: HomomorphicCommitment<F>,
+        T: TranscriptProtocol,
+    {
+        let (commitments, _) = PC::commit(
+            commit_key,
+            [
+                label_polynomial!(polys.q_m),
+                label_polynomial!(polys.q_l),
+                label_polynomial!(polys.q_r),
+                label_polynomial!(polys.q_o),
+                label_polynomial!(polys.q_4),
+                label_polynomial!(polys.q_c),
+                label_polynomial!(polys.q_hl),
+                label_polynomial!(polys.q_hr),
+                label_polynomial!(polys.q_h4),
+                label_polynomial!(polys.q_lookup),
+                label_polynomial!(polys.left_sigma),
+                label_polynomial!(polys.right_sigma),
+                label_polynomial!(polys.out_sigma),
+                label_polynomial!(polys.fourth_sigma),
+                label_polynomial!(polys.table_1),
+                label_polynomial!(polys.table_2),
+                label_polynomial!(polys.table_3),
+                label_polynomial!(polys.table_4),
+            ]
+            .iter(),
+            None,
+        )
+        .map_err(to_pc_error::<F, PC>)?;
+        let comm = |i: usize| commitments[i].commitment().clone();
+
+        let verifier_key = PlonkVerifierKey {
+            n: domain.size(),
+            arithmetic: widget::arithmetic::VerifierKey {
+                q_m: comm(0),
+                q_l: comm(1),
+                q_r: comm(2),
+                q_o: comm(3),
+                q_4: comm(4),
+                q_c: comm(5),
+                q_hl: comm(6),
+                q_hr: comm(7),
+                q_h4: comm(8),
+            },
+            lookup: widget::lookup::VerifierKey {
+                q_lookup: comm(9),
+                table_1: comm(14),
+                table_2: comm(15),
+                table_3: comm(16),
+                table_4: comm(17),
+            },
+            permutation: widget::permutation::VerifierKey {
+                left_sigma: comm(10),
+                right_sigma: comm(11),
+                out_sigma: comm(12),
+                fourth_sigma: comm(13),
+            },
+        };
+        verifier_key.seed_transcript(transcript);
+        Ok(verifier_key)
+    }
+
+    /// Computes the prover key from the preprocessed polynomials, with the
+    /// evaluations over the coset of size `8n`.
+    fn prover_key(
+        domain: &GeneralEvaluationDomain<F>,
+        polys: SelectorPolynomials<F>,
+    ) -> Result<ProverKey<F>, Error> {
+        let n = domain.size();
+        let domain_8n = GeneralEvaluationDomain::<F>::new(8 * n).ok_or(
+            Error::InvalidEvalDomainSize {
+                log_size_of_group: (8 * n).trailing_zeros(),
+                adicity: <<F as ark_ff::FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            },
+        )?;
+        let with_evals = |poly: DensePolynomial<F>| {
+            let evals = domain_8n.coset_fft(&poly);
+            (poly, evals)
+        };
+
+        // The polynomial `X`, whose evaluations are the coset points.
+        let x_poly =
+            DensePolynomial::from_coefficients_vec(vec![F::zero(), F::one()]);
+        let v_h: DensePolynomial<F> = domain.vanishing_polynomial().into();
+
+        let [table_1, table_2, table_3, table_4] = polys.table;
+        Ok(ProverKey {
+            n,
+            arithmetic: widget::arithmetic::ProverKey {
+                q_m: with_evals(polys.q_m),
+                q_l: with_evals(polys.q_l),
+                q_r: with_evals(polys.q_r),
+                q_o: with_evals(polys.q_o),
+                q_4: with_evals(polys.q_4),
+                q_c: with_evals(polys.q_c),
+                q_hl: with_evals(polys.q_hl),
+                q_hr: with_evals(polys.q_hr),
+                q_h4: with_evals(polys.q_h4),
+                q_arith: with_evals(polys.q_arith),
+            },
+            permutation: widget::permutation::ProverKey {
+                left_sigma: with_evals(polys.left_sigma),
+                right_sigma: with_evals(polys.right_sigma),
+                out_sigma: with_evals(polys.out_sigma),
+                fourth_sigma: with_evals(polys.fourth_sigma),
+                linear_evaluations: domain_8n.coset_fft(&x_poly),
+            },
+            lookup: widget::lookup::ProverKey {
+                q_lookup: with_evals(polys.q_lookup),
+                table_1,
+                table_2,
+                table_3,
+                table_4,
+            },
+            v_h_coset_8n: domain_8n.coset_fft(&v_h),
+        })
+    }
+
+    /// Pads the circuit to the next power of two, commits to its
+    /// preprocessed polynomials, seeding `transcript` with the commitments,
+    /// and returns the [`ProverKey`].
+    pub fn preprocess_prover<PC, T>(
+        &mut self,
+        commit_key: &PC::CommitterKey,
+        transcript: &mut T,
+        _pc: PhantomData<PC>,
+    ) -> Result<ProverKey<F>, Error>
+    where
+        PC: HomomorphicCommitment<F>,
+        T: TranscriptProtocol,
+    {
+        self.preprocess(commit_key, transcript, _pc)
+            .map(|(prover_key, _)| prover_key)
+    }
+
+    /// Pads the circuit to the next power of two, commits to its
+    /// preprocessed polynomials, seeding `transcript` with the commitments,
+    /// and returns the [`PlonkVerifierKey`].
+    pub fn preprocess_verifier<PC, T>(
+        &mut self,
+        commit_key: &PC::CommitterKey,
+        transcript: &mut T,
+        _pc: PhantomData<PC>,
+    ) -> Result<PlonkVerifierKey<F, PC>, Error>
+    where
+        PC: HomomorphicCommitment<F>,
+        T: TranscriptProtocol,
+    {
+        let domain = self.domain()?;
+        let polys = self.selector_polynomials(&domain)?;
+        self.commit_selectors(commit_key, transcript, &domain, &polys)
+    }
+
+    /// Computes both keys, committing to the preprocessed polynomials only
+    /// once. `transcript` is seeded with the commitments.
+    pub fn preprocess<PC, T>(
+        &mut self,
+        commit_key: &PC::CommitterKey,
+        transcript: &mut T,
+        _pc: PhantomData<PC>,
+    ) -> Result<(ProverKey<F>, PlonkVerifierKey<F, PC>), Error>
+    where
+        PC: HomomorphicCommitment<F>,
+        T: TranscriptProtocol,
+    {
+        let domain = self.domain()?;
+        let polys = self.selector_polynomials(&domain)?;
+        let verifier_key =
+            self.commit_selectors(commit_key, transcript, &domain, &polys)?;
+        let prover_key = Self::prover_key(&domain, polys)?;
+        Ok((prover_key, verifier_key))
+    }
+}
@@ -0,0 +1,534 @@
+//! A Proof stores the commitments to all of the elements that are needed to
+//! univocally identify a prove of some statement, together with the
+//! evaluations and openings checked by the verifier.
+
+use crate::{
+    commitment::HomomorphicCommitment,
+    error::{to_pc_error, Error},
+    label_commitment,
+    proof_system::{
+        linearisation_poly::{
+            compute_first_lagrange_evaluation, ProofEvaluations,
+        },
+        pi::PublicInputs,
+        PlonkVerifierKey,
+    },
+    transcript::TranscriptProtocol,
+};
+use ark_ff::{FftField, PrimeField};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// A Proof is a composition of `Commitment`s to the Witness, Permutation,
+/// Quotient, Shifted and Opening polynomials as well as the
+/// `ProofEvaluations`.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct Proof<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Commitment to the witness polynomial for the left wires.
+    pub(crate) a_comm: PC::Commitment,
+
+    /// Commitment to the witness polynomial for the right wires.
+    pub(crate) b_comm: PC::Commitment,
+
+    /// Commitment to the witness polynomial for the output wires.
+    pub(crate) c_comm: PC::Commitment,
+
+    /// Commitment to the witness polynomial for the fourth wires.
+    pub(crate) d_comm: PC::Commitment,
+
+    /// Commitment to the permutation polynomial.
+    pub(crate) z_comm: PC::Commitment,
+
+    /// Commitment to the lookup query polynomial.
+    pub(crate) f_comm: PC::Commitment,
+
+    /// Commitment to first half of sorted polynomial
+    pub(crate) h_1_comm: PC::Commitment,
+
+    /// Commitment to second half of sorted polynomial
+    pub(crate) h_2_comm: PC::Commitment,
+
+    /// Commitment to the lookup permutation polynomial.
+    pub(crate) z_2_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_1_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_2_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_3_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_4_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_5_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_6_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_7_comm: PC::Commitment,
+
+    /// Commitment to the quotient polynomial.
+    pub(crate) t_8_comm: PC::Commitment,
+
+    /// Batch opening proof of the aggregated witnesses
+    pub aw_opening: PC::Proof,
+
+    /// Batch opening proof of the shifted aggregated witnesses
+    pub saw_opening: PC::Proof,
+
+    /// Subset of all of the evaluations added to the proof.
+    pub(crate) evaluations: ProofEvaluations<F>,
+}
+
+impl<F, PC> Proof<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Performs the verification of a [`Proof`] returning a boolean result.
+    pub(crate) fn verify<T>(
+        &self,
+        plonk_verifier_key: &PlonkVerifierKey<F, PC>,
+        transcript: &mut T,
+        verifier_key: &PC::VerifierKey,
+        pub_inputs: &PublicInputs<F>,
+    ) -> Result<(), Error>
+    where
+        T: TranscriptProtocol,
+    {
+        let domain =
+            GeneralEvaluationDomain::<F>::new(plonk_verifier_key.n).ok_or(Error::InvalidEvalDomainSize {
+                log_size_of_group: plonk_verifier_key.n.trailing_zeros(),
+                adicity: <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            })?;
+
+        // Append Public Inputs to the transcript, one position and value at a
+        // time so fixed-width transcripts can absorb them.
+        pub_inputs.get_pos().zip(pub_inputs.get_vals()).for_each(
+            |(pos, value)| {
+                transcript.append(b"pi_pos", &F::from(*pos as u64));
+                transcript.append(b"pi", value);
+            },
+        );
+
+        // Subgroup checks are done when the proof is deserialised.
+
+        // In order for the Verifier and Prover to have the same view in the
+        // non-interactive setting Both parties must commit the same
+        // elements into the transcript Below the verifier will simulate
+        // an interaction with the prover by adding the same elements
+        // that the prover added into the transcript, hence generating the
+        // same challenges
+        //
+        // Add commitment to witness polynomials to transcript
+        transcript.append(b"w_l", &self.a_comm);
+        transcript.append(b"w_r", &self.b_comm);
+        transcript.append(b"w_o", &self.c_comm);
+        transcript.append(b"w_4", &self.d_comm);
+
+        // Compute table compression challenge `zeta`.
+        let zeta = transcript.challenge_scalar(b"zeta");
+        transcript.append(b"zeta", &zeta);
+
+        // Add f_poly commitment to transcript
+        transcript.append(b"f", &self.f_comm);
+
+        // Add h polynomials to transcript
+        transcript.append(b"h1", &self.h_1_comm);
+        transcript.append(b"h2", &self.h_2_comm);
+
+        // Compute permutation challenges and add them to transcript
+
+        // Compute permutation challenge `beta`.
+        let beta = transcript.challenge_scalar(b"beta");
+        transcript.append(b"beta", &beta);
+
+        // Compute permutation challenge `gamma`.
+        let gamma = transcript.challenge_scalar(b"gamma");
+        transcript.append(b"gamma", &gamma);
+
+        // Compute permutation challenge `delta`.
+        let delta = transcript.challenge_scalar(b"delta");
+        transcript.append(b"delta", &delta);
+
+        // Compute permutation challenge `epsilon`.
+        let epsilon = transcript.challenge_scalar(b"epsilon");
+        transcript.append(b"epsilon", &epsilon);
+
+        // Challenges must be different
+        assert!(beta != gamma, "challenges must be different");
+        assert!(beta != delta, "challenges must be different");
+        assert!(beta != epsilon, "challenges must be different");
+        assert!(gamma != delta, "challenges must be different");
+        assert!(gamma != epsilon, "challenges must be different");
+        assert!(delta != epsilon, "challenges must be different");
+
+        // Add commitment to permutation polynomial to transcript
+        transcript.append(b"z", &self.z_comm);
+
+        // Compute quotient challenge
+        let alpha = transcript.challenge_scalar(b"alpha");
+        transcript.append(b"alpha", &alpha);
+        let lookup_sep_challenge =
+            transcript.challenge_scalar(b"lookup separation challenge");
+        transcript
+            .append(b"lookup separation challenge", &lookup_sep_challenge);
+
+        // Add commitment to quotient polynomial to transcript
+        transcript.append(b"t_1", &self.t_1_comm);
+        transcript.append(b"t_2", &self.t_2_comm);
+        transcript.append(b"t_3", &self.t_3_comm);
+        transcript.append(b"t_4", &self.t_4_comm);
+        transcript.append(b"t_5", &self.t_5_comm);
+        transcript.append(b"t_6", &self.t_6_comm);
+        transcript.append(b"t_7", &self.t_7_comm);
+        transcript.append(b"t_8", &self.t_8_comm);
+
+        // Compute evaluation point challenge
+        let z_challenge = transcript.challenge_scalar(b"z");
+        transcript.append(b"z", &z_challenge);
+
+        // Compute zero polynomial evaluated at `z_challenge`
+        let z_h_eval = domain.evaluate_vanishing_polynomial(z_challenge);
+
+        // Compute first lagrange polynomial evaluated at `z_challenge`
+        let l1_eval =
+            compute_first_lagrange_evaluation(&domain, &z_h_eval, &z_challenge);
+
+        let r0 = self.compute_r0(
+            &domain,
+            pub_inputs,
+            (alpha, beta, gamma),
+            (delta, epsilon),
+            z_challenge,
+            l1_eval,
+            lookup_sep_challenge,
+        );
+
+        // Add evaluations to transcript
+        transcript.append(b"a_eval", &self.evaluations.wire_evals.a_eval);
+        transcript.append(b"b_eval", &self.evaluations.wire_evals.b_eval);
+        transcript.append(b"c_eval", &self.evaluations.wire_evals.c_eval);
+        transcript.append(b"d_eval", &self.evaluations.wire_evals.d_eval);
+
+        transcript.append(
+            b"left_sig_eval",
+            &self.evaluations.perm_evals.left_sigma_eval,
+        );
+        transcript.append(
+            b"right_sig_eval",
+            &self.evaluations.perm_evals.right_sigma_eval,
+        );
+        transcript.append(
+            b"out_sig_eval",
+            &self.evaluations.perm_evals.out_sigma_eval,
+        );
+        transcript.append(
+            b"perm_eval",
+            &self.evaluations.perm_evals.permutation_eval,
+        );
+
+        transcript.append(b"f_eval", &self.evaluations.lookup_evals.f_eval);
+        transcript.append(
+            b"q_lookup_eval",
+            &self.evaluations.lookup_evals.q_lookup_eval,
+        );
+        transcript.append(
+            b"lookup_perm_eval",
+            &self.evaluations.lookup_evals.z2_next_eval,
+        );
+        transcript.append(b"h_1_eval", &self.evaluations.lookup_evals.h1_eval);
+        transcript.append(
+            b"h_1_next_eval",
+            &self.evaluations.lookup_evals.h1_next_eval,
+        );
+        transcript.append(b"h_2_eval", &self.evaluations.lookup_evals.h2_eval);
+
+        self.evaluations
+            .custom_evals
+            .vals
+            .iter()
+            .for_each(|(label, eval)| {
+                let static_label = Box::leak(label.to_owned().into_boxed_str());
+                transcript.append(static_label.as_bytes(), eval);
+            });
+
+        // Compute linearisation commitment
+        let lin_comm = self.compute_linearisation_commitment(
+            &domain,
+            (alpha, beta, gamma),
+            (delta, epsilon, zeta),
+            lookup_sep_challenge,
+            z_challenge,
+            l1_eval,
+            plonk_verifier_key,
+        )?;
+
+        let zeta_sq = zeta.square();
+        let table_comm = PC::multi_scalar_mul(
+            &[
+                plonk_verifier_key.lookup.table_1.clone(),
+                plonk_verifier_key.lookup.table_2.clone(),
+                plonk_verifier_key.lookup.table_3.clone(),
+                plonk_verifier_key.lookup.table_4.clone(),
+            ],
+            &[F::one(), zeta, zeta_sq, zeta_sq * zeta],
+        )?;
+
+        // Commitment Scheme
+        // Now we delegate computation to the commitment scheme by batch
+        // checking two proofs.
+        //
+        // The `AggregateProof`, which proves that all the necessary
+        // polynomials evaluated at `z_challenge` are
+        // correct and a `Proof` which is proof that the
+        // permutation polynomial evaluated at the shifted root of unity is
+        // correct
+
+        // Generation of the first aggregated proof: It ensures that the
+        // polynomials evaluated at `z_challenge` are correct.
+
+        // Reconstruct the Aggregated Proof commitments and evals
+        // The proof consists of the witness commitment with no blinder
+
+        // Compute aggregate witness to polynomials evaluated at the evaluation
+        // challenge `z`
+        let aw_challenge: F = transcript.challenge_scalar(b"aggregate_witness");
+
+        // Witness, query and sorted polynomials are committed with the degree
+        // bound of the scheme.
+        let bound = PC::degree_bound(plonk_verifier_key.n);
+        let aw_commits = [
+            label_commitment!(lin_comm),
+            label_commitment!(plonk_verifier_key.permutation.left_sigma),
+            label_commitment!(plonk_verifier_key.permutation.right_sigma),
+            label_commitment!(plonk_verifier_key.permutation.out_sigma),
+            label_commitment!(self.f_comm, bound),
+            label_commitment!(self.h_2_comm, bound),
+            label_commitment!(table_comm),
+            label_commitment!(self.a_comm, bound),
+            label_commitment!(self.b_comm, bound),
+            label_commitment!(self.c_comm, bound),
+            label_commitment!(self.d_comm, bound),
+        ];
+
+        let aw_evals = [
+            -r0,
+            self.evaluations.perm_evals.left_sigma_eval,
+            self.evaluations.perm_evals.right_sigma_eval,
+            self.evaluations.perm_evals.out_sigma_eval,
+            self.evaluations.lookup_evals.f_eval,
+            self.evaluations.lookup_evals.h2_eval,
+            self.evaluations.lookup_evals.table_eval,
+            self.evaluations.wire_evals.a_eval,
+            self.evaluations.wire_evals.b_eval,
+            self.evaluations.wire_evals.c_eval,
+            self.evaluations.wire_evals.d_eval,
+        ];
+
+        let saw_challenge: F =
+            transcript.challenge_scalar(b"aggregate_witness");
+
+        let saw_commits = [
+            label_commitment!(self.z_comm),
+            label_commitment!(self.a_comm, bound),
+            label_commitment!(self.b_comm, bound),
+            label_commitment!(self.d_comm, bound),
+            label_commitment!(self.h_1_comm),
+            label_commitment!(self.z_2_comm),
+            label_commitment!(table_comm),
+        ];
+
+        let saw_evals = [
+            self.evaluations.perm_evals.permutation_eval,
+            self.evaluations.custom_evals.get("a_next_eval"),
+            self.evaluations.custom_evals.get("b_next_eval"),
+            self.evaluations.custom_evals.get("d_next_eval"),
+            self.evaluations.lookup_evals.h1_next_eval,
+            self.evaluations.lookup_evals.z2_next_eval,
+            self.evaluations.lookup_evals.table_next_eval,
+        ];
+
+        match PC::check(
+            verifier_key,
+            &aw_commits,
+            &z_challenge,
+            aw_evals,
+            &self.aw_opening,
+            aw_challenge,
+            None,
+        ) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::ProofVerificationError),
+            Err(e) => Err(to_pc_error::<F, PC>(e)),
+        }
+        .and_then(|_| {
+            match PC::check(
+                verifier_key,
+                &saw_commits,
+                &(z_challenge * domain.element(1)),
+                saw_evals,
+                &self.saw_opening,
+                saw_challenge,
+                None,
+            ) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(Error::ProofVerificationError),
+                Err(e) => Err(to_pc_error::<F, PC>(e)),
+            }
+        })
+    }
+    /// Computes the constant part `r_0` of the linearisation polynomial,
+    /// the terms of the quotient identity that do not depend on a
+    /// committed polynomial. The linearisation polynomial opens to `-r_0`
+    /// at `z`.
+    #[allow(clippy::too_many_arguments)]
+    fn compute_r0(
+        &self,
+        domain: &GeneralEvaluationDomain<F>,
+        pub_inputs: &PublicInputs<F>,
+        (alpha, beta, gamma): (F, F, F),
+        (delta, epsilon): (F, F),
+        z_challenge: F,
+        l1_eval: F,
+        lookup_sep_challenge: F,
+    ) -> F {
+        let wire_evals = &self.evaluations.wire_evals;
+        let perm_evals = &self.evaluations.perm_evals;
+        let lookup_evals = &self.evaluations.lookup_evals;
+
+        // PI(z), from the Lagrange basis of the domain.
+        let lagrange = domain.evaluate_all_lagrange_coefficients(z_challenge);
+        let pi_eval = pub_inputs
+            .get_pos()
+            .zip(pub_inputs.get_vals())
+            .map(|(pos, value)| lagrange[*pos] * value)
+            .sum::<F>();
+
+        let alpha_sq = alpha.square();
+        let permutation = alpha
+            * perm_evals.permutation_eval
+            * (wire_evals.a_eval + beta * perm_evals.left_sigma_eval + gamma)
+            * (wire_evals.b_eval + beta * perm_evals.right_sigma_eval + gamma)
+            * (wire_evals.c_eval + beta * perm_evals.out_sigma_eval + gamma)
+            * (wire_evals.d_eval + gamma);
+
+        let epsilon_one_plus_delta = epsilon * (F::one() + delta);
+        let lookup_sep_sq = lookup_sep_challenge.square();
+        let lookup = lookup_sep_sq
+            * lookup_evals.z2_next_eval
+            * (epsilon_one_plus_delta + delta * lookup_evals.h2_eval)
+            * (epsilon_one_plus_delta
+                + lookup_evals.h2_eval
+                + delta * lookup_evals.h1_next_eval);
+
+        pi_eval
+            - l1_eval * alpha_sq
+            - permutation
+            - l1_eval * lookup_sep_sq * lookup_sep_challenge
+            - lookup
+    }
+
+    /// Computes the commitment to the linearisation polynomial from the
+    /// verifier key and the commitments of the proof.
+    #[allow(clippy::too_many_arguments)]
+    fn compute_linearisation_commitment(
+        &self,
+        domain: &GeneralEvaluationDomain<F>,
+        (alpha, beta, gamma): (F, F, F),
+        (delta, epsilon, zeta): (F, F, F),
+        lookup_sep_challenge: F,
+        z_challenge: F,
+        l1_eval: F,
+        plonk_verifier_key: &PlonkVerifierKey<F, PC>,
+    ) -> Result<PC::Commitment, Error> {
+        let mut scalars = Vec::with_capacity(24);
+        let mut points = Vec::with_capacity(24);
+        let wire_evals = &self.evaluations.wire_evals;
+        let perm_evals = &self.evaluations.perm_evals;
+        let lookup_evals = &self.evaluations.lookup_evals;
+        let wires = (
+            wire_evals.a_eval,
+            wire_evals.b_eval,
+            wire_evals.c_eval,
+            wire_evals.d_eval,
+        );
+
+        plonk_verifier_key
+            .arithmetic
+            .compute_linearisation_commitment(
+                &mut scalars,
+                &mut points,
+                self.evaluations.custom_evals.get("q_arith_eval"),
+                wires,
+            );
+
+        plonk_verifier_key
+            .permutation
+            .compute_linearisation_commitment(
+                &mut scalars,
+                &mut points,
+                &self.z_comm,
+                z_challenge,
+                (alpha, beta, gamma),
+                wires,
+                (
+                    perm_evals.left_sigma_eval,
+                    perm_evals.right_sigma_eval,
+                    perm_evals.out_sigma_eval,
+                ),
+                perm_evals.permutation_eval,
+                l1_eval,
+            );
+
+        plonk_verifier_key.lookup.compute_linearisation_commitment(
+            &mut scalars,
+            &mut points,
+            (&self.z_2_comm, &self.h_1_comm),
+            l1_eval,
+            wires,
+            lookup_evals.f_eval,
+            lookup_evals.table_eval,
+            lookup_evals.table_next_eval,
+            lookup_evals.h1_next_eval,
+            lookup_evals.h2_eval,
+            lookup_evals.z2_next_eval,
+            (delta, epsilon, zeta, lookup_sep_challenge),
+        );
+
+        // - Z_H(z) * [t_1 + z^n t_2 + ... + z^7n t_8]
+        let vanishing_poly_eval =
+            domain.evaluate_vanishing_polynomial(z_challenge);
+        let z_challenge_to_n = vanishing_poly_eval + F::one();
+        let t_comms = [
+            &self.t_1_comm,
+            &self.t_2_comm,
+            &self.t_3_comm,
+            &self.t_4_comm,
+            &self.t_5_comm,
+            &self.t_6_comm,
+            &self.t_7_comm,
+            &self.t_8_comm,
+        ];
+        for (power, t_comm) in
+            crate::util::powers_of(z_challenge_to_n).zip(t_comms)
+        {
+            scalars.push(-vanishing_poly_eval * power);
+            points.push(t_comm.clone());
+        }
+
+        PC::multi_scalar_mul(&points, &scalars)
+    }
+}
@@ -0,0 +1,268 @@
+//! Quotient polynomial, computed over the coset of size `8n`.
+
+use crate::{
+    error::Error,
+    proof_system::{
+        linearisation_poly::compute_first_lagrange_poly_scaled, ProverKey,
+    },
+};
+use ark_ff::{FftField, PrimeField};
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+    UVPolynomial,
+};
+
+/// Computes the quotient polynomial `t(X)`, the sum of the gate, permutation
+/// and lookup constraints divided by the vanishing polynomial of the domain.
+#[allow(clippy::too_many_arguments)]
+pub fn compute<F>(
+    domain: &GeneralEvaluationDomain<F>,
+    prover_key: &ProverKey<F>,
+    z_poly: &DensePolynomial<F>,
+    z2_poly: &DensePolynomial<F>,
+    w_l_poly: &DensePolynomial<F>,
+    w_r_poly: &DensePolynomial<F>,
+    w_o_poly: &DensePolynomial<F>,
+    w_4_poly: &DensePolynomial<F>,
+    public_inputs_poly: &DensePolynomial<F>,
+    f_poly: &DensePolynomial<F>,
+    table_poly: &DensePolynomial<F>,
+    h1_poly: &DensePolynomial<F>,
+    h2_poly: &DensePolynomial<F>,
+    alpha: &F,
+    beta: &F,
+    gamma: &F,
+    delta: &F,
+    epsilon: &F,
+    zeta: &F,
+    lookup_challenge: &F,
+) -> Result<DensePolynomial<F>, Error>
+where
+    F: PrimeField,
+{
+    let domain_8n = GeneralEvaluationDomain::<F>::new(8 * domain.size())
+        .ok_or(Error::InvalidEvalDomainSize {
+        log_size_of_group: (8 * domain.size()).trailing_zeros(),
+        adicity:
+            <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+    })?;
+
+    let l1_poly = compute_first_lagrange_poly_scaled(domain, F::one());
+    let l1_eval_8n = domain_8n.coset_fft(&l1_poly);
+
+    let mut z_eval_8n = domain_8n.coset_fft(z_poly);
+    z_eval_8n.push(z_eval_8n[0]);
+    z_eval_8n.push(z_eval_8n[1]);
+    z_eval_8n.push(z_eval_8n[2]);
+    z_eval_8n.push(z_eval_8n[3]);
+    z_eval_8n.push(z_eval_8n[4]);
+    z_eval_8n.push(z_eval_8n[5]);
+    z_eval_8n.push(z_eval_8n[6]);
+    z_eval_8n.push(z_eval_8n[7]);
+
+    let mut wl_eval_8n = domain_8n.coset_fft(w_l_poly);
+    wl_eval_8n.push(wl_eval_8n[0]);
+    wl_eval_8n.push(wl_eval_8n[1]);
+    wl_eval_8n.push(wl_eval_8n[2]);
+    wl_eval_8n.push(wl_eval_8n[3]);
+    wl_eval_8n.push(wl_eval_8n[4]);
+    wl_eval_8n.push(wl_eval_8n[5]);
+    wl_eval_8n.push(wl_eval_8n[6]);
+    wl_eval_8n.push(wl_eval_8n[7]);
+
+    let mut wr_eval_8n = domain_8n.coset_fft(w_r_poly);
+    wr_eval_8n.push(wr_eval_8n[0]);
+    wr_eval_8n.push(wr_eval_8n[1]);
+    wr_eval_8n.push(wr_eval_8n[2]);
+    wr_eval_8n.push(wr_eval_8n[3]);
+    wr_eval_8n.push(wr_eval_8n[4]);
+    wr_eval_8n.push(wr_eval_8n[5]);
+    wr_eval_8n.push(wr_eval_8n[6]);
+    wr_eval_8n.push(wr_eval_8n[7]);
+
+    let wo_eval_8n = domain_8n.coset_fft(w_o_poly);
+
+    let mut w4_eval_8n = domain_8n.coset_fft(w_4_poly);
+    w4_eval_8n.push(w4_eval_8n[0]);
+    w4_eval_8n.push(w4_eval_8n[1]);
+    w4_eval_8n.push(w4_eval_8n[2]);
+    w4_eval_8n.push(w4_eval_8n[3]);
+    w4_eval_8n.push(w4_eval_8n[4]);
+    w4_eval_8n.push(w4_eval_8n[5]);
+    w4_eval_8n.push(w4_eval_8n[6]);
+    w4_eval_8n.push(w4_eval_8n[7]);
+
+    let mut z2_eval_8n = domain_8n.coset_fft(z2_poly);
+    z2_eval_8n.push(z2_eval_8n[0]);
+    z2_eval_8n.push(z2_eval_8n[1]);
+    z2_eval_8n.push(z2_eval_8n[2]);
+    z2_eval_8n.push(z2_eval_8n[3]);
+    z2_eval_8n.push(z2_eval_8n[4]);
+    z2_eval_8n.push(z2_eval_8n[5]);
+    z2_eval_8n.push(z2_eval_8n[6]);
+    z2_eval_8n.push(z2_eval_8n[7]);
+
+    let f_eval_8n = domain_8n.coset_fft(f_poly);
+
+    let mut table_eval_8n = domain_8n.coset_fft(table_poly);
+    table_eval_8n.push(table_eval_8n[0]);
+    table_eval_8n.push(table_eval_8n[1]);
+    table_eval_8n.push(table_eval_8n[2]);
+    table_eval_8n.push(table_eval_8n[3]);
+    table_eval_8n.push(table_eval_8n[4]);
+    table_eval_8n.push(table_eval_8n[5]);
+    table_eval_8n.push(table_eval_8n[6]);
+    table_eval_8n.push(table_eval_8n[7]);
+
+    let mut h1_eval_8n = domain_8n.coset_fft(h1_poly);
+    h1_eval_8n.push(h1_eval_8n[0]);
+    h1_eval_8n.push(h1_eval_8n[1]);
+    h1_eval_8n.push(h1_eval_8n[2]);
+    h1_eval_8n.push(h1_eval_8n[3]);
+    h1_eval_8n.push(h1_eval_8n[4]);
+    h1_eval_8n.push(h1_eval_8n[5]);
+    h1_eval_8n.push(h1_eval_8n[6]);
+    h1_eval_8n.push(h1_eval_8n[7]);
+
+    let h2_eval_8n = domain_8n.coset_fft(h2_poly);
+
+    let gate_constraints = compute_gate_constraint_quotient(
+        domain,
+        prover_key,
+        &wl_eval_8n,
+        &wr_eval_8n,
+        &wo_eval_8n,
+        &w4_eval_8n,
+        public_inputs_poly,
+    )?;
+
+    let permutation = compute_permutation_checks::<F>(
+        domain,
+        prover_key,
+        &wl_eval_8n,
+        &wr_eval_8n,
+        &wo_eval_8n,
+        &w4_eval_8n,
+        &z_eval_8n,
+        *alpha,
+        *beta,
+        *gamma,
+    )?;
+
+    let lookup = prover_key.lookup.compute_lookup_quotient_term(
+        domain,
+        &wl_eval_8n,
+        &wr_eval_8n,
+        &wo_eval_8n,
+        &w4_eval_8n,
+        &f_eval_8n,
+        &table_eval_8n,
+        &h1_eval_8n,
+        &h2_eval_8n,
+        &z2_eval_8n,
+        &l1_eval_8n,
+        *delta,
+        *epsilon,
+        *zeta,
+        *lookup_challenge,
+    )?;
+
+    let quotient = (0..domain_8n.size())
+        .map(|i| {
+            let numerator = gate_constraints[i] + permutation[i] + lookup[i];
+            let denominator = prover_key.v_h_coset_8n()[i];
+            numerator * denominator.inverse().unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    Ok(DensePolynomial::from_coefficients_vec(
+        domain_8n.coset_ifft(&quotient),
+    ))
+}
+
+/// Evaluations over the 8n coset of the gate constraints: the arithmetic
+/// equation, high degree terms included, scaled by `q_arith`, plus the
+/// public inputs.
+fn compute_gate_constraint_quotient<F>(
+    domain: &GeneralEvaluationDomain<F>,
+    prover_key: &ProverKey<F>,
+    wl_eval_8n: &[F],
+    wr_eval_8n: &[F],
+    wo_eval_8n: &[F],
+    w4_eval_8n: &[F],
+    pi_poly: &DensePolynomial<F>,
+) -> Result<Vec<F>, Error>
+where
+    F: PrimeField,
+{
+    let domain_8n = GeneralEvaluationDomain::<F>::new(8 * domain.size())
+        .ok_or(Error::InvalidEvalDomainSize {
+        log_size_of_group: (8 * domain.size()).trailing_zeros(),
+        adicity:
+            <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+    })?;
+    let pi_eval_8n = domain_8n.coset_fft(pi_poly);
+    let arith = &prover_key.arithmetic;
+
+    Ok((0..domain_8n.size())
+        .map(|i| {
+            let (a, b, c, d) =
+                (wl_eval_8n[i], wr_eval_8n[i], wo_eval_8n[i], w4_eval_8n[i]);
+            let gate = arith.q_m.1[i] * a * b
+                + arith.q_l.1[i] * a
+                + arith.q_r.1[i] * b
+                + arith.q_o.1[i] * c
+                + arith.q_4.1[i] * d
+                + arith.q_hl.1[i] * a.pow([5u64])
+                + arith.q_hr.1[i] * b.pow([5u64])
+                + arith.q_h4.1[i] * d.pow([5u64])
+                + arith.q_c.1[i];
+            gate * arith.q_arith.1[i] + pi_eval_8n[i]
+        })
+        .collect())
+}
+
+/// Evaluations over the 8n coset of the permutation term of the quotient
+/// polynomial, see [`ProverKey::compute_quotient_i`] of the permutation
+/// widget.
+#[allow(clippy::too_many_arguments)]
+fn compute_permutation_checks<F>(
+    domain: &GeneralEvaluationDomain<F>,
+    prover_key: &ProverKey<F>,
+    wl_eval_8n: &[F],
+    wr_eval_8n: &[F],
+    wo_eval_8n: &[F],
+    w4_eval_8n: &[F],
+    z_eval_8n: &[F],
+    alpha: F,
+    beta: F,
+    gamma: F,
+) -> Result<Vec<F>, Error>
+where
+    F: PrimeField,
+{
+    let domain_8n = GeneralEvaluationDomain::<F>::new(8 * domain.size())
+        .ok_or(Error::InvalidEvalDomainSize {
+        log_size_of_group: (8 * domain.size()).trailing_zeros(),
+        adicity:
+            <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+    })?;
+    let l1_poly_alpha =
+        compute_first_lagrange_poly_scaled(domain, alpha.square());
+    let l1_alpha_sq_evals = domain_8n.coset_fft(&l1_poly_alpha.coeffs);
+
+    Ok((0..domain_8n.size())
+        .map(|i| {
+            prover_key.permutation.compute_quotient_i(
+                i,
+                (wl_eval_8n[i], wr_eval_8n[i], wo_eval_8n[i], w4_eval_8n[i]),
+                z_eval_8n[i],
+                z_eval_8n[i + 8],
+                alpha,
+                l1_alpha_sq_evals[i],
+                beta,
+                gamma,
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,120 @@
+//! Proving and verifying keys, split by the part of the protocol using them.
+
+pub mod arithmetic;
+pub mod lookup;
+pub mod permutation;
+
+use crate::{
+    commitment::HomomorphicCommitment, transcript::TranscriptProtocol,
+};
+use ark_ff::PrimeField;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// PLONK circuit Verification Key.
+///
+/// This structure is used by the Verifier in order to verify a
+/// [`Proof`](super::Proof).
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Circuit size (not padded to a power of two).
+    pub(crate) n: usize,
+
+    /// Arithmetic Verifier Key
+    pub(crate) arithmetic: arithmetic::VerifierKey<F, PC>,
+
+    /// VerifierKey for permutation checks
+    pub(crate) permutation: permutation::VerifierKey<F, PC>,
+
+    /// VerifierKey for the lookup argument
+    pub(crate) lookup: lookup::VerifierKey<F, PC>,
+}
+
+impl<F, PC> VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Returns the Circuit size padded to the next power of two.
+    pub fn padded_circuit_size(&self) -> usize {
+        self.n.next_power_of_two()
+    }
+
+    /// Adds the commitments of the key to the transcript, the way
+    /// preprocessing does. Used to seed the transcript of a prover or a
+    /// verifier whose keys were not computed in this run.
+    pub fn seed_transcript<T>(&self, transcript: &mut T)
+    where
+        T: TranscriptProtocol,
+    {
+        transcript.append(b"q_m", &self.arithmetic.q_m);
+        transcript.append(b"q_l", &self.arithmetic.q_l);
+        transcript.append(b"q_r", &self.arithmetic.q_r);
+        transcript.append(b"q_o", &self.arithmetic.q_o);
+        transcript.append(b"q_c", &self.arithmetic.q_c);
+        transcript.append(b"q_4", &self.arithmetic.q_4);
+        transcript.append(b"q_hl", &self.arithmetic.q_hl);
+        transcript.append(b"q_hr", &self.arithmetic.q_hr);
+        transcript.append(b"q_h4", &self.arithmetic.q_h4);
+        transcript.append(b"q_lookup", &self.lookup.q_lookup);
+
+        transcript.append(b"left_sigma", &self.permutation.left_sigma);
+        transcript.append(b"right_sigma", &self.permutation.right_sigma);
+        transcript.append(b"out_sigma", &self.permutation.out_sigma);
+        transcript.append(b"fourth_sigma", &self.permutation.fourth_sigma);
+
+        transcript.append(b"table_1", &self.lookup.table_1);
+        transcript.append(b"table_2", &self.lookup.table_2);
+        transcript.append(b"table_3", &self.lookup.table_3);
+        transcript.append(b"table_4", &self.lookup.table_4);
+
+        transcript.circuit_domain_sep(self.n as u64);
+    }
+}
+
+/// PLONK circuit Proving Key.
+///
+/// This structure is used by the Prover in order to construct a
+/// [`Proof`](super::Proof).
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Eq, PartialEq)]
+pub struct ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Circuit size
+    pub(crate) n: usize,
+
+    /// Arithmetic Prover Key
+    pub(crate) arithmetic: arithmetic::ProverKey<F>,
+
+    /// ProverKey for permutation checks
+    pub(crate) permutation: permutation::ProverKey<F>,
+
+    /// ProverKey for the lookup argument
+    pub(crate) lookup: lookup::ProverKey<F>,
+
+    /// Pre-processes the 8n Evaluations for the vanishing polynomial, so
+    /// they do not need to be computed at the proving stage.
+    ///
+    /// NOTE: With the current implementation, the 8n evaluations are
+    /// recomputed for each proof otherwise.
+    pub(crate) v_h_coset_8n: Vec<F>,
+}
+
+impl<F> ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Returns the evaluations of the vanishing polynomial over the coset of
+    /// size `8n`.
+    pub(crate) fn v_h_coset_8n(&self) -> &[F] {
+        &self.v_h_coset_8n
+    }
+}
@@ -0,0 +1,100 @@
+//! Keys of the arithmetic gate.
+
+use crate::commitment::HomomorphicCommitment;
+use ark_ff::PrimeField;
+use ark_poly::univariate::DensePolynomial;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// Arithmetic gate selectors, each as its polynomial and its evaluations
+/// over the coset of size `8n`.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Eq, PartialEq)]
+pub struct ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Multiplication selector
+    pub q_m: (DensePolynomial<F>, Vec<F>),
+    /// Left wire selector
+    pub q_l: (DensePolynomial<F>, Vec<F>),
+    /// Right wire selector
+    pub q_r: (DensePolynomial<F>, Vec<F>),
+    /// Output wire selector
+    pub q_o: (DensePolynomial<F>, Vec<F>),
+    /// Fourth wire selector
+    pub q_4: (DensePolynomial<F>, Vec<F>),
+    /// Constant selector
+    pub q_c: (DensePolynomial<F>, Vec<F>),
+    /// Left wire fifth power selector
+    pub q_hl: (DensePolynomial<F>, Vec<F>),
+    /// Right wire fifth power selector
+    pub q_hr: (DensePolynomial<F>, Vec<F>),
+    /// Fourth wire fifth power selector
+    pub q_h4: (DensePolynomial<F>, Vec<F>),
+    /// Arithmetic gate enabling selector
+    pub q_arith: (DensePolynomial<F>, Vec<F>),
+}
+
+/// Commitments to the arithmetic gate selectors. `q_arith` is only used
+/// through its evaluation.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Multiplication selector commitment
+    pub q_m: PC::Commitment,
+    /// Left wire selector commitment
+    pub q_l: PC::Commitment,
+    /// Right wire selector commitment
+    pub q_r: PC::Commitment,
+    /// Output wire selector commitment
+    pub q_o: PC::Commitment,
+    /// Fourth wire selector commitment
+    pub q_4: PC::Commitment,
+    /// Constant selector commitment
+    pub q_c: PC::Commitment,
+    /// Left wire fifth power selector commitment
+    pub q_hl: PC::Commitment,
+    /// Right wire fifth power selector commitment
+    pub q_hr: PC::Commitment,
+    /// Fourth wire fifth power selector commitment
+    pub q_h4: PC::Commitment,
+}
+
+impl<F, PC> VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Adds the arithmetic gate terms of the linearisation commitment,
+    /// `q_arith(z)` times the gate equation with the wires replaced by their
+    /// evaluations.
+    pub(crate) fn compute_linearisation_commitment(
+        &self,
+        scalars: &mut Vec<F>,
+        points: &mut Vec<PC::Commitment>,
+        q_arith_eval: F,
+        (a, b, c, d): (F, F, F, F),
+    ) {
+        let terms = [
+            (&self.q_m, a * b),
+            (&self.q_l, a),
+            (&self.q_r, b),
+            (&self.q_o, c),
+            (&self.q_4, d),
+            (&self.q_hl, a.pow([5u64])),
+            (&self.q_hr, b.pow([5u64])),
+            (&self.q_h4, d.pow([5u64])),
+            (&self.q_c, F::one()),
+        ];
+        for (comm, value) in terms {
+            scalars.push(q_arith_eval * value);
+            points.push(comm.clone());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Keys of the lookup argument.
+
+use crate::{
+    commitment::HomomorphicCommitment, error::Error, lookup::MultiSet,
+};
+use ark_ff::PrimeField;
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// Lookup selector and the four columns of the lookup table, padded to the
+/// domain size.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Eq, PartialEq)]
+pub struct ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Lookup selector, with its evaluations over the coset of size `8n`
+    pub q_lookup: (DensePolynomial<F>, Vec<F>),
+    /// First column of the lookup table
+    pub table_1: MultiSet<F>,
+    /// Second column of the lookup table
+    pub table_2: MultiSet<F>,
+    /// Third column of the lookup table
+    pub table_3: MultiSet<F>,
+    /// Fourth column of the lookup table
+    pub table_4: MultiSet<F>,
+}
+
+impl<F> ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Computes the lookup term of the quotient polynomial over the coset
+    /// of size `8n`. With `s` the lookup separation challenge, `ζ` the table
+    /// compression challenge and `w = a + ζ b + ζ² c + ζ³ d`:
+    /// `s q_lookup(X)(w - f(X))
+    ///  + s²[z_2(X)(1 + δ)(ε + f(X))(ε(1 + δ) + t(X) + δ t(Xω))
+    ///      - z_2(Xω)(ε(1 + δ) + h_1(X) + δ h_2(X))(ε(1 + δ) + h_2(X) + δ h_1(Xω))]
+    ///  + s³ L_1(X)(z_2(X) - 1)`.
+    ///
+    /// The evaluations read at `Xω` must be extended by their first 8
+    /// values.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_lookup_quotient_term(
+        &self,
+        domain: &GeneralEvaluationDomain<F>,
+        wl_eval_8n: &[F],
+        wr_eval_8n: &[F],
+        wo_eval_8n: &[F],
+        w4_eval_8n: &[F],
+        f_eval_8n: &[F],
+        table_eval_8n: &[F],
+        h1_eval_8n: &[F],
+        h2_eval_8n: &[F],
+        z2_eval_8n: &[F],
+        l1_eval_8n: &[F],
+        delta: F,
+        epsilon: F,
+        zeta: F,
+        lookup_sep: F,
+    ) -> Result<Vec<F>, Error> {
+        let domain_8n = GeneralEvaluationDomain::<F>::new(8 * domain.size())
+            .ok_or(Error::InvalidEvalDomainSize {
+                log_size_of_group: (8 * domain.size()).trailing_zeros(),
+                adicity: <<F as ark_ff::FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            })?;
+
+        let one_plus_delta = F::one() + delta;
+        let epsilon_one_plus_delta = epsilon * one_plus_delta;
+        let lookup_sep_sq = lookup_sep.square();
+        let lookup_sep_cu = lookup_sep_sq * lookup_sep;
+        let zeta_sq = zeta.square();
+        let zeta_cu = zeta_sq * zeta;
+
+        Ok((0..domain_8n.size())
+            .map(|i| {
+                let compressed = wl_eval_8n[i]
+                    + zeta * wr_eval_8n[i]
+                    + zeta_sq * wo_eval_8n[i]
+                    + zeta_cu * w4_eval_8n[i];
+                let query = self.q_lookup.1[i] * (compressed - f_eval_8n[i]);
+
+                let numerator = z2_eval_8n[i]
+                    * one_plus_delta
+                    * (epsilon + f_eval_8n[i])
+                    * (epsilon_one_plus_delta
+                        + table_eval_8n[i]
+                        + delta * table_eval_8n[i + 8]);
+                let denominator = z2_eval_8n[i + 8]
+                    * (epsilon_one_plus_delta
+                        + h1_eval_8n[i]
+                        + delta * h2_eval_8n[i])
+                    * (epsilon_one_plus_delta
+                        + h2_eval_8n[i]
+                        + delta * h1_eval_8n[i + 8]);
+
+                let first = l1_eval_8n[i] * (z2_eval_8n[i] - F::one());
+
+                lookup_sep * query
+                    + lookup_sep_sq * (numerator - denominator)
+                    + lookup_sep_cu * first
+            })
+            .collect())
+    }
+
+    /// Computes the lookup term of the linearisation polynomial, see
+    /// [`ProverKey::compute_lookup_quotient_term`]. The constant part is
+    /// left to `r_0`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_linearisation(
+        &self,
+        l1_eval: F,
+        a_eval: F,
+        b_eval: F,
+        c_eval: F,
+        d_eval: F,
+        f_eval: F,
+        table_eval: F,
+        table_next_eval: F,
+        h1_next_eval: F,
+        h2_eval: F,
+        z2_next_eval: F,
+        delta: F,
+        epsilon: F,
+        zeta: F,
+        z2_poly: &DensePolynomial<F>,
+        h1_poly: &DensePolynomial<F>,
+        lookup_sep: F,
+    ) -> DensePolynomial<F> {
+        let [q_lookup_scalar, z2_scalar, h1_scalar] = lookup_scalars(
+            l1_eval,
+            (a_eval, b_eval, c_eval, d_eval),
+            f_eval,
+            table_eval,
+            table_next_eval,
+            h1_next_eval,
+            h2_eval,
+            z2_next_eval,
+            (delta, epsilon, zeta, lookup_sep),
+        );
+        &(&(&self.q_lookup.0 * q_lookup_scalar) + &(z2_poly * z2_scalar))
+            + &(h1_poly * h1_scalar)
+    }
+}
+
+/// Scalars of `q_lookup(X)`, `z_2(X)` and `h_1(X)` in the lookup term of the
+/// linearisation polynomial.
+#[allow(clippy::too_many_arguments)]
+fn lookup_scalars<F>(
+    l1_eval: F,
+    (a_eval, b_eval, c_eval, d_eval): (F, F, F, F),
+    f_eval: F,
+    table_eval: F,
+    table_next_eval: F,
+    h1_next_eval: F,
+    h2_eval: F,
+    z2_next_eval: F,
+    (delta, epsilon, zeta, lookup_sep): (F, F, F, F),
+) -> [F; 3]
+where
+    F: PrimeField,
+{
+    let one_plus_delta = F::one() + delta;
+    let epsilon_one_plus_delta = epsilon * one_plus_delta;
+    let lookup_sep_sq = lookup_sep.square();
+    let compressed = a_eval + zeta * (b_eval + zeta * (c_eval + zeta * d_eval));
+
+    let q_lookup_scalar = lookup_sep * (compressed - f_eval);
+    let z2_scalar = lookup_sep_sq
+        * one_plus_delta
+        * (epsilon + f_eval)
+        * (epsilon_one_plus_delta + table_eval + delta * table_next_eval)
+        + lookup_sep_sq * lookup_sep * l1_eval;
+    let h1_scalar = -lookup_sep_sq
+        * z2_next_eval
+        * (epsilon_one_plus_delta + h2_eval + delta * h1_next_eval);
+    [q_lookup_scalar, z2_scalar, h1_scalar]
+}
+
+/// Commitments to the lookup selector and table columns.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Lookup selector commitment
+    pub q_lookup: PC::Commitment,
+    /// First table column commitment
+    pub table_1: PC::Commitment,
+    /// Second table column commitment
+    pub table_2: PC::Commitment,
+    /// Third table column commitment
+    pub table_3: PC::Commitment,
+    /// Fourth table column commitment
+    pub table_4: PC::Commitment,
+}
+
+impl<F, PC> VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Adds the lookup terms of the linearisation commitment, see
+    /// [`ProverKey::compute_linearisation`].
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_linearisation_commitment(
+        &self,
+        scalars: &mut Vec<F>,
+        points: &mut Vec<PC::Commitment>,
+        (z2_comm, h1_comm): (&PC::Commitment, &PC::Commitment),
+        l1_eval: F,
+        wire_evals: (F, F, F, F),
+        f_eval: F,
+        table_eval: F,
+        table_next_eval: F,
+        h1_next_eval: F,
+        h2_eval: F,
+        z2_next_eval: F,
+        challenges: (F, F, F, F),
+    ) {
+        let [q_lookup_scalar, z2_scalar, h1_scalar] = lookup_scalars(
+            l1_eval,
+            wire_evals,
+            f_eval,
+            table_eval,
+            table_next_eval,
+            h1_next_eval,
+            h2_eval,
+            z2_next_eval,
+            challenges,
+        );
+        scalars.extend([q_lookup_scalar, z2_scalar, h1_scalar]);
+        points.extend([
+            self.q_lookup.clone(),
+            z2_comm.clone(),
+            h1_comm.clone(),
+        ]);
+    }
+}
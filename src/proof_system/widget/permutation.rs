@@ -0,0 +1,171 @@
+//! Keys of the permutation argument.
+
+use crate::{
+    commitment::HomomorphicCommitment,
+    error::Error,
+    permutation::constants::{K1, K2, K3},
+    proof_system::linearisation_poly::compute_first_lagrange_evaluation,
+};
+use ark_ff::PrimeField;
+use ark_poly::{
+    univariate::DensePolynomial, EvaluationDomain, GeneralEvaluationDomain,
+};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write,
+};
+
+/// Sigma polynomials, each with its evaluations over the coset of size `8n`.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone, Debug, Eq, PartialEq)]
+pub struct ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Left Permutation
+    pub left_sigma: (DensePolynomial<F>, Vec<F>),
+    /// Right Permutation
+    pub right_sigma: (DensePolynomial<F>, Vec<F>),
+    /// Output Permutation
+    pub out_sigma: (DensePolynomial<F>, Vec<F>),
+    /// Fourth Permutation
+    pub fourth_sigma: (DensePolynomial<F>, Vec<F>),
+    /// Evaluations of `X` over the coset of size `8n`
+    pub linear_evaluations: Vec<F>,
+}
+
+impl<F> ProverKey<F>
+where
+    F: PrimeField,
+{
+    /// Computes the permutation term of the quotient polynomial at the
+    /// `index`-th point of the coset of size `8n`:
+    /// `α[Π(w_j + β k_j X + γ) z(X) - Π(w_j + β σ_j(X) + γ) z(Xω)]
+    ///  + α² L_1(X)(z(X) - 1)`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_quotient_i(
+        &self,
+        index: usize,
+        (a, b, c, d): (F, F, F, F),
+        z_i: F,
+        z_i_next: F,
+        alpha: F,
+        l1_alpha_sq: F,
+        beta: F,
+        gamma: F,
+    ) -> F {
+        let x = self.linear_evaluations[index];
+        let identity = (a + beta * x + gamma)
+            * (b + beta * K1::<F>() * x + gamma)
+            * (c + beta * K2::<F>() * x + gamma)
+            * (d + beta * K3::<F>() * x + gamma)
+            * z_i;
+        let copy = (a + beta * self.left_sigma.1[index] + gamma)
+            * (b + beta * self.right_sigma.1[index] + gamma)
+            * (c + beta * self.out_sigma.1[index] + gamma)
+            * (d + beta * self.fourth_sigma.1[index] + gamma)
+            * z_i_next;
+        alpha * (identity - copy) + l1_alpha_sq * (z_i - F::one())
+    }
+
+    /// Computes the permutation term of the linearisation polynomial:
+    /// `z(X)[α Π(w_j + β k_j z + γ) + α² L_1(z)]
+    ///  - σ_4(X) α β z(zω) Π_{j<3}(w_j + β σ_j(z) + γ)`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_linearisation(
+        &self,
+        n: usize,
+        z_challenge: F,
+        (alpha, beta, gamma): (F, F, F),
+        (a_eval, b_eval, c_eval, d_eval): (F, F, F, F),
+        (sigma_1_eval, sigma_2_eval, sigma_3_eval): (F, F, F),
+        z_eval: F,
+        z_poly: &DensePolynomial<F>,
+    ) -> Result<DensePolynomial<F>, Error> {
+        let domain = GeneralEvaluationDomain::<F>::new(n).ok_or(
+            Error::InvalidEvalDomainSize {
+                log_size_of_group: n.trailing_zeros(),
+                adicity: <<F as ark_ff::FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            },
+        )?;
+        let z_h_eval = domain.evaluate_vanishing_polynomial(z_challenge);
+        let l1_eval =
+            compute_first_lagrange_evaluation(&domain, &z_h_eval, &z_challenge);
+
+        let beta_z = beta * z_challenge;
+        let z_scalar = alpha
+            * (a_eval + beta_z + gamma)
+            * (b_eval + K1::<F>() * beta_z + gamma)
+            * (c_eval + K2::<F>() * beta_z + gamma)
+            * (d_eval + K3::<F>() * beta_z + gamma)
+            + l1_eval * alpha.square();
+        let sigma_4_scalar = alpha
+            * beta
+            * z_eval
+            * (a_eval + beta * sigma_1_eval + gamma)
+            * (b_eval + beta * sigma_2_eval + gamma)
+            * (c_eval + beta * sigma_3_eval + gamma);
+
+        Ok(&(z_poly * z_scalar) - &(&self.fourth_sigma.0 * sigma_4_scalar))
+    }
+}
+
+/// Commitments to the sigma polynomials.
+#[derive(CanonicalDeserialize, CanonicalSerialize, derivative::Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Left Permutation Commitment
+    pub left_sigma: PC::Commitment,
+    /// Right Permutation Commitment
+    pub right_sigma: PC::Commitment,
+    /// Output Permutation Commitment
+    pub out_sigma: PC::Commitment,
+    /// Fourth Permutation Commitment
+    pub fourth_sigma: PC::Commitment,
+}
+
+impl<F, PC> VerifierKey<F, PC>
+where
+    F: PrimeField,
+    PC: HomomorphicCommitment<F>,
+{
+    /// Adds the permutation terms of the linearisation commitment, see
+    /// [`ProverKey::compute_linearisation`].
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn compute_linearisation_commitment(
+        &self,
+        scalars: &mut Vec<F>,
+        points: &mut Vec<PC::Commitment>,
+        z_comm: &PC::Commitment,
+        z_challenge: F,
+        (alpha, beta, gamma): (F, F, F),
+        (a_eval, b_eval, c_eval, d_eval): (F, F, F, F),
+        (sigma_1_eval, sigma_2_eval, sigma_3_eval): (F, F, F),
+        z_eval: F,
+        l1_eval: F,
+    ) {
+        let beta_z = beta * z_challenge;
+        scalars.push(
+            alpha
+                * (a_eval + beta_z + gamma)
+                * (b_eval + K1::<F>() * beta_z + gamma)
+                * (c_eval + K2::<F>() * beta_z + gamma)
+                * (d_eval + K3::<F>() * beta_z + gamma)
+                + l1_eval * alpha.square(),
+        );
+        points.push(z_comm.clone());
+
+        scalars.push(
+            -alpha
+                * beta
+                * z_eval
+                * (a_eval + beta * sigma_1_eval + gamma)
+                * (b_eval + beta * sigma_2_eval + gamma)
+                * (c_eval + beta * sigma_3_eval + gamma),
+        );
+        points.push(self.fourth_sigma.clone());
+    }
+}
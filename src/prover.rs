@@ -1,9 +1,13 @@
-use crate::lookup_table;
+//! Prover-side of the PLONK Proving System
+
 use crate::{
+    assignment::Assignment,
     commitment::HomomorphicCommitment,
     constraint_system::{CircuitBuilder, Variable},
     error::{to_pc_error, Error},
+    key_cache::KeyCache,
     label_polynomial,
+    lookup::MultiSet,
     proof_system::{
         linearisation_poly, proof::Proof, quotient_poly, ProverKey,
     },
@@ -18,440 +22,44 @@ use ark_poly::{
 use core::marker::PhantomData;
 use itertools::izip;
 use merlin::Transcript;
-pub struct Proof<F, PC>
-where
-    F: PrimeField,
-    PC: HomomorphicCommitment<F>,
-{
-    /// Commitment to the witness polynomial for the left wires.
-    pub(crate) a_comm: PC::Commitment,
-
-    /// Commitment to the witness polynomial for the right wires.
-    pub(crate) b_comm: PC::Commitment,
-
-    /// Commitment to the witness polynomial for the output wires.
-    pub(crate) c_comm: PC::Commitment,
-
-    /// Commitment to the witness polynomial for the fourth wires.
-    pub(crate) d_comm: PC::Commitment,
-
-    /// Commitment to the permutation polynomial.
-    pub(crate) z_comm: PC::Commitment,
-
-    /// Commitment to the lookup query polynomial.
-    pub(crate) f_comm: PC::Commitment,
-
-    /// Commitment to first half of sorted polynomial
-    pub(crate) h_1_comm: PC::Commitment,
-
-    /// Commitment to second half of sorted polynomial
-    pub(crate) h_2_comm: PC::Commitment,
-
-    /// Commitment to the lookup permutation polynomial.
-    pub(crate) z_2_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_1_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_2_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_3_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_4_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_5_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_6_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_7_comm: PC::Commitment,
-
-    /// Commitment to the quotient polynomial.
-    pub(crate) t_8_comm: PC::Commitment,
-
-    /// Batch opening proof of the aggregated witnesses
-    pub aw_opening: PC::Proof,
-
-    /// Batch opening proof of the shifted aggregated witnesses
-    pub saw_opening: PC::Proof,
-
-    /// Subset of all of the evaluations added to the proof.
-    pub(crate) evaluations: ProofEvaluations<F>,
-}
-
-impl<F, PC> Proof<F, PC>
-where
-    F: PrimeField,
-    PC: HomomorphicCommitment<F>,
-{
-    /// Performs the verification of a [`Proof`] returning a boolean result.
-    pub(crate) fn verify<P>(
-        &self,
-        plonk_verifier_key: &PlonkVerifierKey<F, PC>,
-        transcript: &mut Transcript,
-        verifier_key: &PC::VerifierKey,
-        pub_inputs: &PublicInputs<F>,
-    ) -> Result<(), Error>
-    where
-        P: TEModelParameters<BaseField = F>,
-    {
-        let domain =
-            GeneralEvaluationDomain::<F>::new(plonk_verifier_key.n).ok_or(Error::InvalidEvalDomainSize {
-                log_size_of_group: plonk_verifier_key.n.trailing_zeros(),
-                adicity: <<F as FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
-            })?;
-
-        // Append Public Inputs to the transcript
-        transcript.append(b"pi", pub_inputs);
-
-        // Subgroup checks are done when the proof is deserialised.
-
-        // In order for the Verifier and Prover to have the same view in the
-        // non-interactive setting Both parties must commit the same
-        // elements into the transcript Below the verifier will simulate
-        // an interaction with the prover by adding the same elements
-        // that the prover added into the transcript, hence generating the
-        // same challenges
-        //
-        // Add commitment to witness polynomials to transcript
-        transcript.append(b"w_l", &self.a_comm);
-        transcript.append(b"w_r", &self.b_comm);
-        transcript.append(b"w_o", &self.c_comm);
-        transcript.append(b"w_4", &self.d_comm);
-
-        // Compute table compression challenge `zeta`.
-        let zeta = transcript.challenge_scalar(b"zeta");
-        transcript.append(b"zeta", &zeta);
-
-        // Add f_poly commitment to transcript
-        transcript.append(b"f", &self.f_comm);
-
-        // Add h polynomials to transcript
-        transcript.append(b"h1", &self.h_1_comm);
-        transcript.append(b"h2", &self.h_2_comm);
-
-        // Compute permutation challenges and add them to transcript
-
-        // Compute permutation challenge `beta`.
-        let beta = transcript.challenge_scalar(b"beta");
-        transcript.append(b"beta", &beta);
-
-        // Compute permutation challenge `gamma`.
-        let gamma = transcript.challenge_scalar(b"gamma");
-        transcript.append(b"gamma", &gamma);
-
-        // Compute permutation challenge `delta`.
-        let delta = transcript.challenge_scalar(b"delta");
-        transcript.append(b"delta", &delta);
-
-        // Compute permutation challenge `epsilon`.
-        let epsilon = transcript.challenge_scalar(b"epsilon");
-        transcript.append(b"epsilon", &epsilon);
-
-        // Challenges must be different
-        assert!(beta != gamma, "challenges must be different");
-        assert!(beta != delta, "challenges must be different");
-        assert!(beta != epsilon, "challenges must be different");
-        assert!(gamma != delta, "challenges must be different");
-        assert!(gamma != epsilon, "challenges must be different");
-        assert!(delta != epsilon, "challenges must be different");
-
-        // Add commitment to permutation polynomial to transcript
-        transcript.append(b"z", &self.z_comm);
-
-        // Compute quotient challenge
-        let alpha = transcript.challenge_scalar(b"alpha");
-        transcript.append(b"alpha", &alpha);
-        let range_sep_challenge =
-            transcript.challenge_scalar(b"range separation challenge");
-        transcript.append(b"range seperation challenge", &range_sep_challenge);
-
-        let logic_sep_challenge =
-            transcript.challenge_scalar(b"logic separation challenge");
-        transcript.append(b"logic seperation challenge", &logic_sep_challenge);
-
-        let fixed_base_sep_challenge =
-            transcript.challenge_scalar(b"fixed base separation challenge");
-        transcript.append(
-            b"fixed base separation challenge",
-            &fixed_base_sep_challenge,
-        );
-
-        let var_base_sep_challenge =
-            transcript.challenge_scalar(b"variable base separation challenge");
-        transcript.append(
-            b"variable base separation challenge",
-            &var_base_sep_challenge,
-        );
-
-        let lookup_sep_challenge =
-            transcript.challenge_scalar(b"lookup separation challenge");
-        transcript
-            .append(b"lookup separation challenge", &lookup_sep_challenge);
-
-        // Add commitment to quotient polynomial to transcript
-        transcript.append(b"t_1", &self.t_1_comm);
-        transcript.append(b"t_2", &self.t_2_comm);
-        transcript.append(b"t_3", &self.t_3_comm);
-        transcript.append(b"t_4", &self.t_4_comm);
-        transcript.append(b"t_5", &self.t_5_comm);
-        transcript.append(b"t_6", &self.t_6_comm);
-        transcript.append(b"t_7", &self.t_7_comm);
-        transcript.append(b"t_8", &self.t_8_comm);
-
-        // Compute evaluation point challenge
-        let z_challenge = transcript.challenge_scalar(b"z");
-        transcript.append(b"z", &z_challenge);
-
-        // Compute zero polynomial evaluated at `z_challenge`
-        let z_h_eval = domain.evaluate_vanishing_polynomial(z_challenge);
-
-        // Compute first lagrange polynomial evaluated at `z_challenge`
-        let l1_eval =
-            compute_first_lagrange_evaluation(&domain, &z_h_eval, &z_challenge);
-
-        let r0 = self.compute_r0(
-            &domain,
-            &pub_inputs.as_evals(domain.size()),
-            alpha,
-            beta,
-            gamma,
-            delta,
-            epsilon,
-            z_challenge,
-            l1_eval,
-            self.evaluations.perm_evals.permutation_eval,
-            self.evaluations.lookup_evals.z2_next_eval,
-            self.evaluations.lookup_evals.h1_next_eval,
-            self.evaluations.lookup_evals.h2_eval,
-            lookup_sep_challenge,
-        );
-
-        // Add evaluations to transcript
-        transcript.append(b"a_eval", &self.evaluations.wire_evals.a_eval);
-        transcript.append(b"b_eval", &self.evaluations.wire_evals.b_eval);
-        transcript.append(b"c_eval", &self.evaluations.wire_evals.c_eval);
-        transcript.append(b"d_eval", &self.evaluations.wire_evals.d_eval);
-
-        transcript.append(
-            b"left_sig_eval",
-            &self.evaluations.perm_evals.left_sigma_eval,
-        );
-        transcript.append(
-            b"right_sig_eval",
-            &self.evaluations.perm_evals.right_sigma_eval,
-        );
-        transcript.append(
-            b"out_sig_eval",
-            &self.evaluations.perm_evals.out_sigma_eval,
-        );
-        transcript.append(
-            b"perm_eval",
-            &self.evaluations.perm_evals.permutation_eval,
-        );
-
-        transcript.append(b"f_eval", &self.evaluations.lookup_evals.f_eval);
-        transcript.append(
-            b"q_lookup_eval",
-            &self.evaluations.lookup_evals.q_lookup_eval,
-        );
-        transcript.append(
-            b"lookup_perm_eval",
-            &self.evaluations.lookup_evals.z2_next_eval,
-        );
-        transcript.append(b"h_1_eval", &self.evaluations.lookup_evals.h1_eval);
-        transcript.append(
-            b"h_1_next_eval",
-            &self.evaluations.lookup_evals.h1_next_eval,
-        );
-        transcript.append(b"h_2_eval", &self.evaluations.lookup_evals.h2_eval);
-
-        self.evaluations
-            .custom_evals
-            .vals
-            .iter()
-            .for_each(|(label, eval)| {
-                let static_label = Box::leak(label.to_owned().into_boxed_str());
-                transcript.append(static_label.as_bytes(), eval);
-            });
-
-        // Compute linearisation commitment
-        let lin_comm = self.compute_linearisation_commitment::<P>(
-            &domain,
-            alpha,
-            beta,
-            gamma,
-            delta,
-            epsilon,
-            zeta,
-            range_sep_challenge,
-            logic_sep_challenge,
-            fixed_base_sep_challenge,
-            var_base_sep_challenge,
-            lookup_sep_challenge,
-            z_challenge,
-            l1_eval,
-            plonk_verifier_key,
-        );
-
-        let zeta_sq = zeta.square();
-        let table_comm = PC::multi_scalar_mul(
-            &[
-                plonk_verifier_key.lookup.table_1.clone(),
-                plonk_verifier_key.lookup.table_2.clone(),
-                plonk_verifier_key.lookup.table_3.clone(),
-                plonk_verifier_key.lookup.table_4.clone(),
-            ],
-            &[F::one(), zeta, zeta_sq, zeta_sq * zeta],
-        );
-
-        // Commitment Scheme
-        // Now we delegate computation to the commitment scheme by batch
-        // checking two proofs.
-        //
-        // The `AggregateProof`, which proves that all the necessary
-        // polynomials evaluated at `z_challenge` are
-        // correct and a `Proof` which is proof that the
-        // permutation polynomial evaluated at the shifted root of unity is
-        // correct
-
-        // Generation of the first aggregated proof: It ensures that the
-        // polynomials evaluated at `z_challenge` are correct.
-
-        // Reconstruct the Aggregated Proof commitments and evals
-        // The proof consists of the witness commitment with no blinder
-
-        // Compute aggregate witness to polynomials evaluated at the evaluation
-        // challenge `z`
-        let aw_challenge: F = transcript.challenge_scalar(b"aggregate_witness");
-
-        let aw_commits = [
-            label_commitment!(lin_comm),
-            label_commitment!(plonk_verifier_key.permutation.left_sigma),
-            label_commitment!(plonk_verifier_key.permutation.right_sigma),
-            label_commitment!(plonk_verifier_key.permutation.out_sigma),
-            label_commitment!(self.f_comm),
-            label_commitment!(self.h_2_comm),
-            label_commitment!(table_comm),
-            label_commitment!(self.a_comm),
-            label_commitment!(self.b_comm),
-            label_commitment!(self.c_comm),
-            label_commitment!(self.d_comm),
-        ];
-
-        let aw_evals = [
-            -r0,
-            self.evaluations.perm_evals.left_sigma_eval,
-            self.evaluations.perm_evals.right_sigma_eval,
-            self.evaluations.perm_evals.out_sigma_eval,
-            self.evaluations.lookup_evals.f_eval,
-            self.evaluations.lookup_evals.h2_eval,
-            self.evaluations.lookup_evals.table_eval,
-            self.evaluations.wire_evals.a_eval,
-            self.evaluations.wire_evals.b_eval,
-            self.evaluations.wire_evals.c_eval,
-            self.evaluations.wire_evals.d_eval,
-        ];
-
-        let saw_challenge: F =
-            transcript.challenge_scalar(b"aggregate_witness");
-
-        let saw_commits = [
-            label_commitment!(self.z_comm),
-            label_commitment!(self.a_comm),
-            label_commitment!(self.b_comm),
-            label_commitment!(self.d_comm),
-            label_commitment!(self.h_1_comm),
-            label_commitment!(self.z_2_comm),
-            label_commitment!(table_comm),
-        ];
-
-        let saw_evals = [
-            self.evaluations.perm_evals.permutation_eval,
-            self.evaluations.custom_evals.get("a_next_eval"),
-            self.evaluations.custom_evals.get("b_next_eval"),
-            self.evaluations.custom_evals.get("d_next_eval"),
-            self.evaluations.lookup_evals.h1_next_eval,
-            self.evaluations.lookup_evals.z2_next_eval,
-            self.evaluations.lookup_evals.table_next_eval,
-        ];
-
-        match PC::check(
-            verifier_key,
-            &aw_commits,
-            &z_challenge,
-            aw_evals,
-            &self.aw_opening,
-            aw_challenge,
-            None,
-        ) {
-            Ok(true) => Ok(()),
-            Ok(false) => Err(Error::ProofVerificationError),
-            Err(e) => panic!("{:?}", e),
-        }
-        .and_then(|_| {
-            match PC::check(
-                verifier_key,
-                &saw_commits,
-                &(z_challenge * domain.element(1)),
-                saw_evals,
-                &self.saw_opening,
-                saw_challenge,
-                None,
-            ) {
-                Ok(true) => Ok(()),
-                Ok(false) => Err(Error::ProofVerificationError),
-                Err(e) => panic!("{:?}", e),
-            }
-        })
-    }
-
-}
-
+use rand_core::OsRng;
 
-
-
-
-
-
-
-pub struct Prover<F, P, PC>
+/// Abstraction structure designed to construct a circuit and generate
+/// [`Proof`]s for it.
+pub struct Prover<F, P, PC, T = Transcript>
 where
     F: PrimeField,
     P: ModelParameters<BaseField = F>,
     PC: HomomorphicCommitment<F>,
+    T: TranscriptProtocol + Clone,
 {
     /// Proving Key which is used to create proofs about a specific PLONK
     /// circuit.
     pub prover_key: Option<ProverKey<F>>,
 
     /// Circuit Description
-    pub(crate) cs: StandardComposer<F, P>,
+    pub(crate) cs: CircuitBuilder<F, P>,
 
     /// Store the messages exchanged during the preprocessing stage.
     ///
     /// This is copied each time, we make a proof.
-    pub preprocessed_transcript: Transcript,
+    pub preprocessed_transcript: T,
 
     _phantom: PhantomData<PC>,
 }
-impl<F, P, PC> Prover<F, P, PC>
+impl<F, P, PC, T> Prover<F, P, PC, T>
 where
     F: PrimeField,
     P: TEModelParameters<BaseField = F>,
     PC: HomomorphicCommitment<F>,
+    T: TranscriptProtocol + Clone,
 {
     /// Creates a new `Prover` instance.
     pub fn new(label: &'static [u8]) -> Self {
         Self {
             prover_key: None,
-            cs: StandardComposer::new(),
-            preprocessed_transcript: Transcript::new(label),
+            cs: CircuitBuilder::new(),
+            preprocessed_transcript: T::new(label),
             _phantom: PhantomData::<PC>,
         }
     }
@@ -460,14 +68,14 @@ where
     pub fn with_expected_size(label: &'static [u8], size: usize) -> Self {
         Self {
             prover_key: None,
-            cs: StandardComposer::with_expected_size(size),
-            preprocessed_transcript: Transcript::new(label),
+            cs: CircuitBuilder::with_expected_size(size),
+            preprocessed_transcript: T::new(label),
             _phantom: PhantomData::<PC>,
         }
     }
 
-    /// Returns a mutable copy of the underlying [`StandardComposer`].
-    pub fn mut_cs(&mut self) -> &mut StandardComposer<F, P> {
+    /// Returns a mutable copy of the underlying [`CircuitBuilder`].
+    pub fn mut_cs(&mut self) -> &mut CircuitBuilder<F, P> {
         &mut self.cs
     }
 
@@ -493,6 +101,42 @@ where
         Ok(())
     }
 
+    /// Preprocesses the underlying constraint system, loading the keys from
+    /// `cache` when it holds an entry for the same
+    /// [digest](CircuitBuilder::digest). Otherwise the keys are computed
+    /// and stored for the next run.
+    ///
+    /// Returns whether the keys were loaded from the cache.
+    pub fn preprocess_cached(
+        &mut self,
+        commit_key: &PC::CommitterKey,
+        cache: &KeyCache,
+    ) -> Result<bool, Error> {
+        if self.prover_key.is_some() {
+            return Err(Error::CircuitAlreadyPreprocessed);
+        }
+        let digest = self.cs.digest::<PC>(commit_key);
+        let cached = cache
+            .load::<F, PC>(&digest)
+            .map_err(|e| Error::KeyCache(e.to_string()))?;
+        if let Some((pk, vk)) = cached {
+            vk.seed_transcript(&mut self.preprocessed_transcript);
+            self.prover_key = Some(pk);
+            return Ok(true);
+        }
+
+        let (pk, vk) = self.cs.preprocess(
+            commit_key,
+            &mut self.preprocessed_transcript,
+            PhantomData::<PC>,
+        )?;
+        cache
+            .store(&digest, &pk, &vk)
+            .map_err(|e| Error::KeyCache(e.to_string()))?;
+        self.prover_key = Some(pk);
+        Ok(false)
+    }
+
     /// Split `t(X)` poly into 8 n-sized polynomials.
     #[allow(clippy::type_complexity)] // NOTE: This is an ok type for internal use.
     fn split_tx_poly(
@@ -515,17 +159,35 @@ where
         ]
     }
 
-    /// Convert variables to their actual witness values.
-    fn to_scalars(&self, vars: &[Variable]) -> Vec<F> {
-        vars.iter().map(|var| self.cs.variables[var]).collect()
+    /// Convert variables to their actual witness values, failing on the
+    /// first variable synthesized without one.
+    fn to_scalars(&self, vars: &[Variable]) -> Result<Vec<F>, Error> {
+        vars.iter()
+            .map(|var| self.cs.value(*var).ok_or(Error::MissingWitness(*var)))
+            .collect()
     }
 
-    /// Resets the witnesses in the prover object.
+    /// Resets the witnesses in the prover object, keeping the circuit and
+    /// its [`ProverKey`].
     ///
     /// This function is used when the user wants to make multiple proofs with
-    /// the same circuit.
+    /// the same circuit, see [`Prover::assign_witness`].
     pub fn clear_witness(&mut self) {
-        self.cs = StandardComposer::new();
+        self.cs.clear_witness();
+    }
+
+    /// Assigns the witness and public inputs of the next proof without
+    /// rebuilding the circuit.
+    ///
+    /// The assignment can come from [`CircuitBuilder::assignment`] on a
+    /// circuit synthesized with the new values, or from a witness file. It
+    /// must give a value to exactly the witness variables and public input
+    /// positions of the circuit.
+    pub fn assign_witness(
+        &mut self,
+        assignment: &Assignment<F>,
+    ) -> Result<(), Error> {
+        self.cs.assign(assignment)
     }
 
     /// Clears all data in the [`Prover`] instance.
@@ -533,16 +195,13 @@ where
     /// This function is used when the user wants to use the same `Prover` to
     /// make a [`Proof`] regarding a different circuit.
     pub fn clear(&mut self) {
-        self.clear_witness();
+        self.cs = CircuitBuilder::new();
         self.prover_key = None;
-        self.preprocessed_transcript = Transcript::new(b"plonk");
+        self.preprocessed_transcript = T::new(b"plonk");
     }
 
-    /// Keys the [`Transcript`] with additional seed information
-    /// Wrapper around [`Transcript::append_message`].
-    ///
-    /// [`Transcript`]: merlin::Transcript
-    /// [`Transcript::append_message`]: merlin::Transcript::append_message
+    /// Keys the transcript with additional seed information
+    /// Wrapper around [`TranscriptProtocol::append_message`].
     pub fn key_transcript(&mut self, label: &'static [u8], message: &[u8]) {
         self.preprocessed_transcript.append_message(label, message);
     }
@@ -571,18 +230,23 @@ where
         // Commitments
         let mut transcript = self.preprocessed_transcript.clone();
 
-        // Append Public Inputs to the transcript
-        transcript.append(b"pi", self.cs.get_pi());
+        // Append Public Inputs to the transcript, one position and value at a
+        // time so fixed-width transcripts can absorb them.
+        let pi = self.cs.get_pi();
+        pi.get_pos().zip(pi.get_vals()).for_each(|(pos, value)| {
+            transcript.append(b"pi_pos", &F::from(*pos as u64));
+            transcript.append(b"pi", value);
+        });
 
         // 1. Compute witness Polynomials
         //
         // Convert Variables to scalars padding them to the
         // correct domain size.
         let pad = vec![F::zero(); n - self.cs.w_l.len()];
-        let w_l_scalar = &[&self.to_scalars(&self.cs.w_l)[..], &pad].concat();
-        let w_r_scalar = &[&self.to_scalars(&self.cs.w_r)[..], &pad].concat();
-        let w_o_scalar = &[&self.to_scalars(&self.cs.w_o)[..], &pad].concat();
-        let w_4_scalar = &[&self.to_scalars(&self.cs.w_4)[..], &pad].concat();
+        let w_l_scalar = &[&self.to_scalars(&self.cs.w_l)?[..], &pad].concat();
+        let w_r_scalar = &[&self.to_scalars(&self.cs.w_r)?[..], &pad].concat();
+        let w_o_scalar = &[&self.to_scalars(&self.cs.w_o)?[..], &pad].concat();
+        let w_4_scalar = &[&self.to_scalars(&self.cs.w_4)?[..], &pad].concat();
 
         // Witnesses are now in evaluation form, convert them to coefficients
         // so that we may commit to them.
@@ -595,11 +259,14 @@ where
         let w_4_poly =
             DensePolynomial::from_coefficients_vec(domain.ifft(w_4_scalar));
 
+        // Witness, query and sorted polynomials are committed with the
+        // degree bound of the scheme.
+        let bound = PC::degree_bound(n);
         let w_polys = [
-            label_polynomial!(w_l_poly),
-            label_polynomial!(w_r_poly),
-            label_polynomial!(w_o_poly),
-            label_polynomial!(w_4_poly),
+            label_polynomial!(w_l_poly, bound),
+            label_polynomial!(w_r_poly, bound),
+            label_polynomial!(w_o_poly, bound),
+            label_polynomial!(w_4_poly, bound),
         ];
 
         // Commit to witness polynomials.
@@ -646,8 +313,8 @@ where
         let padded_q_lookup =
             &[self.cs.q_lookup.as_slice(), q_lookup_pad.as_slice()].concat();
 
-        let mut f_scalars: Vec<MultiSet<F>> =
-            vec![MultiSet::with_capacity(w_l_scalar.len()); 4];
+        let mut f_scalars: [MultiSet<F>; 4] =
+            core::array::from_fn(|_| MultiSet::with_capacity(n));
 
         for (q_lookup, w_l, w_r, w_o, w_4) in izip!(
             padded_q_lookup,
@@ -680,16 +347,15 @@ where
 
         // Commit to query polynomial
         let (f_poly_commit, _) =
-            PC::commit(commit_key, &[label_polynomial!(f_poly)], None)
+            PC::commit(commit_key, &[label_polynomial!(f_poly, bound)], None)
                 .map_err(to_pc_error::<F, PC>)?;
 
         // Add f_poly commitment to transcript
         transcript.append(b"f", f_poly_commit[0].commitment());
 
         // Compute s, as the sorted and concatenated version of f and t
-        let (h_1, h_2) = compressed_t_multiset
-            .combine_split(&compressed_f_multiset)
-            .unwrap();
+        let (h_1, h_2) =
+            compressed_t_multiset.combine_split(&compressed_f_multiset)?;
 
         // Compute h polys
         let h_1_poly =
@@ -706,7 +372,7 @@ where
             PC::commit(commit_key, &[label_polynomial!(h_1_poly)], None)
                 .map_err(to_pc_error::<F, PC>)?;
         let (h_2_poly_commit, _) =
-            PC::commit(commit_key, &[label_polynomial!(h_2_poly)], None)
+            PC::commit(commit_key, &[label_polynomial!(h_2_poly, bound)], None)
                 .map_err(to_pc_error::<F, PC>)?;
 
         // Add h polynomials to transcript
@@ -786,39 +452,17 @@ where
 
         // 4. Compute quotient polynomial
         //
-        // Compute quotient challenge; `alpha`, and gate-specific separation
-        // challenges.
+        // Compute quotient challenge; `alpha`, and the lookup separation
+        // challenge.
         let alpha = transcript.challenge_scalar(b"alpha");
         transcript.append(b"alpha", &alpha);
 
-        let range_sep_challenge =
-            transcript.challenge_scalar(b"range separation challenge");
-        transcript.append(b"range seperation challenge", &range_sep_challenge);
-
-        let logic_sep_challenge =
-            transcript.challenge_scalar(b"logic separation challenge");
-        transcript.append(b"logic seperation challenge", &logic_sep_challenge);
-
-        let fixed_base_sep_challenge =
-            transcript.challenge_scalar(b"fixed base separation challenge");
-        transcript.append(
-            b"fixed base separation challenge",
-            &fixed_base_sep_challenge,
-        );
-
-        let var_base_sep_challenge =
-            transcript.challenge_scalar(b"variable base separation challenge");
-        transcript.append(
-            b"variable base separation challenge",
-            &var_base_sep_challenge,
-        );
-
         let lookup_sep_challenge =
             transcript.challenge_scalar(b"lookup separation challenge");
         transcript
             .append(b"lookup separation challenge", &lookup_sep_challenge);
 
-        let t_poly = quotient_poly::compute::<F, P>(
+        let t_poly = quotient_poly::compute(
             &domain,
             prover_key,
             &z_poly,
@@ -838,10 +482,6 @@ where
             &delta,
             &epsilon,
             &zeta,
-            &range_sep_challenge,
-            &logic_sep_challenge,
-            &fixed_base_sep_challenge,
-            &var_base_sep_challenge,
             &lookup_sep_challenge,
         )?;
 
@@ -879,7 +519,7 @@ where
         let z_challenge = transcript.challenge_scalar(b"z");
         transcript.append(b"z", &z_challenge);
 
-        let (lin_poly, evaluations) = linearisation_poly::compute::<F, P>(
+        let (lin_poly, evaluations) = linearisation_poly::compute(
             &domain,
             prover_key,
             &alpha,
@@ -888,10 +528,6 @@ where
             &delta,
             &epsilon,
             &zeta,
-            &range_sep_challenge,
-            &logic_sep_challenge,
-            &fixed_base_sep_challenge,
-            &var_base_sep_challenge,
             &lookup_sep_challenge,
             &z_challenge,
             &w_l_poly,
@@ -974,8 +610,8 @@ where
             label_polynomial!(prover_key.permutation.left_sigma.0.clone()),
             label_polynomial!(prover_key.permutation.right_sigma.0.clone()),
             label_polynomial!(prover_key.permutation.out_sigma.0.clone()),
-            label_polynomial!(f_poly),
-            label_polynomial!(h_2_poly),
+            label_polynomial!(f_poly, bound),
+            label_polynomial!(h_2_poly, bound),
             label_polynomial!(table_poly),
         ];
 
@@ -998,9 +634,9 @@ where
 
         let saw_polys = [
             label_polynomial!(z_poly),
-            label_polynomial!(w_l_poly),
-            label_polynomial!(w_r_poly),
-            label_polynomial!(w_4_poly),
+            label_polynomial!(w_l_poly, bound),
+            label_polynomial!(w_r_poly, bound),
+            label_polynomial!(w_4_poly, bound),
             label_polynomial!(h_1_poly),
             label_polynomial!(z_2_poly),
             label_polynomial!(table_poly),
@@ -1046,7 +682,8 @@ where
 
     /// Proves a circuit is satisfied, then clears the witness variables
     /// If the circuit is not pre-processed, then the preprocessed circuit will
-    /// also be computed.
+    /// also be computed. The circuit and [`ProverKey`] are kept for the next
+    /// proof, see [`Prover::assign_witness`].
     pub fn prove(
         &mut self,
         commit_key: &PC::CommitterKey,
@@ -1061,6 +698,9 @@ where
             )?);
         }
 
+        // Fresh blinding values for every proof of the kept circuit.
+        self.cs.resample_blinding(&mut OsRng);
+
         let prover_key = self.prover_key.as_ref().unwrap();
         let proof = self.prove_with_preprocessed(
             commit_key,
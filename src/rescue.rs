@@ -0,0 +1,535 @@
+//! Rescue-Prime permutation and hash, in circuit and natively.
+//!
+//! Each round applies the `x^alpha` S-box, the MDS matrix and the first
+//! round constants, then the inverse S-box `x^(1/alpha)`, the MDS matrix and
+//! the second round constants, as in the Rescue-Prime specification.
+//!
+//! In circuit, both S-boxes go on the high degree selectors. The forward
+//! S-box and the following affine layer make one row per state element. The
+//! inverse S-box is witnessed: `y = x^(1/alpha)` is computed off-circuit and
+//! `y^5 = x` is checked, so it costs the same as the forward one.
+use crate::constraint_system::{CircuitBuilder, Variable};
+use ark_ec::TEModelParameters;
+use ark_ff::{BigInteger, FpParameters, PrimeField};
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
+
+/// Widest state laid out on the left, right and fourth wires of a row.
+pub const MAX_WIDTH: usize = 3;
+
+/// Rescue-Prime parameters over `F`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RescueParameters<F>
+where
+    F: PrimeField,
+{
+    /// Number of rounds.
+    pub rounds: usize,
+    /// Number of state elements reserved for capacity.
+    pub capacity: usize,
+    /// S-box exponent, the smallest prime not dividing `p - 1`.
+    pub alpha: u64,
+    /// Inverse of `alpha` modulo `p - 1`, as little-endian limbs.
+    pub alpha_inv: Vec<u64>,
+    /// MDS matrix, its size being the state width.
+    pub mds: Vec<Vec<F>>,
+    /// Round constants, two vectors of the state width per round.
+    pub round_constants: Vec<Vec<F>>,
+}
+
+impl<F> RescueParameters<F>
+where
+    F: PrimeField,
+{
+    /// Creates the parameters, computing the inverse S-box exponent.
+    ///
+    /// # Panics
+    ///
+    /// If `alpha` divides `p - 1`, in which case `x^alpha` is not a
+    /// permutation, or the constants do not match the rounds and width.
+    pub fn new(
+        rounds: usize,
+        capacity: usize,
+        alpha: u64,
+        mds: Vec<Vec<F>>,
+        round_constants: Vec<Vec<F>>,
+    ) -> Self {
+        let width = mds.len();
+        assert!(capacity < width, "capacity leaves no rate");
+        assert!(mds.iter().all(|row| row.len() == width), "MDS not square");
+        assert!(
+            round_constants.len() == 2 * rounds
+                && round_constants.iter().all(|c| c.len() == width),
+            "round constants do not match the rounds and width"
+        );
+        Self {
+            rounds,
+            capacity,
+            alpha,
+            alpha_inv: inverse_exponent::<F>(alpha).unwrap_or_else(|| {
+                panic!("x^{} is not a permutation of the field", alpha)
+            }),
+            mds,
+            round_constants,
+        }
+    }
+
+    /// State width.
+    pub fn width(&self) -> usize {
+        self.mds.len()
+    }
+
+    /// Number of elements absorbed and squeezed per permutation.
+    pub fn rate(&self) -> usize {
+        self.width() - self.capacity
+    }
+
+    /// Derives the parameters as the Rescue-Prime reference implementation
+    /// does: the smallest `alpha` such that `x^alpha` is a permutation, the
+    /// number of rounds resisting Gröbner basis attacks plus 50%, the MDS
+    /// matrix from a systematic Vandermonde code and the round constants
+    /// from SHAKE256 seeded with `Rescue-XLIX(p,m,capacity,security_level)`.
+    ///
+    /// The field's multiplicative generator is taken as the primitive
+    /// element of the Vandermonde matrix. The reference takes the smallest
+    /// primitive element, which is the same for BLS12-381's scalar field.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` leaves no rate or `security_level` is above 128.
+    pub fn rescue_prime(
+        width: usize,
+        capacity: usize,
+        security_level: u32,
+    ) -> Self {
+        assert!(capacity < width, "capacity leaves no rate");
+        assert!(security_level <= 128, "security level above 128 bits");
+
+        let alpha = (3..)
+            .find(|alpha| inverse_exponent::<F>(*alpha).is_some())
+            .unwrap();
+        let rounds =
+            rescue_prime_rounds(width, capacity, security_level, alpha);
+
+        // reduced row echelon form of [V_L | V_R], V_ij = g^(i·j)
+        let g = F::multiplicative_generator();
+        let mut v = (0..width as u64)
+            .map(|i| {
+                (0..2 * width as u64)
+                    .map(|j| g.pow([i * j]))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for col in 0..width {
+            let pivot = (col..width).find(|row| !v[*row][col].is_zero());
+            v.swap(col, pivot.expect("Vandermonde matrix is invertible"));
+            let inv = v[col][col].inverse().unwrap();
+            v[col].iter_mut().for_each(|x| *x *= inv);
+            for row in 0..width {
+                let factor = v[row][col];
+                if row != col && !factor.is_zero() {
+                    let pivot_row = v[col].clone();
+                    for (x, y) in v[row].iter_mut().zip(pivot_row) {
+                        *x -= factor * y;
+                    }
+                }
+            }
+        }
+        // the MDS matrix is the transpose of the right half
+        let mds = (0..width)
+            .map(|i| (0..width).map(|j| v[j][width + i]).collect())
+            .collect();
+
+        let modulus = F::Params::MODULUS;
+        let seed = format!(
+            "Rescue-XLIX({},{},{},{})",
+            decimal(modulus.as_ref()),
+            width,
+            capacity,
+            security_level
+        );
+        let bytes_per_int = (F::Params::MODULUS_BITS as usize).div_ceil(8) + 1;
+        let mut bytes = vec![0u8; bytes_per_int * 2 * width * rounds];
+        let mut shake = Shake256::default();
+        shake.update(seed.as_bytes());
+        shake.finalize_xof().read(&mut bytes);
+        let constants = bytes
+            .chunks(bytes_per_int)
+            .map(F::from_le_bytes_mod_order)
+            .collect::<Vec<_>>();
+        let round_constants =
+            constants.chunks(width).map(|c| c.to_vec()).collect();
+
+        Self::new(rounds, capacity, alpha, mds, round_constants)
+    }
+}
+
+/// Number of rounds of the Rescue-Prime reference implementation: the
+/// smallest `l` for which `binomial(v + d, v)^2 > 2^security_level`, with
+/// `v = m·(l - 1) + rate` and `d = (alpha - 1)·m·(l - 1) / 2 + 2`, at least
+/// 5, plus 50%.
+fn rescue_prime_rounds(
+    width: usize,
+    capacity: usize,
+    security_level: u32,
+    alpha: u64,
+) -> usize {
+    let binomial = |n: u128, k: u128| {
+        (0..k).try_fold(1u128, |acc, i| {
+            acc.checked_mul(n - i).map(|acc| acc / (i + 1))
+        })
+    };
+    let m = width as u128;
+    let exceeds_target = |l: u128| {
+        let v = m * (l - 1) + (width - capacity) as u128;
+        let d = (alpha as u128 - 1) * m * (l - 1) / 2 + 2;
+        // a square of at least 2^128 exceeds any supported target
+        match binomial(v + d, v).and_then(|b| b.checked_mul(b)) {
+            Some(square) => {
+                security_level < 128 && square > 1u128 << security_level
+            }
+            None => true,
+        }
+    };
+    let l = (1..25).find(|l| exceeds_target(*l)).unwrap_or(24) as usize;
+    (3 * l.max(5)).div_ceil(2)
+}
+
+/// Decimal representation of little-endian `limbs`.
+fn decimal(limbs: &[u64]) -> String {
+    let mut limbs = limbs.to_vec();
+    let mut digits = Vec::new();
+    while limbs.iter().any(|limb| *limb != 0) || digits.is_empty() {
+        let mut rem = 0u128;
+        for limb in limbs.iter_mut().rev() {
+            let v = (rem << 64) | *limb as u128;
+            *limb = (v / 10) as u64;
+            rem = v % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Returns `alpha^-1 mod (p - 1)`, found as the `(k·(p - 1) + 1) / alpha`
+/// that is an integer for some `k < alpha`, if any.
+fn inverse_exponent<F: PrimeField>(alpha: u64) -> Option<Vec<u64>> {
+    let mut p_minus_one = F::Params::MODULUS;
+    p_minus_one.sub_noborrow(&F::BigInt::from(1));
+    let p_minus_one = p_minus_one.as_ref();
+
+    for k in 1..alpha {
+        // k·(p - 1) + 1, with one more limb for the carry
+        let mut n = Vec::with_capacity(p_minus_one.len() + 1);
+        let mut carry = 1u128;
+        for limb in p_minus_one {
+            let v = *limb as u128 * k as u128 + carry;
+            n.push(v as u64);
+            carry = v >> 64;
+        }
+        n.push(carry as u64);
+
+        let mut rem = 0u128;
+        for limb in n.iter_mut().rev() {
+            let v = (rem << 64) | *limb as u128;
+            *limb = (v / alpha as u128) as u64;
+            rem = v % alpha as u128;
+        }
+        if rem == 0 {
+            return Some(n);
+        }
+    }
+    None
+}
+
+/// `mds · state + constants`
+fn affine<F: PrimeField>(
+    mds: &[Vec<F>],
+    state: &[F],
+    constants: &[F],
+) -> Vec<F> {
+    mds.iter()
+        .zip(constants)
+        .map(|(row, c)| {
+            row.iter().zip(state).fold(*c, |acc, (m, x)| acc + *m * x)
+        })
+        .collect()
+}
+
+/// Applies the Rescue-Prime permutation to `state`.
+pub fn permute<F: PrimeField>(params: &RescueParameters<F>, state: &mut [F]) {
+    for round in 0..params.rounds {
+        state.iter_mut().for_each(|x| *x = x.pow([params.alpha]));
+        let next =
+            affine(&params.mds, state, &params.round_constants[2 * round]);
+        state.copy_from_slice(&next);
+
+        state.iter_mut().for_each(|x| *x = x.pow(&params.alpha_inv));
+        let next =
+            affine(&params.mds, state, &params.round_constants[2 * round + 1]);
+        state.copy_from_slice(&next);
+    }
+}
+
+/// Hashes `inputs` as the Rescue-Prime sponge: a one is appended, then
+/// zeros up to a multiple of the rate, each chunk is added to the rate part
+/// of the state before a permutation and the rate part is returned.
+pub fn hash<F: PrimeField>(
+    params: &RescueParameters<F>,
+    inputs: &[F],
+) -> Vec<F> {
+    let rate = params.rate();
+    let mut padded = inputs.to_vec();
+    padded.push(F::one());
+    padded.resize(
+        padded.len() + (rate - padded.len() % rate) % rate,
+        F::zero(),
+    );
+
+    let mut state = vec![F::zero(); params.width()];
+    for chunk in padded.chunks(rate) {
+        for (elem, input) in state.iter_mut().zip(chunk) {
+            *elem += input;
+        }
+        permute(params, &mut state);
+    }
+    state.truncate(rate);
+    state
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Constrains the Rescue-Prime permutation of `state` and returns the
+    /// permuted state. Takes three rows per state element and round.
+    ///
+    /// # Panics
+    ///
+    /// If the parameters do not use the `x^5` S-box, or the state is wider
+    /// than [`MAX_WIDTH`].
+    pub fn rescue_permutation(
+        &mut self,
+        params: &RescueParameters<F>,
+        state: &[Variable],
+    ) -> Vec<Variable> {
+        assert_eq!(params.alpha, 5, "only the x^5 S-box is supported");
+        assert_eq!(state.len(), params.width(), "state width mismatch");
+        assert!(state.len() <= MAX_WIDTH, "state is too wide");
+        let zero = self.zero_var();
+
+        let mut state = state.to_vec();
+        for round in 0..params.rounds {
+            // x = mds · s^5 + c
+            let x = self.rescue_affine(
+                params,
+                &state,
+                &params.round_constants[2 * round],
+                true,
+            );
+
+            // y = x^(1/5), checked as y^5 - x = 0
+            let y = x
+                .iter()
+                .map(|x| {
+                    let value =
+                        self.value(*x).map(|x| x.pow(&params.alpha_inv));
+                    let y = self.add_witness(value);
+                    self.arithmetic_gate(|gate| {
+                        gate.witness(y, zero, Some(*x))
+                            .high_degree(F::one(), F::zero(), F::zero())
+                            .out(-F::one())
+                    });
+                    y
+                })
+                .collect::<Vec<_>>();
+
+            // s = mds · y + c
+            state = self.rescue_affine(
+                params,
+                &y,
+                &params.round_constants[2 * round + 1],
+                false,
+            );
+        }
+        state
+    }
+
+    /// Constrains the hash of `inputs`, matching [`hash`].
+    pub fn rescue_hash(
+        &mut self,
+        params: &RescueParameters<F>,
+        inputs: &[Variable],
+    ) -> Vec<Variable> {
+        let zero = self.zero_var();
+        let rate = params.rate();
+        // the padding is a constant added on the absorbing rows
+        let mut padded = inputs
+            .iter()
+            .map(|input| (*input, F::zero()))
+            .collect::<Vec<_>>();
+        padded.push((zero, F::one()));
+        padded.resize(
+            padded.len() + (rate - padded.len() % rate) % rate,
+            (zero, F::zero()),
+        );
+
+        let mut state = vec![zero; params.width()];
+        for chunk in padded.chunks(rate) {
+            for (elem, (input, c)) in state.iter_mut().zip(chunk) {
+                let (elem_in, c) = (*elem, *c);
+                *elem = self.arithmetic_gate(|gate| {
+                    gate.witness(elem_in, *input, None)
+                        .add(F::one(), F::one())
+                        .constant(c)
+                });
+            }
+            state = self.rescue_permutation(params, &state);
+        }
+        state.truncate(rate);
+        state
+    }
+
+    /// One row per output of `mds · S(state) + constants`, `S` being `x^5`
+    /// on the high degree selectors or the identity.
+    fn rescue_affine(
+        &mut self,
+        params: &RescueParameters<F>,
+        state: &[Variable],
+        constants: &[F],
+        sbox: bool,
+    ) -> Vec<Variable> {
+        let mut wires = state.to_vec();
+        wires.resize(MAX_WIDTH, self.zero_var());
+        params
+            .mds
+            .iter()
+            .zip(constants)
+            .map(|(row, c)| {
+                let mut q = [F::zero(); MAX_WIDTH];
+                q[..row.len()].copy_from_slice(row);
+                let zero = F::zero();
+                let (linear, high) = match sbox {
+                    true => ([zero; MAX_WIDTH], q),
+                    false => (q, [zero; MAX_WIDTH]),
+                };
+                let c = *c;
+                self.arithmetic_gate(|gate| {
+                    gate.witness(wires[0], wires[1], None)
+                        .add(linear[0], linear[1])
+                        .fan_in_3(linear[2], wires[2])
+                        .high_degree(high[0], high[1], high[2])
+                        .constant(c)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::str::FromStr;
+
+    fn fr(s: &str) -> Fr {
+        Fr::from_str(s).unwrap()
+    }
+
+    fn params() -> RescueParameters<Fr> {
+        RescueParameters::rescue_prime(3, 1, 128)
+    }
+
+    /// Digests computed with the Rescue-Prime reference implementation for
+    /// BLS12-381's scalar field, a width of 3, a capacity of 1 and 128 bits
+    /// of security.
+    fn known_answers() -> Vec<(Vec<u64>, [Fr; 2])> {
+        vec![
+            (
+                vec![],
+                [
+                    fr("45993037853272783328790042058527048059282173798497580423926667558766040168892"),
+                    fr("187574453274371882323712298204892413834887742756496940869597550275281091251"),
+                ],
+            ),
+            (
+                vec![1],
+                [
+                    fr("41011951273150345694468419471079630531820430334778639570623573903916367281048"),
+                    fr("27437461783060567635524915951747000735905811939926409738399527223918703373163"),
+                ],
+            ),
+            (
+                vec![1, 2],
+                [
+                    fr("42303628707484006548021885135693419317605301127947247732044606598698050891451"),
+                    fr("5568822378828862916683630056109958946823505663626286118631195740236217058843"),
+                ],
+            ),
+            (
+                vec![0, 1, 2, 3, 4],
+                [
+                    fr("19776184667938562521376702892873430204492066361449801868884955080101078911108"),
+                    fr("285683095352736448135728773802898335841686764411237695598364253428855078220"),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn bls12_381_parameters_match_reference() {
+        let params = params();
+        assert_eq!((params.alpha, params.rounds), (5, 14));
+        assert_eq!(
+            params.mds[0],
+            vec![
+                fr("343"),
+                fr("52435875175126190479447740508185965837690552500527637822603658699938581184114"),
+                fr("57"),
+            ]
+        );
+        assert_eq!(
+            params.round_constants[0][0],
+            fr("35495817390819093545263349384941809089491580678942832859579453034368810736263")
+        );
+        assert_eq!(
+            params.round_constants[27][2],
+            fr("966835047744911231490794763166379188555949592683359886287393788918898119684")
+        );
+    }
+
+    #[test]
+    fn hash_matches_known_answers() {
+        let params = params();
+        for (inputs, digest) in known_answers() {
+            let inputs = inputs.into_iter().map(Fr::from).collect::<Vec<_>>();
+            assert_eq!(hash(&params, &inputs), digest, "{:?}", inputs);
+        }
+    }
+
+    #[test]
+    fn gadget_matches_known_answers() {
+        let params = params();
+        for (inputs, digest) in known_answers() {
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let vars = inputs
+                .iter()
+                .map(|input| cs.add_input(Fr::from(*input)))
+                .collect::<Vec<_>>();
+            let out = cs.rescue_hash(&params, &vars);
+            let values =
+                out.iter().map(|var| cs.value(*var)).collect::<Vec<_>>();
+            assert_eq!(values, digest.map(Some).to_vec());
+            assert_eq!(cs.check_satisfied(), Ok(()));
+
+            // A wrong digest does not satisfy the circuit.
+            cs.constrain_to_constant(out[0], digest[0] + Fr::from(1u64), None);
+            assert!(cs.check_satisfied().is_err());
+        }
+    }
+}
@@ -0,0 +1,402 @@
+//! Satisfiability checks run directly on the witness of a [`CircuitBuilder`],
+//! so a wrong witness is caught before a proof is attempted.
+use crate::constraint_system::{CircuitBuilder, Variable, WireData};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use core::fmt;
+use hashbrown::HashSet;
+
+/// First constraint of a [`CircuitBuilder`] found not to hold.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Unsatisfied<F>
+where
+    F: PrimeField,
+{
+    /// The arithmetic equation `q_arith·(q_m·a·b + q_l·a + q_r·b + q_o·c +
+    /// q_4·d + q_hl·a^5 + q_hr·b^5 + q_h4·d^5 + q_c) + PI` is not zero.
+    Gate {
+        /// Gate index.
+        index: usize,
+        /// Innermost region the gate was added in.
+        region: Option<String>,
+        /// Names of the four wire variables, when given.
+        names: [Option<String>; 4],
+        /// Left, right, output and fourth wires with their values.
+        wires: [(Variable, F); 4],
+        /// Value the equation evaluates to.
+        result: F,
+    },
+
+    /// The wires of a lookup gate are not a row of the lookup table.
+    Lookup {
+        /// Gate index.
+        index: usize,
+        /// Innermost region the gate was added in.
+        region: Option<String>,
+        /// Names of the four wire variables, when given.
+        names: [Option<String>; 4],
+        /// Left, right, output and fourth wires with their values.
+        wires: [(Variable, F); 4],
+    },
+
+    /// A variable placed on a wire has no value, the circuit was synthesized
+    /// without a witness.
+    MissingWitness {
+        /// Variable without a value.
+        variable: Variable,
+    },
+
+    /// Two wires of the permutation cycle of `variable` hold different
+    /// values, so the copy constraint does not hold.
+    CopyConstraint {
+        /// Variable the cycle belongs to in the permutation map.
+        variable: Variable,
+        /// First wire of the cycle, which gives its gate index, with its
+        /// value.
+        first: (WireData, F),
+        /// Wire of the cycle holding another value, with that value.
+        wire: (WireData, F),
+    },
+}
+
+impl<F> fmt::Display for Unsatisfied<F>
+where
+    F: PrimeField,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fmt_gate = |f: &mut fmt::Formatter,
+                        index: &usize,
+                        region: &Option<String>|
+         -> fmt::Result {
+            write!(f, "gate {}", index)?;
+            if let Some(region) = region {
+                write!(f, " in {}", region)?;
+            }
+            Ok(())
+        };
+        let fmt_wires = |f: &mut fmt::Formatter,
+                         wires: &[(Variable, F); 4],
+                         names: &[Option<String>; 4]|
+         -> fmt::Result {
+            for ((wire, (var, value)), name) in
+                ["a", "b", "c", "d"].iter().zip(wires).zip(names)
+            {
+                match name {
+                    Some(name) => write!(f, " {}={}:{}", wire, name, value)?,
+                    None => write!(f, " {}=v{}:{}", wire, var, value)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Unsatisfied::Gate {
+                index,
+                region,
+                names,
+                wires,
+                result,
+            } => {
+                fmt_gate(f, index, region)?;
+                write!(f, " evaluates to {} instead of 0:", result)?;
+                fmt_wires(f, wires, names)
+            }
+            Unsatisfied::Lookup {
+                index,
+                region,
+                names,
+                wires,
+            } => {
+                write!(f, "lookup ")?;
+                fmt_gate(f, index, region)?;
+                write!(f, " is not in the table:")?;
+                fmt_wires(f, wires, names)
+            }
+            Unsatisfied::MissingWitness { variable } => {
+                write!(f, "variable v{} has no value", variable)
+            }
+            Unsatisfied::CopyConstraint {
+                variable,
+                first: (first, first_value),
+                wire: (wire, value),
+            } => write!(
+                f,
+                "copy of v{}: {:?} holds {} but {:?} holds {}",
+                variable, first, first_value, wire, value
+            ),
+        }
+    }
+}
+
+// `Unsatisfied` carries the whole failing gate for the report, it is only
+// built once per check.
+#[allow(clippy::result_large_err)]
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Checks every gate, copy constraint and lookup of the circuit against
+    /// the current witness and returns the first one that does not hold.
+    ///
+    /// Gates with the lookup selector on are checked against the
+    /// [`LookupTable`](crate::lookup_table::LookupTable), every other gate
+    /// against the arithmetic equation.
+    pub fn check_satisfied(&self) -> Result<(), Unsatisfied<F>> {
+        self.check_witness_complete()?;
+        let pi = self.public_inputs.as_evals(self.circuit_bound());
+        let table = self.lookup_rows();
+        (0..self.n)
+            .try_for_each(|index| self.check_gate(index, &pi, &table))?;
+        self.check_copy_constraints()
+    }
+
+    /// Rows of the lookup table, for [`CircuitBuilder::check_gate`].
+    pub(crate) fn lookup_rows(&self) -> HashSet<[F; 4]> {
+        self.lookup_table.0.iter().copied().collect()
+    }
+
+    /// Checks that every variable placed on a wire has a value, which the
+    /// other checks rely on.
+    pub(crate) fn check_witness_complete(&self) -> Result<(), Unsatisfied<F>> {
+        [&self.w_l, &self.w_r, &self.w_o, &self.w_4]
+            .iter()
+            .flat_map(|wires| wires.iter())
+            .find(|var| !self.variables.contains_key(var))
+            .map_or(Ok(()), |var| {
+                Err(Unsatisfied::MissingWitness { variable: *var })
+            })
+    }
+
+    /// Checks gate `index` against the arithmetic equation and, when its
+    /// lookup selector is on, against the rows of the lookup `table`.
+    pub(crate) fn check_gate(
+        &self,
+        index: usize,
+        pi: &[F],
+        table: &HashSet<[F; 4]>,
+    ) -> Result<(), Unsatisfied<F>> {
+        let wires = self.gate_wires(index);
+        let [a, b, c, d] = wires.map(|(_, value)| value);
+
+        if !self.q_lookup[index].is_zero() && !table.contains(&[a, b, c, d]) {
+            return Err(Unsatisfied::Lookup {
+                index,
+                region: self.region_of(index).map(str::to_string),
+                names: self.wire_names(&wires),
+                wires,
+            });
+        }
+
+        let result = self.q_arith[index]
+            * (self.q_m[index] * a * b
+                + self.q_l[index] * a
+                + self.q_r[index] * b
+                + self.q_o[index] * c
+                + self.q_4[index] * d
+                + self.q_hl[index] * a.pow([5u64])
+                + self.q_hr[index] * b.pow([5u64])
+                + self.q_h4[index] * d.pow([5u64])
+                + self.q_c[index])
+            + pi[index];
+        if !result.is_zero() {
+            return Err(Unsatisfied::Gate {
+                index,
+                region: self.region_of(index).map(str::to_string),
+                names: self.wire_names(&wires),
+                wires,
+                result,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that all the wires of each permutation cycle hold the same
+    /// value, which is what the permutation argument enforces.
+    ///
+    /// Cycles are visited in the order their variables first appear on the
+    /// gates, so the violation reported is the same on every run.
+    fn check_copy_constraints(&self) -> Result<(), Unsatisfied<F>> {
+        let mut visited = HashSet::new();
+        let variables = (0..self.n)
+            .flat_map(|i| [self.w_l[i], self.w_r[i], self.w_o[i], self.w_4[i]])
+            .filter(|var| visited.insert(*var));
+        for variable in variables {
+            let wires = match self.perm.variable_map.get(&variable) {
+                Some(wires) => wires,
+                None => continue,
+            };
+            let mut values = wires.iter().map(|wire| {
+                let var = match *wire {
+                    WireData::Left(i) => self.w_l[i],
+                    WireData::Right(i) => self.w_r[i],
+                    WireData::Output(i) => self.w_o[i],
+                    WireData::Fourth(i) => self.w_4[i],
+                };
+                (*wire, self.variables[&var])
+            });
+            let first = match values.next() {
+                Some(first) => first,
+                None => continue,
+            };
+            if let Some(wire) = values.find(|(_, value)| *value != first.1) {
+                return Err(Unsatisfied::CopyConstraint {
+                    variable,
+                    first,
+                    wire,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Variables and values on the four wires of gate `index`, which must
+    /// all have a value.
+    pub(crate) fn gate_wires(&self, index: usize) -> [(Variable, F); 4] {
+        [
+            self.w_l[index],
+            self.w_r[index],
+            self.w_o[index],
+            self.w_4[index],
+        ]
+        .map(|var| (var, self.variables[&var]))
+    }
+
+    /// Names given to the variables on a gate's wires.
+    fn wire_names(&self, wires: &[(Variable, F); 4]) -> [Option<String>; 4] {
+        wires.map(|(var, _)| self.variable_name(var).map(str::to_string))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+
+    type Builder = CircuitBuilder<Fr, EdwardsParameters>;
+
+    /// Adds `a * b = c` and returns its gate index.
+    fn mul_gate(cs: &mut Builder, a: u64, b: u64, c: u64) -> usize {
+        let index = cs.n;
+        let a = cs.add_input(Fr::from(a));
+        let b = cs.add_input(Fr::from(b));
+        let c = cs.add_input(Fr::from(c));
+        let zero = Fr::from(0u64);
+        cs.poly_gate(
+            a,
+            b,
+            c,
+            Fr::from(1u64),
+            zero,
+            zero,
+            -Fr::from(1u64),
+            zero,
+            None,
+        );
+        index
+    }
+
+    #[test]
+    fn satisfied_circuit_passes() {
+        let mut cs = Builder::new();
+        mul_gate(&mut cs, 3, 5, 15);
+        assert_eq!(cs.check_satisfied(), Ok(()));
+    }
+
+    #[test]
+    fn violated_gate_reports_its_row() {
+        let mut cs = Builder::new();
+        mul_gate(&mut cs, 3, 5, 15);
+        let index = mul_gate(&mut cs, 3, 5, 16);
+        match cs.check_satisfied() {
+            Err(Unsatisfied::Gate {
+                index: found,
+                result,
+                ..
+            }) => {
+                assert_eq!(found, index);
+                assert_eq!(result, -Fr::from(1u64));
+            }
+            other => panic!("expected a gate violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn violated_copy_constraint_reports_its_row() {
+        let mut cs = Builder::new();
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        let zero = cs.zero_var();
+        // Gates with no selector only place `a` on their left wires, so
+        // swapping the second one for `b` leaves every gate satisfied.
+        let z = Fr::from(0u64);
+        let first = cs.n;
+        cs.poly_gate(a, zero, zero, z, z, z, z, z, None);
+        let index = cs.n;
+        cs.poly_gate(a, zero, zero, z, z, z, z, z, None);
+        cs.w_l[index] = b;
+        assert_eq!(
+            cs.check_satisfied(),
+            Err(Unsatisfied::CopyConstraint {
+                variable: a,
+                first: (WireData::Left(first), Fr::from(3u64)),
+                wire: (WireData::Left(index), Fr::from(5u64)),
+            })
+        );
+    }
+
+    #[test]
+    fn first_violated_copy_constraint_is_reported() {
+        // Rebuilt a few times, each permutation map being hashed afresh.
+        for _ in 0..4 {
+            let mut cs = Builder::new();
+            let zero = cs.zero_var();
+            let z = Fr::from(0u64);
+            let vars = (0..8u64)
+                .map(|i| cs.add_input(Fr::from(i)))
+                .collect::<Vec<_>>();
+            let first = cs.n;
+            for var in vars.iter().chain(&vars) {
+                cs.poly_gate(*var, zero, zero, z, z, z, z, z, None);
+            }
+            // Break every cycle but the first one, on its second wire.
+            let second = first + vars.len();
+            for i in 1..vars.len() {
+                cs.w_l[second + i] = vars[0];
+            }
+            assert_eq!(
+                cs.check_satisfied(),
+                Err(Unsatisfied::CopyConstraint {
+                    variable: vars[1],
+                    first: (WireData::Left(first + 1), Fr::from(1u64)),
+                    wire: (WireData::Left(second + 1), Fr::from(0u64)),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn violated_lookup_reports_its_row() {
+        let mut cs = Builder::new();
+        let zero = Fr::from(0u64);
+        cs.lookup_table_mut().insert_row(
+            Fr::from(3u64),
+            Fr::from(5u64),
+            Fr::from(15u64),
+            zero,
+        );
+        let a = cs.add_input(Fr::from(3u64));
+        let b = cs.add_input(Fr::from(5u64));
+        let c = cs.add_input(Fr::from(15u64));
+        let d = cs.add_input(Fr::from(16u64));
+        cs.lookup_gate(a, b, c, None, None);
+        let index = cs.n;
+        cs.lookup_gate(a, b, d, None, None);
+        match cs.check_satisfied() {
+            Err(Unsatisfied::Lookup { index: found, .. }) => {
+                assert_eq!(found, index)
+            }
+            other => panic!("expected a lookup violation, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! SHA-256 over byte variables, built on lookup tables.
+//!
+//! Words are 32-bit words of 4-bit limbs, see [`crate::limbs`]. Ch and Maj
+//! are sums of disjoint ANDs, `(e & f) + (!e & g)` and
+//! `(a & b) + (c & (a ^ b))`, so their terms go straight into the round
+//! additions.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    limbs::LimbTables,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+
+/// Initial hash value.
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c,
+    0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Round constants.
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1,
+    0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
+    0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786,
+    0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147,
+    0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+    0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a,
+    0x5b9cca4f, 0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
+    0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Rotations of Σ0, Σ1, σ0 and σ1, `true` marking a shift.
+const BIG_SIGMA_0: [(u32, bool); 3] = [(2, false), (13, false), (22, false)];
+const BIG_SIGMA_1: [(u32, bool); 3] = [(6, false), (11, false), (25, false)];
+const SMALL_SIGMA_0: [(u32, bool); 3] = [(7, false), (18, false), (3, true)];
+const SMALL_SIGMA_1: [(u32, bool); 3] = [(17, false), (19, false), (10, true)];
+
+/// A 32-bit word as eight 4-bit limbs, least significant first.
+type Word = [Variable; 8];
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Constrains the SHA-256 digest of `bytes`. Each input is range checked
+    /// to a byte, and each output is a byte of the digest.
+    ///
+    /// The 4-bit XOR, AND and split rows are added to the lookup table if it
+    /// does not hold them yet.
+    pub fn sha256(&mut self, bytes: &[Variable]) -> [Variable; 32] {
+        let mut tables = self.limb_tables();
+        self.sha256_with_tables(&mut tables, bytes)
+    }
+
+    /// Constrains the SHA-256 digest of `bytes` with the table indices and
+    /// constants of `tables`, shared between several digests.
+    pub(crate) fn sha256_with_tables(
+        &mut self,
+        tables: &mut LimbTables,
+        bytes: &[Variable],
+    ) -> [Variable; 32] {
+        // (low, high) limbs of the message, then of the padding
+        let mut message = bytes
+            .iter()
+            .map(|byte| self.byte_limbs(tables, *byte))
+            .collect::<Vec<_>>();
+        let mut padding = vec![0x80];
+        padding.resize(1 + (119 - bytes.len() % 64) % 64, 0);
+        padding.extend_from_slice(&(bytes.len() as u64 * 8).to_be_bytes());
+        for byte in padding {
+            let lo = self.limb_constant(tables, byte as u64 & 15);
+            let hi = self.limb_constant(tables, byte as u64 >> 4);
+            message.push([lo, hi]);
+        }
+
+        let mut state = IV.map(|h| self.word_constant(tables, h as u64));
+        for block in message.chunks(64) {
+            state = self.sha256_compress(tables, &state, block);
+        }
+
+        let mut digest = [self.zero_var; 32];
+        for (i, word) in state.iter().enumerate() {
+            for j in 0..4 {
+                let (lo, hi) = (word[6 - 2 * j], word[7 - 2 * j]);
+                digest[4 * i + j] = self.limbs_byte(lo, hi);
+            }
+        }
+        digest
+    }
+
+    /// XOR of the three rotations or shifts of `x`.
+    fn sigma(
+        &mut self,
+        tables: &LimbTables,
+        x: &Word,
+        amounts: [(u32, bool); 3],
+    ) -> Word {
+        let rotated = self.word_rotations(tables, x, &amounts);
+        let xored = self.word_xor(tables, &rotated[0], &rotated[1]);
+        self.word_xor(tables, &xored, &rotated[2])
+    }
+
+    /// Compresses one 64-byte block of `(low, high)` limbs into `state`.
+    fn sha256_compress(
+        &mut self,
+        tables: &mut LimbTables,
+        state: &[Word; 8],
+        block: &[[Variable; 2]],
+    ) -> [Word; 8] {
+        // message schedule, words being big-endian
+        let mut w = block
+            .chunks(4)
+            .map(|bytes| {
+                let mut word = [self.zero_var; 8];
+                for (j, byte) in bytes.iter().enumerate() {
+                    word[6 - 2 * j] = byte[0];
+                    word[7 - 2 * j] = byte[1];
+                }
+                word
+            })
+            .collect::<Vec<_>>();
+        for i in 16..64 {
+            let s0 = self.sigma(tables, &w[i - 15], SMALL_SIGMA_0);
+            let s1 = self.sigma(tables, &w[i - 2], SMALL_SIGMA_1);
+            let (w7, w16) = (w[i - 7], w[i - 16]);
+            let next = self.word_add(tables, &[&s1, &w7, &s0, &w16], 0);
+            w.push(next);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+        for i in 0..64 {
+            // T1 = h + Σ1(e) + Ch(e, f, g) + K_i + W_i
+            let s1 = self.sigma(tables, &e, BIG_SIGMA_1);
+            let e_and_f = self.word_and(tables, &e, &f);
+            let not_e = self.word_not(&e);
+            let not_e_and_g = self.word_and(tables, &not_e, &g);
+            let t1 = self.word_add(
+                tables,
+                &[&h, &s1, &e_and_f, &not_e_and_g, &w[i]],
+                K[i] as u64,
+            );
+
+            // T2 = Σ0(a) + Maj(a, b, c)
+            let s0 = self.sigma(tables, &a, BIG_SIGMA_0);
+            let a_and_b = self.word_and(tables, &a, &b);
+            let a_xor_b = self.word_xor(tables, &a, &b);
+            let c_and_a_xor_b = self.word_and(tables, &c, &a_xor_b);
+
+            h = g;
+            g = f;
+            f = e;
+            e = self.word_add(tables, &[&d, &t1], 0);
+            d = c;
+            c = b;
+            b = a;
+            a = self.word_add(tables, &[&t1, &s0, &a_and_b, &c_and_a_xor_b], 0);
+        }
+
+        let mut next = *state;
+        for (word, v) in next.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = self.word_add(tables, &[&*word, &v], 0);
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::limbs::to_u64;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::rand::RngCore;
+    use sha2::{Digest, Sha256};
+
+    /// Constrains the digest of `bytes`, returning the circuit and the
+    /// digest it computes.
+    fn sha256_circuit(
+        bytes: &[u64],
+    ) -> (CircuitBuilder<Fr, EdwardsParameters>, Vec<u8>) {
+        let mut cs = CircuitBuilder::new();
+        let vars = bytes
+            .iter()
+            .map(|byte| cs.add_input(Fr::from(*byte)))
+            .collect::<Vec<_>>();
+        let digest = cs
+            .sha256(&vars)
+            .iter()
+            .map(|byte| to_u64(cs.value(*byte).unwrap()) as u8)
+            .collect();
+        (cs, digest)
+    }
+
+    #[test]
+    fn digest_matches_sha2_around_block_boundaries() {
+        let rng = &mut ark_std::test_rng();
+        for len in [0, 55, 56, 63, 64, 119] {
+            let mut bytes = vec![0u8; len];
+            rng.fill_bytes(&mut bytes);
+            let (cs, digest) = sha256_circuit(
+                &bytes.iter().map(|b| *b as u64).collect::<Vec<_>>(),
+            );
+            assert_eq!(digest, Sha256::digest(&bytes).to_vec(), "{}", len);
+            assert_eq!(cs.check_satisfied(), Ok(()), "{}", len);
+        }
+    }
+
+    #[test]
+    fn inputs_are_range_checked_to_bytes() {
+        let (cs, _) = sha256_circuit(&[1, 256, 3]);
+        assert!(cs.check_satisfied().is_err());
+    }
+}
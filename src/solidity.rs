@@ -0,0 +1,849 @@
+//! Generation of a Solidity verifier contract from a [`PlonkVerifierKey`].
+//!
+//! The emitted contract replays the same transcript schedule as
+//! [`Proof::verify`] using a [`KeccakTranscript`], recomputes the
+//! linearisation commitment and checks both KZG openings with the EVM
+//! `ecAdd`, `ecMul` and `ecPairing` precompiles. Only curves whose base field
+//! fits in one EVM word are supported, in practice BN254.
+use crate::{
+    commitment::{KZG10Commitment, KZG10},
+    error::Error,
+    permutation::constants::{K1, K2, K3},
+    proof_system::{proof::Proof, PlonkVerifierKey},
+    transcript::{to_evm_words, KeccakTranscript, EVM_WORD_SIZE},
+};
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{BigInteger, Field, FpParameters, PrimeField};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_poly_commit::sonic_pc::VerifierKey;
+use sha3::{Digest, Keccak256};
+
+/// Labels of the custom evaluations, in the order they are absorbed by the
+/// transcript and laid out in the calldata.
+pub const CUSTOM_EVAL_LABELS: [&str; 10] = [
+    "q_arith_eval",
+    "q_c_eval",
+    "q_l_eval",
+    "q_r_eval",
+    "q_hl_eval",
+    "q_hr_eval",
+    "q_h4_eval",
+    "a_next_eval",
+    "b_next_eval",
+    "d_next_eval",
+];
+
+/// Number of words of an encoded [`Proof`]: 19 points followed by 26
+/// evaluations.
+pub const PROOF_WORDS: usize = 19 * 2 + 14 + 2 + CUSTOM_EVAL_LABELS.len();
+
+/// ABI signature of the generated entry point.
+const VERIFY_SIGNATURE: &[u8] = b"verify(uint256[],uint256[])";
+
+/// Solidity verifier generator for a preprocessed circuit.
+pub struct SolidityVerifier<'a, E>
+where
+    E: PairingEngine,
+{
+    /// Circuit verifier key holding the selector and sigma commitments.
+    plonk_verifier_key: &'a PlonkVerifierKey<E::Fr, KZG10<E>>,
+
+    /// KZG verifier key of the SRS.
+    verifier_key: &'a VerifierKey<E>,
+
+    /// Transcript right after preprocessing, before any public input.
+    transcript: &'a KeccakTranscript,
+
+    /// Gate indices of the public inputs.
+    pi_positions: Vec<usize>,
+}
+
+impl<'a, E> SolidityVerifier<'a, E>
+where
+    E: PairingEngine,
+{
+    /// Creates a generator. `transcript` must be the seeded transcript that
+    /// would otherwise be handed to [`Proof::verify`].
+    pub fn new(
+        plonk_verifier_key: &'a PlonkVerifierKey<E::Fr, KZG10<E>>,
+        verifier_key: &'a VerifierKey<E>,
+        transcript: &'a KeccakTranscript,
+        pi_positions: &[usize],
+    ) -> Self {
+        Self {
+            plonk_verifier_key,
+            verifier_key,
+            transcript,
+            pi_positions: pi_positions.to_vec(),
+        }
+    }
+
+    /// Emits the verifier contract named `name`.
+    pub fn generate(&self, name: &str) -> Result<String, Error> {
+        let vk = self.plonk_verifier_key;
+        let domain = GeneralEvaluationDomain::<E::Fr>::new(vk.n).ok_or(
+            Error::InvalidEvalDomainSize {
+                log_size_of_group: vk.n.trailing_zeros(),
+                adicity: <<E::Fr as ark_ff::FftField>::FftParams as ark_ff::FftParameters>::TWO_ADICITY,
+            },
+        )?;
+
+        let mut out = String::new();
+        out.push_str(&format!(
+            "// SPDX-License-Identifier: MIT\n\
+             // Generated PLONK verifier. Do not edit.\n\
+             pragma solidity ^0.8.0;\n\n\
+             contract {} {{\n",
+            name
+        ));
+        out.push_str(&self.constants(&domain));
+        out.push_str(SOLIDITY_HELPERS);
+        out.push_str(&self.verify_function());
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// Emits every circuit and SRS dependent constant.
+    fn constants(&self, domain: &GeneralEvaluationDomain<E::Fr>) -> String {
+        let vk = self.plonk_verifier_key;
+        let two_256 = E::Fr::from(2u64).pow([256u64]);
+        let mut out = String::new();
+        let mut scalar = |name: &str, value: E::Fr| {
+            out.push_str(&format!(
+                "    uint256 constant {} = {};\n",
+                name,
+                scalar_literal(value)
+            ));
+        };
+        scalar("N", E::Fr::from(vk.n as u64));
+        scalar("OMEGA", domain.element(1));
+        scalar("K1", K1::<E::Fr>());
+        scalar("K2", K2::<E::Fr>());
+        scalar("K3", K3::<E::Fr>());
+        scalar("R2", two_256);
+
+        out.push_str(&format!(
+            "    uint256 constant R = {};\n    uint256 constant Q = {};\n",
+            modulus_literal::<E::Fr>(),
+            modulus_literal::<E::Fq>(),
+        ));
+
+        let mut point = |name: &str, comm: &KZG10Commitment<E>| {
+            out.push_str(&point_constant(name, &comm.0));
+        };
+        point("QM", &vk.arithmetic.q_m);
+        point("QL", &vk.arithmetic.q_l);
+        point("QR", &vk.arithmetic.q_r);
+        point("QO", &vk.arithmetic.q_o);
+        point("Q4", &vk.arithmetic.q_4);
+        point("QC", &vk.arithmetic.q_c);
+        point("QHL", &vk.arithmetic.q_hl);
+        point("QHR", &vk.arithmetic.q_hr);
+        point("QH4", &vk.arithmetic.q_h4);
+        point("S1", &vk.permutation.left_sigma);
+        point("S2", &vk.permutation.right_sigma);
+        point("S3", &vk.permutation.out_sigma);
+        point("S4", &vk.permutation.fourth_sigma);
+        point("QLOOKUP", &vk.lookup.q_lookup);
+        point("T1", &vk.lookup.table_1);
+        point("T2", &vk.lookup.table_2);
+        point("T3", &vk.lookup.table_3);
+        point("T4", &vk.lookup.table_4);
+        out.push_str(&point_constant("G", &self.verifier_key.g));
+
+        // The pairing precompile expects `x.c1, x.c0, y.c1, y.c0`.
+        let mut g2 = |name: &str, p: &E::G2Affine| {
+            let words = to_evm_words(p);
+            [1, 0, 3, 2].iter().enumerate().for_each(|(i, &j)| {
+                out.push_str(&format!(
+                    "    uint256 constant {}_{} = {};\n",
+                    name,
+                    i,
+                    word_literal(&words[j])
+                ));
+            });
+        };
+        g2("H", &self.verifier_key.h);
+        g2("BETA_H", &self.verifier_key.beta_h);
+
+        out.push_str(&format!(
+            "    uint256 constant PI_COUNT = {};\n    uint256 constant PROOF_WORDS = {};\n",
+            self.pi_positions.len(),
+            PROOF_WORDS
+        ));
+
+        let (state, pending) = self.transcript.snapshot();
+        out.push_str(&format!(
+            "    bytes32 constant TRANSCRIPT_STATE = {};\n    bytes constant TRANSCRIPT_PENDING = hex\"{}\";\n\n",
+            word_literal(&state),
+            to_hex(&pending),
+        ));
+        out
+    }
+
+    /// Emits the external `verify` function.
+    fn verify_function(&self) -> String {
+        // Public inputs are unrolled so their Lagrange bases are constants.
+        let mut absorb_pi = String::new();
+        let mut eval_pi = String::new();
+        let domain =
+            GeneralEvaluationDomain::<E::Fr>::new(self.plonk_verifier_key.n)
+                .unwrap();
+        for (i, pos) in self.pi_positions.iter().enumerate() {
+            absorb_pi.push_str(&format!(
+                "        absorb(t, {});\n        require(pi[{}] < R);\n        absorb(t, pi[{}]);\n",
+                pos, i, i
+            ));
+            eval_pi.push_str(&format!(
+                "        piEval = addmod(piEval, mulmod(pi[{}], lagrange({}, v[Z], zh), R), R);\n",
+                i,
+                scalar_literal(domain.element(*pos))
+            ));
+        }
+        VERIFY_TEMPLATE
+            .replace("{{ABSORB_PI}}", &absorb_pi)
+            .replace("{{EVAL_PI}}", &eval_pi)
+    }
+
+    /// ABI encodes a call to `verify(proof, pi)` for the generated contract.
+    pub fn encode_calldata(
+        proof: &Proof<E::Fr, KZG10<E>>,
+        pub_inputs: &[E::Fr],
+    ) -> Result<Vec<u8>, Error> {
+        let words = Self::encode_proof(proof)?;
+        let mut selector = Keccak256::digest(VERIFY_SIGNATURE).to_vec();
+        selector.truncate(4);
+
+        let word = |value: usize| {
+            let mut w = [0u8; EVM_WORD_SIZE];
+            w[EVM_WORD_SIZE - 8..]
+                .copy_from_slice(&(value as u64).to_be_bytes());
+            w
+        };
+
+        // Head: two offsets, then each array as length followed by items.
+        let mut out = selector;
+        out.extend_from_slice(&word(2 * EVM_WORD_SIZE));
+        out.extend_from_slice(&word((3 + words.len()) * EVM_WORD_SIZE));
+        out.extend_from_slice(&word(words.len()));
+        words.iter().for_each(|w| out.extend_from_slice(w));
+        out.extend_from_slice(&word(pub_inputs.len()));
+        pub_inputs
+            .iter()
+            .flat_map(to_evm_words)
+            .for_each(|w| out.extend_from_slice(&w));
+        Ok(out)
+    }
+
+    /// Lays out a [`Proof`] as the `uint256[]` expected by the contract.
+    ///
+    /// Returns [`Error::UnsupportedGate`] if the proof carries custom
+    /// evaluations other than [`CUSTOM_EVAL_LABELS`].
+    pub fn encode_proof(
+        proof: &Proof<E::Fr, KZG10<E>>,
+    ) -> Result<Vec<[u8; EVM_WORD_SIZE]>, Error> {
+        let points = [
+            &proof.a_comm.0,
+            &proof.b_comm.0,
+            &proof.c_comm.0,
+            &proof.d_comm.0,
+            &proof.z_comm.0,
+            &proof.f_comm.0,
+            &proof.h_1_comm.0,
+            &proof.h_2_comm.0,
+            &proof.z_2_comm.0,
+            &proof.t_1_comm.0,
+            &proof.t_2_comm.0,
+            &proof.t_3_comm.0,
+            &proof.t_4_comm.0,
+            &proof.t_5_comm.0,
+            &proof.t_6_comm.0,
+            &proof.t_7_comm.0,
+            &proof.t_8_comm.0,
+            &proof.aw_opening.w,
+            &proof.saw_opening.w,
+        ];
+
+        let evals = &proof.evaluations;
+        let scalars = [
+            evals.wire_evals.a_eval,
+            evals.wire_evals.b_eval,
+            evals.wire_evals.c_eval,
+            evals.wire_evals.d_eval,
+            evals.perm_evals.left_sigma_eval,
+            evals.perm_evals.right_sigma_eval,
+            evals.perm_evals.out_sigma_eval,
+            evals.perm_evals.permutation_eval,
+            evals.lookup_evals.f_eval,
+            evals.lookup_evals.q_lookup_eval,
+            evals.lookup_evals.z2_next_eval,
+            evals.lookup_evals.h1_eval,
+            evals.lookup_evals.h1_next_eval,
+            evals.lookup_evals.h2_eval,
+            evals.lookup_evals.table_eval,
+            evals.lookup_evals.table_next_eval,
+        ];
+
+        let labels = evals
+            .custom_evals
+            .vals
+            .iter()
+            .map(|(label, _)| label.as_str());
+        if !labels.clone().eq(CUSTOM_EVAL_LABELS.iter().copied()) {
+            return Err(Error::UnsupportedGate(format!(
+                "{:?} custom",
+                labels.collect::<Vec<_>>()
+            )));
+        }
+
+        let mut words = points
+            .iter()
+            .flat_map(|p| point_words(*p))
+            .collect::<Vec<_>>();
+        words.extend(scalars.iter().flat_map(to_evm_words));
+        words.extend(
+            evals
+                .custom_evals
+                .vals
+                .iter()
+                .flat_map(|(_, eval)| to_evm_words(eval)),
+        );
+        debug_assert_eq!(words.len(), PROOF_WORDS);
+        Ok(words)
+    }
+}
+
+/// Affine G1 point as `(x, y)`, the identity being `(0, 0)`.
+fn point_words<G: AffineCurve>(p: &G) -> Vec<[u8; EVM_WORD_SIZE]> {
+    if p.is_zero() {
+        return vec![[0u8; EVM_WORD_SIZE]; 2];
+    }
+    let words = to_evm_words(p);
+    words[..2].to_vec()
+}
+
+/// Emits the `_X` and `_Y` constants of a G1 point.
+fn point_constant<G: AffineCurve>(name: &str, p: &G) -> String {
+    let words = point_words(p);
+    format!(
+        "    uint256 constant {}_X = {};\n    uint256 constant {}_Y = {};\n",
+        name,
+        word_literal(&words[0]),
+        name,
+        word_literal(&words[1])
+    )
+}
+
+fn scalar_literal<F: PrimeField>(value: F) -> String {
+    word_literal(&to_evm_words(&value)[0])
+}
+
+fn modulus_literal<F: PrimeField>() -> String {
+    let bytes = F::Params::MODULUS.to_bytes_be();
+    let mut word = [0u8; EVM_WORD_SIZE];
+    word[EVM_WORD_SIZE - bytes.len()..].copy_from_slice(&bytes);
+    word_literal(&word)
+}
+
+fn word_literal(word: &[u8; EVM_WORD_SIZE]) -> String {
+    format!("0x{}", to_hex(word))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Transcript, field and curve helpers shared by every generated contract.
+const SOLIDITY_HELPERS: &str = r#"
+    struct Transcript {
+        bytes32 state;
+        bytes pending;
+    }
+
+    function absorb(Transcript memory t, uint256 w) internal pure {
+        t.pending = abi.encodePacked(t.pending, w);
+    }
+
+    function absorbPoint(Transcript memory t, uint256[] calldata p, uint256 i) internal pure {
+        t.pending = abi.encodePacked(t.pending, p[i], p[i + 1]);
+    }
+
+    // (h0 * 2^256 + h1) mod R, matching `KeccakTranscript::challenge_scalar`.
+    function squeeze(Transcript memory t) internal pure returns (uint256) {
+        uint256 h0 = uint256(keccak256(abi.encodePacked(t.state, t.pending, uint8(0))));
+        uint256 h1 = uint256(keccak256(abi.encodePacked(t.state, t.pending, uint8(1))));
+        t.state = bytes32(h0);
+        t.pending = "";
+        return addmod(mulmod(h0, R2, R), h1, R);
+    }
+
+    function challenge(Transcript memory t) internal pure returns (uint256 c) {
+        c = squeeze(t);
+        absorb(t, c);
+    }
+
+    function inverse(uint256 a) internal view returns (uint256 r) {
+        bool ok;
+        assembly {
+            let p := mload(0x40)
+            mstore(p, 0x20)
+            mstore(add(p, 0x20), 0x20)
+            mstore(add(p, 0x40), 0x20)
+            mstore(add(p, 0x60), a)
+            mstore(add(p, 0x80), sub(R, 2))
+            mstore(add(p, 0xa0), R)
+            ok := staticcall(gas(), 0x05, p, 0xc0, p, 0x20)
+            r := mload(p)
+        }
+        require(ok && a != 0, "inverse");
+    }
+
+    function expmod(uint256 b, uint256 e) internal pure returns (uint256 r) {
+        r = 1;
+        while (e != 0) {
+            if (e & 1 == 1) r = mulmod(r, b, R);
+            b = mulmod(b, b, R);
+            e >>= 1;
+        }
+    }
+
+    // L_i(z) = w^i (z^n - 1) / (n (z - w^i))
+    function lagrange(uint256 root, uint256 z, uint256 zh) internal view returns (uint256) {
+        uint256 den = mulmod(N, addmod(z, R - root, R), R);
+        return mulmod(mulmod(root, zh, R), inverse(den), R);
+    }
+
+    function ecAdd(uint256[2] memory a, uint256 x, uint256 y) internal view {
+        uint256[4] memory input = [a[0], a[1], x, y];
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x06, input, 0x80, a, 0x40)
+        }
+        require(ok, "ecAdd");
+    }
+
+    // acc += s * (x, y)
+    function ecMulAdd(uint256[2] memory acc, uint256 x, uint256 y, uint256 s) internal view {
+        uint256[3] memory input = [x, y, s];
+        uint256[2] memory p;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x07, input, 0x60, p, 0x40)
+        }
+        require(ok, "ecMul");
+        ecAdd(acc, p[0], p[1]);
+    }
+
+    function negate(uint256[2] memory p) internal pure returns (uint256[2] memory) {
+        if (p[0] == 0 && p[1] == 0) return p;
+        return [p[0], Q - (p[1] % Q)];
+    }
+
+    // e(c - v*G + z*W, H) == e(W, BETA_H)
+    function checkOpening(uint256[2] memory c, uint256 v, uint256 z, uint256 wx, uint256 wy)
+        internal view returns (bool)
+    {
+        ecMulAdd(c, G_X, G_Y, R - v);
+        ecMulAdd(c, wx, wy, z);
+        uint256[2] memory w = negate([wx, wy]);
+        uint256[12] memory input = [
+            c[0], c[1], H_0, H_1, H_2, H_3,
+            w[0], w[1], BETA_H_0, BETA_H_1, BETA_H_2, BETA_H_3
+        ];
+        uint256[1] memory out;
+        bool ok;
+        assembly {
+            ok := staticcall(gas(), 0x08, input, 0x180, out, 0x20)
+        }
+        return ok && out[0] == 1;
+    }
+"#;
+
+/// Body of the `verify` entry point. `{{ABSORB_PI}}` and `{{EVAL_PI}}` are
+/// replaced by the unrolled public input handling.
+const VERIFY_TEMPLATE: &str = r#"
+    // Calldata layout of the proof.
+    uint256 constant P_A = 0;
+    uint256 constant P_B = 2;
+    uint256 constant P_C = 4;
+    uint256 constant P_D = 6;
+    uint256 constant P_Z = 8;
+    uint256 constant P_F = 10;
+    uint256 constant P_H1 = 12;
+    uint256 constant P_H2 = 14;
+    uint256 constant P_Z2 = 16;
+    uint256 constant P_T1 = 18;
+    uint256 constant P_AW = 34;
+    uint256 constant P_SAW = 36;
+    uint256 constant E_A = 38;
+    uint256 constant E_B = 39;
+    uint256 constant E_C = 40;
+    uint256 constant E_D = 41;
+    uint256 constant E_S1 = 42;
+    uint256 constant E_S2 = 43;
+    uint256 constant E_S3 = 44;
+    uint256 constant E_PERM = 45;
+    uint256 constant E_F = 46;
+    uint256 constant E_QLOOKUP = 47;
+    uint256 constant E_Z2_NEXT = 48;
+    uint256 constant E_H1 = 49;
+    uint256 constant E_H1_NEXT = 50;
+    uint256 constant E_H2 = 51;
+    uint256 constant E_TABLE = 52;
+    uint256 constant E_TABLE_NEXT = 53;
+    uint256 constant E_QARITH = 54;
+    uint256 constant E_A_NEXT = 61;
+    uint256 constant E_B_NEXT = 62;
+    uint256 constant E_D_NEXT = 63;
+
+    // Slots of the challenges and intermediate values.
+    uint256 constant ZETA = 0;
+    uint256 constant BETA = 1;
+    uint256 constant GAMMA = 2;
+    uint256 constant DELTA = 3;
+    uint256 constant EPSILON = 4;
+    uint256 constant ALPHA = 5;
+    uint256 constant LOOKUP_SEP = 6;
+    uint256 constant Z = 7;
+    uint256 constant AW = 8;
+    uint256 constant SAW = 9;
+    uint256 constant ZN = 10;
+    uint256 constant ZH = 11;
+    uint256 constant L1 = 12;
+    uint256 constant R0 = 13;
+
+    function verify(uint256[] calldata proof, uint256[] calldata pi) external view returns (bool) {
+        require(proof.length == PROOF_WORDS, "proof length");
+        require(pi.length == PI_COUNT, "public input length");
+        for (uint256 i = 38; i < PROOF_WORDS; i++) {
+            require(proof[i] < R, "evaluation out of range");
+        }
+
+        uint256[14] memory v;
+        Transcript memory t = Transcript(TRANSCRIPT_STATE, TRANSCRIPT_PENDING);
+
+{{ABSORB_PI}}
+        for (uint256 i = P_A; i < P_Z; i += 2) absorbPoint(t, proof, i);
+        v[ZETA] = challenge(t);
+        absorbPoint(t, proof, P_F);
+        absorbPoint(t, proof, P_H1);
+        absorbPoint(t, proof, P_H2);
+        v[BETA] = challenge(t);
+        v[GAMMA] = challenge(t);
+        v[DELTA] = challenge(t);
+        v[EPSILON] = challenge(t);
+        require(
+            v[BETA] != v[GAMMA] && v[BETA] != v[DELTA] && v[BETA] != v[EPSILON]
+                && v[GAMMA] != v[DELTA] && v[GAMMA] != v[EPSILON] && v[DELTA] != v[EPSILON],
+            "challenges must be different"
+        );
+        absorbPoint(t, proof, P_Z);
+        v[ALPHA] = challenge(t);
+        v[LOOKUP_SEP] = challenge(t);
+        for (uint256 i = P_T1; i < P_AW; i += 2) absorbPoint(t, proof, i);
+        v[Z] = challenge(t);
+        for (uint256 i = E_A; i <= E_H2; i++) absorb(t, proof[i]);
+        for (uint256 i = E_QARITH; i < PROOF_WORDS; i++) absorb(t, proof[i]);
+        v[AW] = squeeze(t);
+        v[SAW] = squeeze(t);
+
+        v[ZN] = expmod(v[Z], N);
+        v[ZH] = addmod(v[ZN], R - 1, R);
+        v[L1] = mulmod(v[ZH], inverse(mulmod(N, addmod(v[Z], R - 1, R), R)), R);
+        v[R0] = computeR0(proof, v, publicInputEval(pi, v));
+
+        uint256[2] memory lin = linearisation(proof, v);
+        return checkAggregate(proof, v, lin) && checkShifted(proof, v);
+    }
+
+    function publicInputEval(uint256[] calldata pi, uint256[14] memory v)
+        internal view returns (uint256 piEval)
+    {
+        uint256 zh = v[ZH];
+{{EVAL_PI}}    }
+
+    function computeR0(uint256[] calldata p, uint256[14] memory v, uint256 piEval)
+        internal pure returns (uint256 r0)
+    {
+        uint256 alphaSq = mulmod(v[ALPHA], v[ALPHA], R);
+        uint256 sepSq = mulmod(v[LOOKUP_SEP], v[LOOKUP_SEP], R);
+        uint256 sepCu = mulmod(sepSq, v[LOOKUP_SEP], R);
+        uint256 epsOnePlusDelta = mulmod(v[EPSILON], addmod(1, v[DELTA], R), R);
+
+        uint256 perm = mulmod(v[ALPHA], p[E_PERM], R);
+        perm = mulmod(perm, addmod(addmod(p[E_A], mulmod(v[BETA], p[E_S1], R), R), v[GAMMA], R), R);
+        perm = mulmod(perm, addmod(addmod(p[E_B], mulmod(v[BETA], p[E_S2], R), R), v[GAMMA], R), R);
+        perm = mulmod(perm, addmod(addmod(p[E_C], mulmod(v[BETA], p[E_S3], R), R), v[GAMMA], R), R);
+        perm = mulmod(perm, addmod(p[E_D], v[GAMMA], R), R);
+
+        uint256 lookup = mulmod(mulmod(sepSq, p[E_Z2_NEXT], R),
+            addmod(epsOnePlusDelta, mulmod(v[DELTA], p[E_H2], R), R), R);
+        lookup = mulmod(lookup,
+            addmod(addmod(epsOnePlusDelta, p[E_H2], R), mulmod(v[DELTA], p[E_H1_NEXT], R), R), R);
+
+        r0 = addmod(piEval, R - mulmod(v[L1], alphaSq, R), R);
+        r0 = addmod(r0, R - perm, R);
+        r0 = addmod(r0, R - mulmod(v[L1], sepCu, R), R);
+        r0 = addmod(r0, R - lookup, R);
+    }
+
+    function linearisation(uint256[] calldata p, uint256[14] memory v)
+        internal view returns (uint256[2] memory acc)
+    {
+        // Arithmetic gate
+        uint256 qa = p[E_QARITH];
+        uint256 a5 = expmod(p[E_A], 5);
+        uint256 b5 = expmod(p[E_B], 5);
+        uint256 d5 = expmod(p[E_D], 5);
+        ecMulAdd(acc, QM_X, QM_Y, mulmod(qa, mulmod(p[E_A], p[E_B], R), R));
+        ecMulAdd(acc, QL_X, QL_Y, mulmod(qa, p[E_A], R));
+        ecMulAdd(acc, QR_X, QR_Y, mulmod(qa, p[E_B], R));
+        ecMulAdd(acc, QO_X, QO_Y, mulmod(qa, p[E_C], R));
+        ecMulAdd(acc, Q4_X, Q4_Y, mulmod(qa, p[E_D], R));
+        ecMulAdd(acc, QHL_X, QHL_Y, mulmod(qa, a5, R));
+        ecMulAdd(acc, QHR_X, QHR_Y, mulmod(qa, b5, R));
+        ecMulAdd(acc, QH4_X, QH4_Y, mulmod(qa, d5, R));
+        ecMulAdd(acc, QC_X, QC_Y, qa);
+
+        // Permutation argument
+        uint256 bz = mulmod(v[BETA], v[Z], R);
+        uint256 s = mulmod(v[ALPHA], addmod(addmod(p[E_A], bz, R), v[GAMMA], R), R);
+        s = mulmod(s, addmod(addmod(p[E_B], mulmod(K1, bz, R), R), v[GAMMA], R), R);
+        s = mulmod(s, addmod(addmod(p[E_C], mulmod(K2, bz, R), R), v[GAMMA], R), R);
+        s = mulmod(s, addmod(addmod(p[E_D], mulmod(K3, bz, R), R), v[GAMMA], R), R);
+        s = addmod(s, mulmod(v[L1], mulmod(v[ALPHA], v[ALPHA], R), R), R);
+        ecMulAdd(acc, p[P_Z], p[P_Z + 1], s);
+
+        s = mulmod(mulmod(v[ALPHA], v[BETA], R), p[E_PERM], R);
+        s = mulmod(s, addmod(addmod(p[E_A], mulmod(v[BETA], p[E_S1], R), R), v[GAMMA], R), R);
+        s = mulmod(s, addmod(addmod(p[E_B], mulmod(v[BETA], p[E_S2], R), R), v[GAMMA], R), R);
+        s = mulmod(s, addmod(addmod(p[E_C], mulmod(v[BETA], p[E_S3], R), R), v[GAMMA], R), R);
+        ecMulAdd(acc, S4_X, S4_Y, R - s);
+
+        lookupLinearisation(acc, p, v);
+
+        // - Z_H(z) * (t_1 + z^n t_2 + ... + z^7n t_8)
+        s = R - v[ZH];
+        for (uint256 i = P_T1; i < P_AW; i += 2) {
+            ecMulAdd(acc, p[i], p[i + 1], s);
+            s = mulmod(s, v[ZN], R);
+        }
+    }
+
+    function lookupLinearisation(uint256[2] memory acc, uint256[] calldata p, uint256[14] memory v)
+        internal view
+    {
+        uint256 sep = v[LOOKUP_SEP];
+        uint256 sepSq = mulmod(sep, sep, R);
+        uint256 zeta = v[ZETA];
+        uint256 onePlusDelta = addmod(1, v[DELTA], R);
+        uint256 epsOnePlusDelta = mulmod(v[EPSILON], onePlusDelta, R);
+
+        uint256 compressed = addmod(p[E_A], mulmod(zeta, p[E_B], R), R);
+        compressed = addmod(compressed, mulmod(mulmod(zeta, zeta, R), p[E_C], R), R);
+        compressed = addmod(compressed, mulmod(expmod(zeta, 3), p[E_D], R), R);
+        uint256 s = mulmod(addmod(compressed, R - p[E_F], R), sep, R);
+        ecMulAdd(acc, QLOOKUP_X, QLOOKUP_Y, s);
+
+        s = mulmod(addmod(v[EPSILON], p[E_F], R), onePlusDelta, R);
+        s = mulmod(s, addmod(addmod(epsOnePlusDelta, p[E_TABLE], R), mulmod(v[DELTA], p[E_TABLE_NEXT], R), R), R);
+        s = addmod(mulmod(s, sepSq, R), mulmod(v[L1], mulmod(sepSq, sep, R), R), R);
+        ecMulAdd(acc, p[P_Z2], p[P_Z2 + 1], s);
+
+        s = mulmod(p[E_Z2_NEXT], addmod(addmod(epsOnePlusDelta, p[E_H2], R), mulmod(v[DELTA], p[E_H1_NEXT], R), R), R);
+        ecMulAdd(acc, p[P_H1], p[P_H1 + 1], R - mulmod(s, sepSq, R));
+    }
+
+    function tableCommitment(uint256 zeta) internal view returns (uint256[2] memory acc) {
+        acc = [T1_X, T1_Y];
+        ecMulAdd(acc, T2_X, T2_Y, zeta);
+        ecMulAdd(acc, T3_X, T3_Y, mulmod(zeta, zeta, R));
+        ecMulAdd(acc, T4_X, T4_Y, expmod(zeta, 3));
+    }
+
+    function checkAggregate(uint256[] calldata p, uint256[14] memory v, uint256[2] memory lin)
+        internal view returns (bool)
+    {
+        uint256 ch = v[AW];
+        uint256 pow = ch;
+        uint256[2] memory c = lin;
+        uint256 value = R - v[R0];
+        uint256[2] memory table = tableCommitment(v[ZETA]);
+        uint256[12] memory points = [
+            S1_X, S1_Y, S2_X, S2_Y, S3_X, S3_Y,
+            p[P_F], p[P_F + 1], p[P_H2], p[P_H2 + 1], table[0], table[1]
+        ];
+        uint256[6] memory evals = [p[E_S1], p[E_S2], p[E_S3], p[E_F], p[E_H2], p[E_TABLE]];
+        for (uint256 i = 0; i < 6; i++) {
+            ecMulAdd(c, points[2 * i], points[2 * i + 1], pow);
+            value = addmod(value, mulmod(evals[i], pow, R), R);
+            pow = mulmod(pow, ch, R);
+        }
+        for (uint256 i = 0; i < 4; i++) {
+            ecMulAdd(c, p[P_A + 2 * i], p[P_A + 2 * i + 1], pow);
+            value = addmod(value, mulmod(p[E_A + i], pow, R), R);
+            pow = mulmod(pow, ch, R);
+        }
+        return checkOpening(c, value, v[Z], p[P_AW], p[P_AW + 1]);
+    }
+
+    function checkShifted(uint256[] calldata p, uint256[14] memory v) internal view returns (bool) {
+        uint256 ch = v[SAW];
+        uint256 pow = 1;
+        uint256[2] memory c;
+        uint256 value;
+        uint256[2] memory table = tableCommitment(v[ZETA]);
+        uint256[14] memory points = [
+            p[P_Z], p[P_Z + 1], p[P_A], p[P_A + 1], p[P_B], p[P_B + 1], p[P_D], p[P_D + 1],
+            p[P_H1], p[P_H1 + 1], p[P_Z2], p[P_Z2 + 1], table[0], table[1]
+        ];
+        uint256[7] memory evals = [
+            p[E_PERM], p[E_A_NEXT], p[E_B_NEXT], p[E_D_NEXT], p[E_H1_NEXT], p[E_Z2_NEXT], p[E_TABLE_NEXT]
+        ];
+        for (uint256 i = 0; i < 7; i++) {
+            ecMulAdd(c, points[2 * i], points[2 * i + 1], pow);
+            value = addmod(value, mulmod(evals[i], pow, R), R);
+            pow = mulmod(pow, ch, R);
+        }
+        return checkOpening(c, value, mulmod(v[Z], OMEGA, R), p[P_SAW], p[P_SAW + 1]);
+    }
+"#;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::proof_system::linearisation_poly::ProofEvaluations;
+    use ark_bn254::{Bn254, Fr, G1Affine};
+    use ark_ff::{One, Zero};
+    use ark_poly_commit::kzg10;
+
+    /// Big-endian word from a hex string without the `0x` prefix.
+    fn hex_word(hex: &str) -> [u8; EVM_WORD_SIZE] {
+        let hex = format!("{:0>64}", hex);
+        let mut word = [0u8; EVM_WORD_SIZE];
+        for (i, byte) in word.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        word
+    }
+
+    fn int_word(value: u64) -> [u8; EVM_WORD_SIZE] {
+        hex_word(&format!("{:x}", value))
+    }
+
+    /// A proof with `a_comm = G`, `b_comm = -G`, `aw_opening = G`, every
+    /// other point the identity, and the evaluations `1..=26` in calldata
+    /// order.
+    fn known_proof() -> Proof<Fr, KZG10<Bn254>> {
+        let g = G1Affine::prime_subgroup_generator();
+        let zero = G1Affine::zero();
+        let comm = |p: G1Affine| kzg10::Commitment(p);
+        let opening = |w: G1Affine| kzg10::Proof { w, random_v: None };
+        let s = |i: u64| Fr::from(i);
+
+        let mut evaluations = ProofEvaluations::<Fr>::default();
+        let wires = &mut evaluations.wire_evals;
+        (wires.a_eval, wires.b_eval, wires.c_eval, wires.d_eval) =
+            (s(1), s(2), s(3), s(4));
+        let perm = &mut evaluations.perm_evals;
+        perm.left_sigma_eval = s(5);
+        perm.right_sigma_eval = s(6);
+        perm.out_sigma_eval = s(7);
+        perm.permutation_eval = s(8);
+        let lookup = &mut evaluations.lookup_evals;
+        lookup.f_eval = s(9);
+        lookup.q_lookup_eval = s(10);
+        lookup.z2_next_eval = s(11);
+        lookup.h1_eval = s(12);
+        lookup.h1_next_eval = s(13);
+        lookup.h2_eval = s(14);
+        lookup.table_eval = s(15);
+        lookup.table_next_eval = s(16);
+        for (i, label) in CUSTOM_EVAL_LABELS.iter().enumerate() {
+            evaluations
+                .custom_evals
+                .vals
+                .push((label.to_string(), s(17 + i as u64)));
+        }
+
+        Proof {
+            a_comm: comm(g),
+            b_comm: comm(-g),
+            c_comm: comm(zero),
+            d_comm: comm(zero),
+            z_comm: comm(zero),
+            f_comm: comm(zero),
+            h_1_comm: comm(zero),
+            h_2_comm: comm(zero),
+            z_2_comm: comm(zero),
+            t_1_comm: comm(zero),
+            t_2_comm: comm(zero),
+            t_3_comm: comm(zero),
+            t_4_comm: comm(zero),
+            t_5_comm: comm(zero),
+            t_6_comm: comm(zero),
+            t_7_comm: comm(zero),
+            t_8_comm: comm(zero),
+            aw_opening: opening(g),
+            saw_opening: opening(zero),
+            evaluations,
+        }
+    }
+
+    #[test]
+    fn encode_proof_layout() {
+        // BN254 base field modulus minus 2, the `y` of `-G`.
+        let minus_two = hex_word(
+            "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45",
+        );
+        let mut expected = vec![int_word(1), int_word(2)];
+        expected.extend([int_word(1), minus_two]);
+        expected.extend(vec![[0u8; EVM_WORD_SIZE]; 2 * 15]);
+        expected.extend([int_word(1), int_word(2)]);
+        expected.extend(vec![[0u8; EVM_WORD_SIZE]; 2]);
+        expected.extend((1..=26).map(int_word));
+
+        let words =
+            SolidityVerifier::<Bn254>::encode_proof(&known_proof()).unwrap();
+        assert_eq!(words.len(), PROOF_WORDS);
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn encode_calldata_layout() {
+        let proof = known_proof();
+        let calldata =
+            SolidityVerifier::<Bn254>::encode_calldata(&proof, &[-Fr::one()])
+                .unwrap();
+
+        // `bytes4(keccak256("verify(uint256[],uint256[])"))`
+        let mut expected = vec![0xb8, 0x64, 0xf5, 0xa9];
+        expected.extend(int_word(2 * 32));
+        expected.extend(int_word((3 + PROOF_WORDS as u64) * 32));
+        expected.extend(int_word(PROOF_WORDS as u64));
+        SolidityVerifier::<Bn254>::encode_proof(&proof)
+            .unwrap()
+            .iter()
+            .for_each(|w| expected.extend(w));
+        expected.extend(int_word(1));
+        // BN254 scalar field modulus minus 1.
+        expected.extend(hex_word(
+            "30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+        ));
+        assert_eq!(calldata, expected);
+    }
+
+    #[test]
+    fn encode_proof_rejects_unknown_custom_evaluations() {
+        let mut proof = known_proof();
+        proof
+            .evaluations
+            .custom_evals
+            .vals
+            .push(("q_fixed_group_add_eval".to_string(), Fr::one()));
+        assert!(matches!(
+            SolidityVerifier::<Bn254>::encode_proof(&proof),
+            Err(Error::UnsupportedGate(_))
+        ));
+    }
+}
@@ -0,0 +1,546 @@
+//! Sparse Merkle trees over 256-bit keys, with updates and non-membership
+//! proven in circuit.
+//!
+//! The leaf of a key sits at the index given by the key read as a
+//! little-endian integer, so the direction bit of level `l` is bit `l` of
+//! the key. Leaves never set are the default leaf and every subtree holding
+//! only those hashes to a precomputed default, so that a tree of depth 256
+//! only stores the nodes above the leaves that were set.
+//!
+//! In circuit, a key is 32 byte variables, each decomposed into its eight
+//! bits, which both range checks the byte and gives the direction bits.
+use crate::{
+    constraint_system::{CircuitBuilder, Variable},
+    limbs::to_u64,
+    merkle::{MerkleHash, MerklePathVar},
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use hashbrown::HashMap;
+
+/// Key of a leaf.
+pub type Key = [u8; 32];
+
+/// Largest depth, as many levels as key bits.
+pub const MAX_DEPTH: usize = 256;
+
+fn key_bit(key: &Key, level: usize) -> bool {
+    (key[level / 8] >> (level % 8)) & 1 == 1
+}
+
+/// `key` with the bits below `level` cleared, naming the node at `level`
+/// above its leaf.
+fn prefix(key: &Key, level: usize) -> Key {
+    let mut prefix = *key;
+    for bit in 0..level {
+        prefix[bit / 8] &= !(1 << (bit % 8));
+    }
+    prefix
+}
+
+/// Authentication path of a key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SparseMerklePath<N> {
+    /// Key of the leaf.
+    pub key: Key,
+    /// Siblings of the nodes on the path, from the leaf up.
+    pub siblings: Vec<N>,
+}
+
+impl<N> SparseMerklePath<N>
+where
+    N: Clone + core::fmt::Debug + Eq,
+{
+    /// Root of the tree holding `leaf` at this path.
+    pub fn root<F, H>(&self, hash: &H, leaf: &N) -> N
+    where
+        F: PrimeField,
+        H: MerkleHash<F, Node = N>,
+    {
+        self.siblings.iter().enumerate().fold(
+            leaf.clone(),
+            |node, (level, sibling)| match key_bit(&self.key, level) {
+                true => hash.hash(sibling, &node),
+                false => hash.hash(&node, sibling),
+            },
+        )
+    }
+}
+
+/// Witness of the update of one leaf.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SparseMerkleUpdate<N> {
+    /// Path of the key, the same before and after the update.
+    pub path: SparseMerklePath<N>,
+    /// Leaf before the update.
+    pub old_leaf: N,
+    /// Leaf after the update.
+    pub new_leaf: N,
+    /// Root before the update.
+    pub old_root: N,
+    /// Root after the update.
+    pub new_root: N,
+}
+
+/// Sparse Merkle tree storing only the nodes that differ from the defaults.
+#[derive(Clone, Debug)]
+pub struct SparseMerkleTree<F, H>
+where
+    F: PrimeField,
+    H: MerkleHash<F>,
+{
+    hash: H,
+    depth: usize,
+    /// Root of an empty subtree, by level.
+    defaults: Vec<H::Node>,
+    /// Nodes set so far, by level and key prefix.
+    nodes: HashMap<(usize, Key), H::Node>,
+    __: core::marker::PhantomData<F>,
+}
+
+impl<F, H> SparseMerkleTree<F, H>
+where
+    F: PrimeField,
+    H: MerkleHash<F>,
+{
+    /// Creates an empty tree of the given depth.
+    ///
+    /// # Panics
+    ///
+    /// If the depth is above [`MAX_DEPTH`].
+    pub fn new(hash: H, depth: usize, default_leaf: H::Node) -> Self {
+        assert!(depth <= MAX_DEPTH, "the tree is too deep");
+        let mut defaults = Vec::with_capacity(depth + 1);
+        defaults.push(default_leaf);
+        for level in 0..depth {
+            let node = hash.hash(&defaults[level], &defaults[level]);
+            defaults.push(node);
+        }
+        Self {
+            hash,
+            depth,
+            defaults,
+            nodes: HashMap::new(),
+            __: core::marker::PhantomData,
+        }
+    }
+
+    /// Depth of the tree.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The default leaf.
+    pub fn default_leaf(&self) -> &H::Node {
+        &self.defaults[0]
+    }
+
+    fn node(&self, level: usize, prefix: &Key) -> &H::Node {
+        self.nodes
+            .get(&(level, *prefix))
+            .unwrap_or(&self.defaults[level])
+    }
+
+    /// Root of the tree.
+    pub fn root(&self) -> &H::Node {
+        self.node(self.depth, &[0; 32])
+    }
+
+    /// Leaf of `key`, the default leaf if it was never set.
+    pub fn get(&self, key: &Key) -> &H::Node {
+        self.check_key(key);
+        self.node(0, key)
+    }
+
+    /// Authentication path of `key`.
+    pub fn path(&self, key: &Key) -> SparseMerklePath<H::Node> {
+        self.check_key(key);
+        let siblings = (0..self.depth)
+            .map(|level| {
+                let mut sibling = prefix(key, level);
+                sibling[level / 8] ^= 1 << (level % 8);
+                self.node(level, &sibling).clone()
+            })
+            .collect();
+        SparseMerklePath {
+            key: *key,
+            siblings,
+        }
+    }
+
+    /// Sets the leaf of `key` and returns the witness of the update.
+    pub fn insert(
+        &mut self,
+        key: &Key,
+        leaf: H::Node,
+    ) -> SparseMerkleUpdate<H::Node> {
+        let path = self.path(key);
+        let old_leaf = self.get(key).clone();
+        let old_root = self.root().clone();
+
+        let mut node = leaf.clone();
+        for (level, sibling) in path.siblings.iter().enumerate() {
+            let next = match key_bit(key, level) {
+                true => self.hash.hash(sibling, &node),
+                false => self.hash.hash(&node, sibling),
+            };
+            self.set_node(level, prefix(key, level), node);
+            node = next;
+        }
+        self.set_node(self.depth, [0; 32], node);
+
+        SparseMerkleUpdate {
+            path,
+            old_leaf,
+            new_leaf: leaf,
+            old_root,
+            new_root: self.root().clone(),
+        }
+    }
+
+    /// Stores a node, or forgets it when it is back to the default.
+    fn set_node(&mut self, level: usize, prefix: Key, node: H::Node) {
+        match node == self.defaults[level] {
+            true => self.nodes.remove(&(level, prefix)),
+            false => self.nodes.insert((level, prefix), node),
+        };
+    }
+
+    /// Panics if `key` has bits set at or above the depth.
+    fn check_key(&self, key: &Key) {
+        assert!(
+            (self.depth..MAX_DEPTH).all(|level| !key_bit(key, level)),
+            "the key does not fit in the tree"
+        );
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Allocates the bytes of a key, their values being unknown without a
+    /// witness. They are range checked by [`Self::add_sparse_merkle_path`].
+    pub fn add_sparse_merkle_key(
+        &mut self,
+        key: Option<&Key>,
+    ) -> [Variable; 32] {
+        let mut bytes = [self.zero_var; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.add_witness(key.map(|key| F::from(key[i])));
+        }
+        bytes
+    }
+
+    /// Allocates the authentication path of `key` in a tree of `depth`
+    /// levels. Every byte of the key is decomposed into bits constrained to
+    /// be boolean, the bits below the depth being the direction bits, least
+    /// significant first, and the others being constrained to zero.
+    ///
+    /// # Panics
+    ///
+    /// If the depth is above [`MAX_DEPTH`].
+    pub fn add_sparse_merkle_path<H>(
+        &mut self,
+        key: &[Variable; 32],
+        depth: usize,
+        path: Option<&SparseMerklePath<H::Node>>,
+    ) -> MerklePathVar
+    where
+        H: MerkleHash<F>,
+    {
+        assert!(depth <= MAX_DEPTH, "the tree is too deep");
+        let mut directions = Vec::with_capacity(depth);
+        for (i, byte) in key.iter().enumerate() {
+            let value = self.value(*byte).map(to_u64);
+            let mut terms = Vec::with_capacity(9);
+            for j in 0..8 {
+                let bit = self.add_boolean(value.map(|v| (v >> j) & 1 == 1));
+                terms.push((bit, F::from(1u64 << j)));
+                match 8 * i + j < depth {
+                    true => directions.push(bit),
+                    false => self.constrain_to_constant(bit, F::zero(), None),
+                }
+            }
+            terms.push((*byte, -F::one()));
+            self.constrain_linear(terms, F::zero());
+        }
+
+        let siblings = (0..depth)
+            .map(|level| {
+                let sibling = path.map(|path| &path.siblings[level]);
+                self.add_merkle_node::<H>(sibling)
+            })
+            .collect();
+        MerklePathVar {
+            directions,
+            siblings,
+        }
+    }
+
+    /// Constrains the roots of the tree before and after replacing
+    /// `old_leaf` by `new_leaf` at `path`, returned in that order.
+    ///
+    /// Both roots share the direction bits, the siblings and the products
+    /// `bit·sibling`. Each level then orders the children of a root with two
+    /// rows per node variable, `left = node - bit·node + bit·sibling` and
+    /// `right = node + sibling - left`.
+    pub fn sparse_merkle_update<H>(
+        &mut self,
+        hash: &H,
+        old_leaf: &[Variable],
+        new_leaf: &[Variable],
+        path: &MerklePathVar,
+    ) -> (Vec<Variable>, Vec<Variable>)
+    where
+        H: MerkleHash<F>,
+    {
+        let mut state = hash.gadget_state(self);
+        let mut old = old_leaf.to_vec();
+        let mut new = new_leaf.to_vec();
+        for (bit, sibling) in path.directions.iter().zip(path.siblings.iter()) {
+            let bit_sibling = self.bit_sibling(*bit, sibling);
+            old = self.sparse_merkle_level(
+                hash,
+                &mut state,
+                *bit,
+                sibling,
+                &bit_sibling,
+                &old,
+            );
+            new = self.sparse_merkle_level(
+                hash,
+                &mut state,
+                *bit,
+                sibling,
+                &bit_sibling,
+                &new,
+            );
+        }
+        (old, new)
+    }
+
+    /// Constrains the root of a tree in which the key of `path` holds the
+    /// default leaf, proving that the key was never set.
+    pub fn sparse_merkle_non_membership<H>(
+        &mut self,
+        hash: &H,
+        default_leaf: &H::Node,
+        path: &MerklePathVar,
+    ) -> Vec<Variable>
+    where
+        H: MerkleHash<F>,
+    {
+        let mut state = hash.gadget_state(self);
+        let mut node = H::node_values(default_leaf)
+            .into_iter()
+            .map(|value| {
+                let var = self.add_input(value);
+                self.constrain_to_constant(var, value, None);
+                var
+            })
+            .collect::<Vec<_>>();
+        for (bit, sibling) in path.directions.iter().zip(path.siblings.iter()) {
+            let bit_sibling = self.bit_sibling(*bit, sibling);
+            node = self.sparse_merkle_level(
+                hash,
+                &mut state,
+                *bit,
+                sibling,
+                &bit_sibling,
+                &node,
+            );
+        }
+        node
+    }
+
+    fn bit_sibling(
+        &mut self,
+        bit: Variable,
+        sibling: &[Variable],
+    ) -> Vec<Variable> {
+        sibling
+            .iter()
+            .map(|sibling| {
+                self.arithmetic_gate(|gate| {
+                    gate.witness(bit, *sibling, None).mul(F::one())
+                })
+            })
+            .collect()
+    }
+
+    /// Hashes `node` with its sibling, in the order given by `bit`.
+    fn sparse_merkle_level<H>(
+        &mut self,
+        hash: &H,
+        state: &mut H::GadgetState,
+        bit: Variable,
+        sibling: &[Variable],
+        bit_sibling: &[Variable],
+        node: &[Variable],
+    ) -> Vec<Variable>
+    where
+        H: MerkleHash<F>,
+    {
+        let (left, right): (Vec<_>, Vec<_>) = node
+            .iter()
+            .zip(sibling.iter().zip(bit_sibling.iter()))
+            .map(|(node, (sibling, bit_sibling))| {
+                let left = self.arithmetic_gate(|gate| {
+                    gate.witness(bit, *node, None)
+                        .mul(-F::one())
+                        .add(F::zero(), F::one())
+                        .fan_in_3(F::one(), *bit_sibling)
+                });
+                let right = self.arithmetic_gate(|gate| {
+                    gate.witness(*node, *sibling, None)
+                        .add(F::one(), F::one())
+                        .fan_in_3(-F::one(), left)
+                });
+                (left, right)
+            })
+            .unzip();
+        hash.hash_gadget(self, state, &left, &right)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::poseidon::PoseidonParameters;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+    use ark_std::UniformRand;
+
+    type Tree = SparseMerkleTree<Fr, PoseidonParameters<Fr>>;
+
+    fn poseidon_params() -> PoseidonParameters<Fr> {
+        let rng = &mut ark_std::test_rng();
+        let mut rand_vec = |len| (0..len).map(|_| Fr::rand(rng)).collect();
+        let mds = (0..3).map(|_| rand_vec(3)).collect();
+        let ark = (0..8 + 57).map(|_| rand_vec(3)).collect();
+        PoseidonParameters::new(8, 57, 5, mds, ark)
+    }
+
+    fn key(low: u16) -> Key {
+        let mut key = [0; 32];
+        key[..2].copy_from_slice(&low.to_le_bytes());
+        key
+    }
+
+    /// A tree of depth 16 holding a few leaves.
+    fn tree() -> Tree {
+        let mut tree = Tree::new(poseidon_params(), 16, Fr::from(0u64));
+        for (k, leaf) in [(3, 30u64), (0x8001, 10), (0xfffe, 20)] {
+            tree.insert(&key(k), Fr::from(leaf));
+        }
+        tree
+    }
+
+    fn add_path(
+        cs: &mut CircuitBuilder<Fr, EdwardsParameters>,
+        tree: &Tree,
+        key: &Key,
+    ) -> MerklePathVar {
+        let key_vars = cs.add_sparse_merkle_key(Some(key));
+        cs.add_sparse_merkle_path::<PoseidonParameters<Fr>>(
+            &key_vars,
+            tree.depth(),
+            Some(&tree.path(key)),
+        )
+    }
+
+    #[test]
+    fn resetting_leaves_forgets_their_nodes() {
+        let params = poseidon_params();
+        let (a, b) = ([0xa5; 32], [0x5a; 32]);
+        let mut tree = Tree::new(params.clone(), MAX_DEPTH, Fr::from(0u64));
+        let empty_root = *tree.root();
+        let mut only_b = tree.clone();
+        only_b.insert(&b, Fr::from(2u64));
+
+        tree.insert(&a, Fr::from(1u64));
+        tree.insert(&b, Fr::from(2u64));
+        let update = tree.insert(&a, Fr::from(0u64));
+        assert_eq!(update.path.root(&params, &update.new_leaf), *tree.root());
+        assert_eq!(tree.root(), only_b.root());
+        assert_eq!(tree.nodes, only_b.nodes);
+
+        tree.insert(&b, Fr::from(0u64));
+        assert_eq!(*tree.root(), empty_root);
+        assert!(tree.nodes.is_empty());
+    }
+
+    #[test]
+    fn update_gadget_matches_native() {
+        let params = poseidon_params();
+        for k in [3, 4, 0xfffe] {
+            let mut tree = tree();
+            let key = key(k);
+            let update = tree.insert(&key, Fr::from(99u64));
+            assert_eq!(
+                update.path.root(&params, &update.old_leaf),
+                update.old_root
+            );
+
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let path = add_path(&mut cs, &tree, &key);
+            let old_leaf = cs.add_input(update.old_leaf);
+            let new_leaf = cs.add_input(update.new_leaf);
+            let (old_root, new_root) = cs.sparse_merkle_update(
+                &params,
+                &[old_leaf],
+                &[new_leaf],
+                &path,
+            );
+            assert_eq!(cs.value(old_root[0]), Some(update.old_root));
+            assert_eq!(cs.value(new_root[0]), Some(update.new_root));
+            assert_eq!(cs.check_satisfied(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn non_membership_gadget_matches_native() {
+        let params = poseidon_params();
+        let tree = tree();
+        for (k, member) in [(4, false), (0xffff, false), (3, true)] {
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let path = add_path(&mut cs, &tree, &key(k));
+            let root = cs.sparse_merkle_non_membership(
+                &params,
+                tree.default_leaf(),
+                &path,
+            );
+            assert_eq!(cs.value(root[0]) == Some(*tree.root()), !member);
+            assert_eq!(cs.check_satisfied(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn keys_are_range_checked() {
+        let tree = tree();
+        let path = tree.path(&key(3));
+        let unsatisfied = |key: &Key, first_byte: Option<u64>| {
+            let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+            let key_vars = cs.add_sparse_merkle_key(Some(key));
+            if let Some(byte) = first_byte {
+                cs.variables.insert(key_vars[0], Fr::from(byte));
+            }
+            cs.add_sparse_merkle_path::<PoseidonParameters<Fr>>(
+                &key_vars,
+                tree.depth(),
+                Some(&path),
+            );
+            cs.check_satisfied().is_err()
+        };
+
+        assert!(!unsatisfied(&key(3), None));
+        // a byte out of range
+        assert!(unsatisfied(&key(3), Some(3 + 256)));
+        // a bit at the depth
+        let mut above = key(3);
+        above[2] = 1;
+        assert!(unsatisfied(&above, None));
+    }
+}
@@ -0,0 +1,185 @@
+//! Size and cost report of a [`CircuitBuilder`], used to track the cost of
+//! gadgets across releases.
+use crate::constraint_system::{CircuitBuilder, GateKind};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use core::fmt;
+use serde::Serialize;
+
+/// Number of rows, variables and lookups used by a circuit.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct CircuitStats {
+    /// Arithmetic rows, including `poly_gate` rows.
+    pub arithmetic: usize,
+    /// Plookup rows.
+    pub lookup: usize,
+    /// Range rows.
+    pub range: usize,
+    /// Logic rows.
+    pub logic: usize,
+    /// Fixed and variable base curve addition rows.
+    pub curve: usize,
+    /// Dummy rows added by `add_dummy_constraints`.
+    pub dummy: usize,
+    /// Blinding rows added by `add_blinding_factors`.
+    pub blinding: usize,
+    /// Total number of rows.
+    pub rows: usize,
+    /// Number of rows of the lookup table.
+    pub lookup_table_size: usize,
+    /// Number of variables allocated.
+    pub variables: usize,
+    /// Number of copy constraint cycles, i.e. variables used on more than one
+    /// wire.
+    pub copy_cycles: usize,
+    /// Number of public inputs.
+    pub public_inputs: usize,
+    /// Domain size the circuit is proven over.
+    pub circuit_bound: usize,
+    /// Rows added to reach `circuit_bound`.
+    pub padding: usize,
+    /// Rows spent in each region, nested regions being counted in their
+    /// parents too.
+    pub regions: Vec<(String, usize)>,
+}
+
+impl CircuitStats {
+    /// Serializes the statistics to JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = [
+            ("arithmetic rows", self.arithmetic),
+            ("lookup rows", self.lookup),
+            ("range rows", self.range),
+            ("logic rows", self.logic),
+            ("curve rows", self.curve),
+            ("dummy rows", self.dummy),
+            ("blinding rows", self.blinding),
+            ("total rows", self.rows),
+            ("lookup table size", self.lookup_table_size),
+            ("variables", self.variables),
+            ("copy cycles", self.copy_cycles),
+            ("public inputs", self.public_inputs),
+            ("circuit bound", self.circuit_bound),
+            ("padding", self.padding),
+        ];
+        let width = self
+            .regions
+            .iter()
+            .map(|(path, _)| path.len())
+            .chain(rows.iter().map(|(label, _)| label.len()))
+            .max()
+            .unwrap_or(0);
+
+        for (label, value) in rows.iter() {
+            writeln!(f, "{:<width$}  {:>10}", label, value, width = width)?;
+        }
+        if !self.regions.is_empty() {
+            writeln!(f)?;
+            for (path, value) in self.regions.iter() {
+                writeln!(f, "{:<width$}  {:>10}", path, value, width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F, P> CircuitBuilder<F, P>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    /// Returns the size and cost report of the circuit built so far.
+    pub fn stats(&self) -> CircuitStats {
+        let mut stats = CircuitStats::default();
+        for kind in self.gate_kinds.iter() {
+            let count = match kind {
+                GateKind::Arithmetic => &mut stats.arithmetic,
+                GateKind::Lookup => &mut stats.lookup,
+                GateKind::Range => &mut stats.range,
+                GateKind::Logic => &mut stats.logic,
+                GateKind::Curve => &mut stats.curve,
+                GateKind::Dummy => &mut stats.dummy,
+                GateKind::Blinding => &mut stats.blinding,
+            };
+            *count += 1;
+        }
+
+        // List regions in the order they were opened.
+        let mut regions = self.regions.clone();
+        regions.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)));
+
+        let circuit_bound = self.circuit_bound();
+        CircuitStats {
+            rows: self.n,
+            lookup_table_size: self.lookup_table.size(),
+            variables: self.perm.variable_map.len(),
+            copy_cycles: self
+                .perm
+                .variable_map
+                .values()
+                .filter(|wires| wires.len() > 1)
+                .count(),
+            public_inputs: self.intended_pi_pos.len(),
+            circuit_bound,
+            padding: circuit_bound - self.n,
+            regions: regions
+                .into_iter()
+                .map(|(path, start, end)| (path, end - start))
+                .collect(),
+            ..stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bls12_381::Fr;
+    use ark_ed_on_bls12_381::EdwardsParameters;
+
+    #[test]
+    fn stats_count_rows_by_kind_and_region() {
+        let mut cs = CircuitBuilder::<Fr, EdwardsParameters>::new();
+        let before = cs.stats();
+
+        cs.lookup_table_mut().insert_row(
+            Fr::from(3u64),
+            Fr::from(5u64),
+            Fr::from(15u64),
+            Fr::from(0u64),
+        );
+        cs.namespace("mul", |cs| {
+            let a = cs.add_input(Fr::from(3u64));
+            let b = cs.add_input(Fr::from(5u64));
+            let c = cs.arithmetic_gate(|gate| {
+                gate.witness(a, b, None).mul(Fr::from(1u64))
+            });
+            cs.namespace("lookup", |cs| {
+                cs.lookup_gate(a, b, c, None, None);
+            });
+        });
+
+        let stats = cs.stats();
+        assert_eq!(stats.arithmetic, before.arithmetic + 1);
+        assert_eq!(stats.lookup, before.lookup + 1);
+        assert_eq!(stats.rows, before.rows + 2);
+        assert_eq!(stats.lookup_table_size, before.lookup_table_size + 1);
+        assert_eq!(stats.circuit_bound, cs.circuit_bound());
+        assert_eq!(stats.padding, stats.circuit_bound - stats.rows);
+        assert_eq!(
+            stats.regions,
+            vec![("mul".to_string(), 2), ("mul/lookup".to_string(), 1)]
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["rows"], stats.rows);
+        assert_eq!(json["regions"][1][0], "mul/lookup");
+    }
+}
@@ -1,10 +1,19 @@
 use ark_ff::PrimeField;
 use ark_serialize::CanonicalSerialize;
 use merlin::Transcript;
+use sha3::{Digest, Keccak256};
 
 /// Transcript adds an abstraction over the Merlin transcript
 /// For convenience
-pub(crate) trait TranscriptProtocol {
+pub trait TranscriptProtocol {
+    /// Create a new transcript seeded with the given `label`.
+    fn new(label: &'static [u8]) -> Self
+    where
+        Self: Sized;
+
+    /// Append raw `message` bytes with the given `label`.
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]);
+
     /// Append an `item` with the given `label`.
     fn append(&mut self, label: &'static [u8], item: &impl CanonicalSerialize);
 
@@ -16,6 +25,14 @@ pub(crate) trait TranscriptProtocol {
 }
 
 impl TranscriptProtocol for Transcript {
+    fn new(label: &'static [u8]) -> Self {
+        Transcript::new(label)
+    }
+
+    fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        Transcript::append_message(self, label, message)
+    }
+
     fn append(&mut self, label: &'static [u8], item: &impl CanonicalSerialize) {
         let mut bytes = Vec::new();
         item.serialize(&mut bytes).unwrap();
@@ -26,12 +43,12 @@ impl TranscriptProtocol for Transcript {
     where
         F: PrimeField,
     {
-        // XXX: review this: assure from_random_bytes returnes a valid Field
-        // element
-        let size = F::size_in_bits() / 8;
+        // Squeeze twice the modulus size and reduce, so the challenge is
+        // statistically close to uniform and can never fail to decode.
+        let size = 2 * F::size_in_bits().div_ceil(8);
         let mut buf = vec![0u8; size];
         self.challenge_bytes(label, &mut buf);
-        F::from_random_bytes(&buf).unwrap()
+        F::from_le_bytes_mod_order(&buf)
     }
 
     fn circuit_domain_sep(&mut self, n: u64) {
@@ -39,3 +56,226 @@ impl TranscriptProtocol for Transcript {
         self.append_u64(b"n", n);
     }
 }
+
+/// Size in bytes of an EVM word.
+pub(crate) const EVM_WORD_SIZE: usize = 32;
+
+/// Keccak256 based transcript whose challenges can be recomputed by an EVM
+/// contract.
+///
+/// Framing:
+/// - Labels are not absorbed, they only exist for parity with Merlin. The
+///   label given to `new` is absorbed as a message.
+/// - Messages are absorbed as raw bytes, right padded with zeroes to a whole
+///   number of 32 byte words so that every later item stays word aligned.
+/// - Every appended item is re-encoded as big-endian words, see
+///   [`to_evm_words`]. Field elements become one `uint256`, affine points
+///   become `(x, y)`.
+/// - `circuit_domain_sep(n)` absorbs `n` as a single big-endian word.
+/// - A challenge is `(h0 * 2^256 + h1) mod r` where
+///   `h_i = keccak256(state || pending || uint8(i))`. Afterwards `state = h0`
+///   and the pending buffer is cleared.
+#[derive(Clone, Debug)]
+pub struct KeccakTranscript {
+    /// Digest chained from the last squeeze.
+    state: [u8; EVM_WORD_SIZE],
+
+    /// Words absorbed since the last squeeze.
+    pending: Vec<u8>,
+}
+
+impl KeccakTranscript {
+    /// Absorb a single big-endian word.
+    fn absorb_word(&mut self, word: &[u8; EVM_WORD_SIZE]) {
+        self.pending.extend_from_slice(word);
+    }
+
+    /// Returns the chained digest and the words absorbed since the last
+    /// squeeze, so an external verifier can resume from this point.
+    pub fn snapshot(&self) -> ([u8; EVM_WORD_SIZE], Vec<u8>) {
+        (self.state, self.pending.clone())
+    }
+
+    /// Hash the current state, the pending words and a counter byte.
+    fn squeeze(&self, counter: u8) -> [u8; EVM_WORD_SIZE] {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.state);
+        hasher.update(&self.pending);
+        hasher.update([counter]);
+        hasher.finalize().into()
+    }
+}
+
+/// Re-encode the little-endian arkworks serialization of `item` as a list
+/// of big-endian EVM words.
+///
+/// Items whose compressed and uncompressed encodings have the same size are
+/// treated as field elements: the integer is written big-endian and left
+/// padded to a whole number of words. Other items are treated as short
+/// Weierstrass affine points, each coordinate being encoded as a field
+/// element, so a point takes two words on BN254 and four on the BLS curves.
+/// The identity is encoded as `(0, 0)`.
+pub(crate) fn to_evm_words(
+    item: &impl CanonicalSerialize,
+) -> Vec<[u8; EVM_WORD_SIZE]> {
+    let mut bytes = Vec::new();
+    item.serialize_uncompressed(&mut bytes).unwrap();
+    let coordinate_size = item.uncompressed_size() - item.serialized_size();
+    if coordinate_size == 0 {
+        return be_words(&bytes);
+    }
+
+    // The flags of `y` either sit in the two top bits of its last byte or,
+    // when the field has no spare bits, in an extra byte.
+    let (x, y) = bytes.split_at(coordinate_size);
+    if y[y.len() - 1] & SW_INFINITY_FLAG != 0 {
+        let zero = be_words(&vec![0u8; coordinate_size]);
+        return [zero.clone(), zero].concat();
+    }
+    let mut y = y[..coordinate_size].to_vec();
+    if bytes.len() == 2 * coordinate_size {
+        y[coordinate_size - 1] &= !SW_FLAGS_MASK;
+    }
+    [be_words(x), be_words(&y)].concat()
+}
+
+/// Bits of the last serialized byte of a point holding its flags.
+const SW_FLAGS_MASK: u8 = 0b1100_0000;
+
+/// Flag set in the last serialized byte of the point at infinity.
+const SW_INFINITY_FLAG: u8 = 1 << 6;
+
+/// Reverse little-endian `bytes` into big-endian words, left padding the
+/// first one.
+fn be_words(bytes: &[u8]) -> Vec<[u8; EVM_WORD_SIZE]> {
+    let len = bytes.len().div_ceil(EVM_WORD_SIZE);
+    let mut be = vec![0u8; len * EVM_WORD_SIZE];
+    be.iter_mut()
+        .rev()
+        .zip(bytes)
+        .for_each(|(dst, src)| *dst = *src);
+    be.chunks(EVM_WORD_SIZE)
+        .map(|chunk| {
+            let mut word = [0u8; EVM_WORD_SIZE];
+            word.copy_from_slice(chunk);
+            word
+        })
+        .collect()
+}
+
+impl TranscriptProtocol for KeccakTranscript {
+    fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            state: [0u8; EVM_WORD_SIZE],
+            pending: Vec::new(),
+        };
+        transcript.append_message(b"dom-sep", label);
+        transcript
+    }
+
+    fn append_message(&mut self, _label: &'static [u8], message: &[u8]) {
+        self.pending.extend_from_slice(message);
+        let padding =
+            (EVM_WORD_SIZE - message.len() % EVM_WORD_SIZE) % EVM_WORD_SIZE;
+        self.pending.extend(core::iter::repeat_n(0u8, padding));
+    }
+
+    fn append(
+        &mut self,
+        _label: &'static [u8],
+        item: &impl CanonicalSerialize,
+    ) {
+        to_evm_words(item)
+            .iter()
+            .for_each(|word| self.absorb_word(word));
+    }
+
+    fn challenge_scalar<F>(&mut self, _label: &'static [u8]) -> F
+    where
+        F: PrimeField,
+    {
+        let h0 = self.squeeze(0);
+        let h1 = self.squeeze(1);
+        self.state = h0;
+        self.pending.clear();
+        F::from_be_bytes_mod_order(&[h0, h1].concat())
+    }
+
+    fn circuit_domain_sep(&mut self, n: u64) {
+        let mut word = [0u8; EVM_WORD_SIZE];
+        word[EVM_WORD_SIZE - 8..].copy_from_slice(&n.to_be_bytes());
+        self.absorb_word(&word);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_bn254::{Fr, G1Affine};
+    use ark_ec::AffineCurve;
+    use ark_ff::{BigInteger, Zero};
+
+    /// `keccak256(data)` computed independently of the transcript.
+    fn keccak(data: &[u8]) -> [u8; 32] {
+        Keccak256::digest(data).into()
+    }
+
+    /// Big-endian 32 byte word holding `value`.
+    fn word(value: u64) -> [u8; 32] {
+        let mut word = [0u8; 32];
+        word[24..].copy_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    #[test]
+    fn challenges_match_reference_keccak() {
+        let mut transcript =
+            <KeccakTranscript as TranscriptProtocol>::new(b"plonk");
+        transcript.append(b"x", &Fr::from(5u64));
+        transcript.append(b"g", &G1Affine::prime_subgroup_generator());
+        transcript.circuit_domain_sep(8);
+        let first: Fr = transcript.challenge_scalar(b"c");
+        transcript.append(b"id", &G1Affine::zero());
+        let second: Fr = transcript.challenge_scalar(b"c");
+
+        // The label padded to a word, then `5`, the generator `(1, 2)` and
+        // `n = 8` as big-endian words.
+        let mut label = [0u8; 32];
+        label[..5].copy_from_slice(b"plonk");
+        let pending = [label, word(5), word(1), word(2), word(8)].concat();
+        let h0 = keccak(&[&[0u8; 32][..], &pending, &[0]].concat());
+        let h1 = keccak(&[&[0u8; 32][..], &pending, &[1]].concat());
+        assert_eq!(first, Fr::from_be_bytes_mod_order(&[h0, h1].concat()));
+
+        // The state is chained and the identity is encoded as `(0, 0)`.
+        let pending = [word(0), word(0)].concat();
+        let g0 = keccak(&[&h0[..], &pending, &[0]].concat());
+        let g1 = keccak(&[&h0[..], &pending, &[1]].concat());
+        assert_eq!(second, Fr::from_be_bytes_mod_order(&[g0, g1].concat()));
+    }
+
+    #[test]
+    fn field_elements_are_big_endian_words() {
+        let x = -Fr::from(1u64);
+        let words = to_evm_words(&x);
+        assert_eq!(words.len(), 1);
+        assert_eq!(words[0].to_vec(), x.into_repr().to_bytes_be());
+    }
+
+    #[test]
+    fn points_drop_their_flags() {
+        // On BLS12-381 each coordinate takes 48 bytes, padded to two words,
+        // and the flags sit in the spare top bits of `y`.
+        let g = ark_bls12_381::G1Affine::prime_subgroup_generator();
+        let words = to_evm_words(&g);
+        assert_eq!(words.len(), 4);
+        let y = [words[2], words[3]].concat();
+        assert_eq!(y[16..].to_vec(), g.y.into_repr().to_bytes_be());
+        let x = [words[0], words[1]].concat();
+        assert_eq!(x[16..].to_vec(), g.x.into_repr().to_bytes_be());
+        assert_eq!(
+            to_evm_words(&-g)[2..],
+            be_words(&(-g).y.into_repr().to_bytes_le())[..]
+        );
+    }
+}
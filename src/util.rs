@@ -0,0 +1,75 @@
+//! Utility functions and macros shared across the crate.
+
+use ark_ff::Field;
+
+/// Returns an iterator over increasing powers of the given `scalar` starting
+/// at `0`.
+#[inline]
+pub fn powers_of<F>(scalar: F) -> impl Iterator<Item = F>
+where
+    F: Field,
+{
+    core::iter::successors(Some(F::one()), move |p| Some(*p * scalar))
+}
+
+/// Evaluation of `Σ powers[i]·values[i]`, i.e. the linear combination of
+/// `values` with the increasing powers of `challenge`.
+#[inline]
+pub fn lc<F>(values: &[F], challenge: F) -> F
+where
+    F: Field,
+{
+    values
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, value| acc * challenge + value)
+}
+
+/// Macro to quickly label polynomials, with an optional degree bound.
+#[macro_export]
+macro_rules! label_polynomial {
+    ($poly:expr) => {
+        ark_poly_commit::LabeledPolynomial::new(
+            stringify!($poly).to_owned(),
+            $poly.clone(),
+            None,
+            None,
+        )
+    };
+    ($poly:expr, $bound:expr) => {
+        ark_poly_commit::LabeledPolynomial::new(
+            stringify!($poly).to_owned(),
+            $poly.clone(),
+            $bound,
+            None,
+        )
+    };
+}
+
+/// Macro to quickly label polynomial commitments, with an optional degree
+/// bound.
+#[macro_export]
+macro_rules! label_commitment {
+    ($comm:expr) => {
+        ark_poly_commit::LabeledCommitment::new(
+            stringify!($comm).to_owned(),
+            $comm.clone(),
+            None,
+        )
+    };
+    ($comm:expr, $bound:expr) => {
+        ark_poly_commit::LabeledCommitment::new(
+            stringify!($comm).to_owned(),
+            $comm.clone(),
+            $bound,
+        )
+    };
+}
+
+/// Macro to quickly label evaluations.
+#[macro_export]
+macro_rules! label_eval {
+    ($eval:expr) => {
+        (stringify!($eval).to_owned(), $eval)
+    };
+}
@@ -21,5 +21,5 @@ pub enum WireData {
     Left(usize),
     Right(usize),
     Output(usize),
-
+    Fourth(usize),
 }
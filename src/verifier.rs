@@ -0,0 +1,128 @@
+//! Verifier-side of the PLONK Proving System
+
+use crate::{
+    commitment::HomomorphicCommitment,
+    constraint_system::CircuitBuilder,
+    error::Error,
+    proof_system::{pi::PublicInputs, PlonkVerifierKey, Proof},
+    transcript::TranscriptProtocol,
+};
+use ark_ec::TEModelParameters;
+use ark_ff::PrimeField;
+use core::marker::PhantomData;
+use merlin::Transcript;
+
+/// Abstraction structure designed verify [`Proof`]s.
+pub struct Verifier<F, P, PC, T = Transcript>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+    PC: HomomorphicCommitment<F>,
+    T: TranscriptProtocol + Clone,
+{
+    /// VerificationKey which is used to verify a specific PLONK circuit
+    pub verifier_key: Option<PlonkVerifierKey<F, PC>>,
+
+    /// Circuit Description
+    pub(crate) cs: CircuitBuilder<F, P>,
+
+    /// Store the messages exchanged during the preprocessing stage.
+    ///
+    /// This is copied each time, we make a proof, so that we can use the same
+    /// verifier to verify multiple proofs from the same circuit. If this is
+    /// not copied, then the verification procedure will modify the
+    /// transcript, making it unusable for future proofs.
+    pub preprocessed_transcript: T,
+}
+
+impl<F, P, PC, T> Verifier<F, P, PC, T>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+    PC: HomomorphicCommitment<F>,
+    T: TranscriptProtocol + Clone,
+{
+    /// Creates a new `Verifier` instance.
+    pub fn new(label: &'static [u8]) -> Self {
+        Self {
+            verifier_key: None,
+            cs: CircuitBuilder::new(),
+            preprocessed_transcript: T::new(label),
+        }
+    }
+
+    /// Creates a new `Verifier` instance with some expected size.
+    pub fn with_expected_size(label: &'static [u8], size: usize) -> Self {
+        Self {
+            verifier_key: None,
+            cs: CircuitBuilder::with_expected_size(size),
+            preprocessed_transcript: T::new(label),
+        }
+    }
+
+    /// Returns the smallest power of two needed for the curcuit.
+    pub fn circuit_bound(&self) -> usize {
+        self.cs.circuit_bound()
+    }
+
+    /// Returns a mutable copy of the underlying composer.
+    pub fn mut_cs(&mut self) -> &mut CircuitBuilder<F, P> {
+        &mut self.cs
+    }
+
+    /// Preprocess a circuit to obtain a [`PlonkVerifierKey`] and a
+    /// circuit descriptor so that the `Verifier` instance can verify
+    /// [`Proof`]s for this circuit descriptor instance.
+    pub fn preprocess(
+        &mut self,
+        commit_key: &PC::CommitterKey,
+    ) -> Result<(), Error> {
+        let vk = self.cs.preprocess_verifier(
+            commit_key,
+            &mut self.preprocessed_transcript,
+            PhantomData::<PC>,
+        )?;
+
+        self.verifier_key = Some(vk);
+        Ok(())
+    }
+
+    /// Keys the [`Transcript`] with additional seed information
+    /// Wrapper around [`Transcript::append_message`].
+    ///
+    /// [`Transcript`]: merlin::Transcript
+    /// [`Transcript::append_message`]: merlin::Transcript::append_message
+    pub fn key_transcript(&mut self, label: &'static [u8], message: &[u8]) {
+        self.preprocessed_transcript.append_message(label, message);
+    }
+
+    /// Verifies a [`Proof`] using `pc_verifier_key` and `public_inputs`.
+    pub fn verify(
+        &self,
+        proof: &Proof<F, PC>,
+        pc_verifier_key: &PC::VerifierKey,
+        public_inputs: &PublicInputs<F>,
+    ) -> Result<(), Error> {
+        proof.verify(
+            self.verifier_key
+                .as_ref()
+                .ok_or(Error::CircuitInputsNotFound)?,
+            &mut self.preprocessed_transcript.clone(),
+            pc_verifier_key,
+            public_inputs,
+        )
+    }
+}
+
+impl<F, P, PC, T> Default for Verifier<F, P, PC, T>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+    PC: HomomorphicCommitment<F>,
+    T: TranscriptProtocol + Clone,
+{
+    #[inline]
+    fn default() -> Verifier<F, P, PC, T> {
+        Verifier::new(b"plonk")
+    }
+}
@@ -0,0 +1,158 @@
+//! Runs the generated Solidity verifier in an in-process EVM.
+//!
+//! The contract is compiled with `solc`. When it is not on `PATH` the test
+//! returns early instead of failing, the calldata layout being covered by the
+//! unit tests of `pr::solidity`.
+
+use ark_bn254::{Bn254, Fr};
+use ark_ed_on_bn254::EdwardsParameters;
+use ark_poly_commit::PolynomialCommitment;
+use pr::{
+    commitment::KZG10, constraint_system::CircuitBuilder, prover::Prover,
+    solidity::SolidityVerifier, transcript::KeccakTranscript,
+    verifier::Verifier,
+};
+use revm::{
+    db::{CacheDB, EmptyDB},
+    primitives::{address, ExecutionResult, Output, TransactTo, TxKind, U256},
+    Evm,
+};
+use std::process::Command;
+
+type PC = KZG10<Bn254>;
+
+/// `a * b = c` with `c` public, plus a lookup of `(a, b, c)`.
+fn mul_circuit(cs: &mut CircuitBuilder<Fr, EdwardsParameters>, a: u64, b: u64) {
+    let a_var = cs.add_input(Fr::from(a));
+    let b_var = cs.add_input(Fr::from(b));
+    let c_var = cs.arithmetic_gate(|gate| {
+        gate.witness(a_var, b_var, None).mul(Fr::from(1u64))
+    });
+    cs.constrain_to_constant(c_var, Fr::from(0u64), Some(-Fr::from(a * b)));
+
+    cs.lookup_table_mut().insert_row(
+        Fr::from(a),
+        Fr::from(b),
+        Fr::from(a * b),
+        Fr::from(0u64),
+    );
+    cs.lookup_gate(a_var, b_var, c_var, None, None);
+}
+
+/// Whether `solc` can be run.
+fn solc_available() -> bool {
+    Command::new("solc").arg("--version").output().is_ok()
+}
+
+/// Compiles `source` and returns the creation bytecode of `name`.
+fn solc(source: &str, name: &str) -> Vec<u8> {
+    let dir = tempdir::TempDir::new("plonk-solidity").unwrap();
+    let path = dir.path().join(format!("{}.sol", name));
+    std::fs::write(&path, source).unwrap();
+    let output = Command::new("solc")
+        .args(["--optimize", "--via-ir", "--combined-json", "bin"])
+        .arg(&path)
+        .output()
+        .expect("solc is not on PATH");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let bin = json["contracts"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .find(|(key, _)| key.ends_with(&format!(":{}", name)))
+        .and_then(|(_, contract)| contract["bin"].as_str())
+        .unwrap()
+        .to_owned();
+    (0..bin.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&bin[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Executes a transaction and returns its output, `None` if it reverted.
+fn transact(
+    db: &mut CacheDB<EmptyDB>,
+    to: TxKind,
+    data: Vec<u8>,
+) -> Option<Output> {
+    let mut evm = Evm::builder()
+        .with_db(db)
+        .modify_cfg_env(|cfg| cfg.limit_contract_code_size = Some(usize::MAX))
+        .modify_tx_env(|tx| {
+            tx.caller = address!("1000000000000000000000000000000000000000");
+            tx.transact_to = to;
+            tx.data = data.into();
+            tx.gas_limit = 30_000_000;
+            tx.gas_price = U256::ZERO;
+        })
+        .build();
+    match evm.transact_commit().unwrap() {
+        ExecutionResult::Success { output, .. } => Some(output),
+        _ => None,
+    }
+}
+
+#[test]
+fn generated_verifier_accepts_proof_and_rejects_tampered_one() {
+    if !solc_available() {
+        eprintln!("skipping: solc is not on PATH");
+        return;
+    }
+    let rng = &mut ark_std::test_rng();
+
+    let mut prover =
+        Prover::<Fr, EdwardsParameters, PC, KeccakTranscript>::new(b"mul");
+    mul_circuit(prover.mut_cs(), 3, 5);
+    let n = prover.circuit_bound();
+    let pp = PC::setup(n, None, rng).unwrap();
+    let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+    let proof = prover.prove(&ck).unwrap();
+
+    let mut verifier =
+        Verifier::<Fr, EdwardsParameters, PC, KeccakTranscript>::new(b"mul");
+    mul_circuit(verifier.mut_cs(), 3, 5);
+    let pi = verifier.mut_cs().get_pi().clone();
+    verifier.preprocess(&ck).unwrap();
+    verifier.verify(&proof, &vk, &pi).unwrap();
+
+    let positions = pi.get_pos().copied().collect::<Vec<_>>();
+    let values = pi.get_vals().copied().collect::<Vec<_>>();
+    let contract = SolidityVerifier::<Bn254>::new(
+        verifier.verifier_key.as_ref().unwrap(),
+        &vk,
+        &verifier.preprocessed_transcript,
+        &positions,
+    )
+    .generate("PlonkVerifier")
+    .unwrap();
+
+    let mut db = CacheDB::new(EmptyDB::default());
+    let address = match transact(
+        &mut db,
+        TransactTo::Create,
+        solc(&contract, "PlonkVerifier"),
+    ) {
+        Some(Output::Create(_, Some(address))) => address,
+        other => panic!("deployment failed: {:?}", other),
+    };
+
+    let calldata =
+        SolidityVerifier::<Bn254>::encode_calldata(&proof, &values).unwrap();
+    let accepted = |calldata: Vec<u8>, db: &mut CacheDB<EmptyDB>| {
+        let output = transact(db, TxKind::Call(address), calldata);
+        matches!(output, Some(Output::Call(out)) if out.last() == Some(&1))
+    };
+    assert!(accepted(calldata.clone(), &mut db));
+
+    // Flip a bit of the `a` evaluation, the first evaluation word after the
+    // selector, the two offsets, the length and the 38 point words.
+    let mut tampered = calldata;
+    tampered[4 + (3 + 38) * 32 + 31] ^= 1;
+    assert!(!accepted(tampered, &mut db));
+}
@@ -0,0 +1,259 @@
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::TEModelParameters;
+use ark_ed_on_bls12_381::EdwardsParameters as JubjubParameters;
+use ark_ff::PrimeField;
+use ark_poly_commit::PolynomialCommitment;
+use pr::{
+    commitment::{HomomorphicCommitment, KZG10},
+    constraint_system::CircuitBuilder,
+    error::Error,
+    prover::Prover,
+    transcript::{KeccakTranscript, TranscriptProtocol},
+    verifier::Verifier,
+};
+
+type PC = KZG10<Bls12_381>;
+
+/// `a * b = c` with `c` public, plus a lookup of `(a, b, c)`.
+fn mul_circuit<F, P>(cs: &mut CircuitBuilder<F, P>, a: u64, b: u64, c: u64)
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+{
+    let a_var = cs.add_input(F::from(a));
+    let b_var = cs.add_input(F::from(b));
+    let c_var = cs
+        .arithmetic_gate(|gate| gate.witness(a_var, b_var, None).mul(F::one()));
+    cs.constrain_to_constant(c_var, F::zero(), Some(-F::from(c)));
+
+    cs.lookup_table_mut().insert_row(
+        F::from(a),
+        F::from(b),
+        F::from(a * b),
+        F::zero(),
+    );
+    cs.lookup_gate(a_var, b_var, c_var, None, None);
+}
+
+/// Proves `mul_circuit(a, b, c)` and verifies the proof against
+/// `mul_circuit(a, b, c_public)`'s public inputs.
+fn prove_and_verify<T>(
+    a: u64,
+    b: u64,
+    c: u64,
+    c_public: u64,
+) -> Result<(), Error>
+where
+    T: TranscriptProtocol + Clone,
+{
+    let rng = &mut ark_std::test_rng();
+
+    let mut prover = Prover::<Fr, JubjubParameters, PC, T>::new(b"mul");
+    mul_circuit(prover.mut_cs(), a, b, c);
+    let n = prover.circuit_bound();
+    let pp = PC::setup(n, None, rng).unwrap();
+    let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+
+    let proof = prover.prove(&ck)?;
+
+    let mut verifier = Verifier::<Fr, JubjubParameters, PC, T>::new(b"mul");
+    mul_circuit(verifier.mut_cs(), a, b, c_public);
+    let pi = verifier.mut_cs().get_pi().clone();
+    verifier.preprocess(&ck)?;
+    verifier.verify(&proof, &vk, &pi)
+}
+
+#[test]
+fn proof_verifies() {
+    assert_eq!(prove_and_verify::<merlin::Transcript>(3, 5, 15, 15), Ok(()));
+}
+
+#[test]
+fn proof_verifies_with_keccak_transcript() {
+    assert_eq!(prove_and_verify::<KeccakTranscript>(3, 5, 15, 15), Ok(()));
+}
+
+#[test]
+fn proof_with_wrong_public_input_fails() {
+    assert_eq!(
+        prove_and_verify::<merlin::Transcript>(3, 5, 15, 16),
+        Err(Error::ProofVerificationError)
+    );
+}
+
+#[test]
+fn unsatisfied_circuit_fails() {
+    assert_eq!(
+        prove_and_verify::<merlin::Transcript>(3, 5, 16, 16),
+        Err(Error::ProofVerificationError)
+    );
+}
+
+/// Proves `mul_circuit(3, 5, 15)` on the curve given by `F`, `P` and `PC`,
+/// swaps the two opening proofs if `tamper` is set, and verifies the proof
+/// against `mul_circuit(3, 5, c_public)`'s public inputs.
+fn prove_and_verify_on<F, P, PC>(
+    c_public: u64,
+    tamper: bool,
+) -> Result<(), Error>
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+    PC: HomomorphicCommitment<F>,
+{
+    let rng = &mut ark_std::test_rng();
+
+    let mut prover = Prover::<F, P, PC>::new(b"mul");
+    mul_circuit(prover.mut_cs(), 3, 5, 15);
+    let n = prover.circuit_bound();
+    let pp = PC::setup(n, None, rng).unwrap();
+    let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+    let mut proof = prover.prove(&ck)?;
+    if tamper {
+        std::mem::swap(&mut proof.aw_opening, &mut proof.saw_opening);
+    }
+
+    let mut verifier = Verifier::<F, P, PC>::new(b"mul");
+    mul_circuit(verifier.mut_cs(), 3, 5, c_public);
+    let pi = verifier.mut_cs().get_pi().clone();
+    verifier.preprocess(&ck)?;
+    verifier.verify(&proof, &vk, &pi)
+}
+
+fn mul_circuit_verifies<F, P, PC>()
+where
+    F: PrimeField,
+    P: TEModelParameters<BaseField = F>,
+    PC: HomomorphicCommitment<F>,
+{
+    assert_eq!(prove_and_verify_on::<F, P, PC>(15, false), Ok(()));
+}
+
+#[test]
+fn proof_verifies_on_every_pairing_curve() {
+    pr::for_each_curve!(mul_circuit_verifies);
+}
+
+#[test]
+fn proof_verifies_with_inner_product_argument() {
+    use pr::curves::{pallas, vesta};
+    mul_circuit_verifies::<pallas::F, pallas::P, pallas::PC>();
+    mul_circuit_verifies::<vesta::F, vesta::P, vesta::PC>();
+}
+
+#[test]
+fn inner_product_argument_rejects_bad_proofs() {
+    use pr::curves::{pallas, vesta};
+    assert_eq!(
+        prove_and_verify_on::<pallas::F, pallas::P, pallas::PC>(16, false),
+        Err(Error::ProofVerificationError)
+    );
+    assert_eq!(
+        prove_and_verify_on::<pallas::F, pallas::P, pallas::PC>(15, true),
+        Err(Error::ProofVerificationError)
+    );
+    assert_eq!(
+        prove_and_verify_on::<vesta::F, vesta::P, vesta::PC>(15, true),
+        Err(Error::ProofVerificationError)
+    );
+}
+
+/// `a * b = c` with `c` public, `a` and `b` synthesized from `values`.
+fn mul_circuit_with_witness(
+    cs: &mut CircuitBuilder<Fr, JubjubParameters>,
+    values: Option<(u64, u64)>,
+    c: u64,
+) {
+    let a = cs.add_witness(values.map(|(a, _)| Fr::from(a)));
+    let b = cs.add_witness(values.map(|(_, b)| Fr::from(b)));
+    let product =
+        cs.arithmetic_gate(|gate| gate.witness(a, b, None).mul(Fr::from(1u64)));
+    cs.constrain_to_constant(product, Fr::from(0u64), Some(-Fr::from(c)));
+}
+
+#[test]
+fn witness_free_synthesis_gives_the_same_keys() {
+    let rng = &mut ark_std::test_rng();
+
+    let mut prover = Prover::<Fr, JubjubParameters, PC>::new(b"mul");
+    mul_circuit_with_witness(prover.mut_cs(), Some((3, 5)), 15);
+    let n = prover.circuit_bound();
+    let pp = PC::setup(n, None, rng).unwrap();
+    let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+    let proof = prover.prove(&ck).unwrap();
+
+    let mut verifier = Verifier::<Fr, JubjubParameters, PC>::new(b"mul");
+    mul_circuit_with_witness(verifier.mut_cs(), None, 15);
+    let pi = verifier.mut_cs().get_pi().clone();
+    verifier.preprocess(&ck).unwrap();
+    assert_eq!(verifier.verify(&proof, &vk, &pi), Ok(()));
+
+    let mut prover = Prover::<Fr, JubjubParameters, PC>::new(b"mul");
+    mul_circuit_with_witness(prover.mut_cs(), None, 15);
+    assert!(matches!(prover.prove(&ck), Err(Error::MissingWitness(_))));
+}
+
+/// `y = x^5 + 3w^5 + d^5 + 2x + 3` on a single high degree gate, with `y`
+/// public, next to a lookup row whose `q_arith` is zero.
+fn power_circuit(
+    cs: &mut CircuitBuilder<Fr, JubjubParameters>,
+    (x, w, d): (u64, u64, u64),
+    y: u64,
+) {
+    let x = cs.add_input(Fr::from(x));
+    let w = cs.add_input(Fr::from(w));
+    let d = cs.add_input(Fr::from(d));
+    let out = cs.arithmetic_gate(|gate| {
+        gate.witness(x, w, None)
+            .fan_in_3(Fr::from(0u64), d)
+            .high_degree(Fr::from(1u64), Fr::from(3u64), Fr::from(1u64))
+            .add(Fr::from(2u64), Fr::from(0u64))
+            .constant(Fr::from(3u64))
+    });
+    cs.constrain_to_constant(out, Fr::from(0u64), Some(-Fr::from(y)));
+
+    cs.lookup_table_mut().insert_row(
+        Fr::from(9u64),
+        Fr::from(8u64),
+        Fr::from(7u64),
+        Fr::from(0u64),
+    );
+    let a = cs.add_input(Fr::from(9u64));
+    let b = cs.add_input(Fr::from(8u64));
+    let c = cs.add_input(Fr::from(7u64));
+    cs.lookup_gate(a, b, c, None, None);
+}
+
+fn prove_power(
+    values: (u64, u64, u64),
+    y: u64,
+    y_public: u64,
+) -> Result<(), Error> {
+    let rng = &mut ark_std::test_rng();
+
+    let mut prover = Prover::<Fr, JubjubParameters, PC>::new(b"power");
+    power_circuit(prover.mut_cs(), values, y);
+    let n = prover.circuit_bound();
+    let pp = PC::setup(n, None, rng).unwrap();
+    let (ck, vk) = PC::trim(&pp, n, 0, None).unwrap();
+    let proof = prover.prove(&ck)?;
+
+    let mut verifier = Verifier::<Fr, JubjubParameters, PC>::new(b"power");
+    power_circuit(verifier.mut_cs(), (0, 0, 0), y_public);
+    let pi = verifier.mut_cs().get_pi().clone();
+    verifier.preprocess(&ck)?;
+    verifier.verify(&proof, &vk, &pi)
+}
+
+#[test]
+fn high_degree_gate_proof_verifies() {
+    assert_eq!(prove_power((2, 3, 1), 769, 769), Ok(()));
+    assert_eq!(
+        prove_power((2, 3, 1), 769, 770),
+        Err(Error::ProofVerificationError)
+    );
+    assert_eq!(
+        prove_power((2, 3, 2), 769, 769),
+        Err(Error::ProofVerificationError)
+    );
+}